@@ -1,6 +1,10 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 
-use primes::{PrimeSet, Sieve, TrialDivision};
+use primes::small::{factors_u32, is_prime_u32};
+use primes::{
+    factors, factors_with, is_prime, is_prime_u64, miller_rabin, Bpsw, PrimalityTest, PrimeSet,
+    Sieve, StagedMillerRabin, TrialDivision, WitnessSet,
+};
 
 fn bench_primes(c: &mut Criterion) {
     let mut sizes: Vec<u64> = Vec::new();
@@ -35,5 +39,93 @@ fn bench_primes(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_primes);
+// Inputs for the factoring and primality benchmarks, labelled by their shape
+const NUMBERS: [(&str, u64); 6] = [
+    ("small", 9_973),
+    (
+        "smooth",
+        2 * 3 * 5 * 7 * 11 * 13 * 17 * 19 * 23 * 29 * 31 * 37,
+    ),
+    ("prime_2^32", 4_294_967_291),
+    ("semiprime_2^40", 1_048_573 * 1_048_583),
+    ("prime_2^64", 18_446_744_073_709_551_557),
+    ("semiprime_2^64", 4_294_967_291 * 4_294_967_279),
+];
+
+fn bench_factors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("factors");
+    // Trial division to the square root of a 64-bit number takes seconds, so plain trial division
+    // only gets the inputs below 2^48, and even `factors_with` skips the large semiprime
+    for &(name, n) in NUMBERS.iter().filter(|&&(_, n)| n < 1 << 48) {
+        group.bench_with_input(BenchmarkId::new("factors", name), &n, |b, &n| {
+            b.iter(|| black_box(factors(n)))
+        });
+        let mut pset = Sieve::new();
+        pset.find(1 << 24);
+        group.bench_with_input(BenchmarkId::new("prime_factors", name), &n, |b, &n| {
+            b.iter(|| black_box(pset.prime_factors(n)))
+        });
+    }
+    for &(name, n) in NUMBERS
+        .iter()
+        .filter(|&&(name, _)| name != "semiprime_2^64")
+    {
+        group.bench_with_input(BenchmarkId::new("factors_with_bpsw", name), &n, |b, &n| {
+            b.iter(|| black_box(factors_with(n, &Bpsw)))
+        });
+    }
+    for &(name, n) in NUMBERS.iter().filter(|&&(_, n)| n <= u32::MAX as u64) {
+        group.bench_with_input(BenchmarkId::new("factors_u32", name), &n, |b, &n| {
+            b.iter(|| black_box(factors_u32(n as u32)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_is_prime(c: &mut Criterion) {
+    let mut group = c.benchmark_group("is_prime");
+    for &(name, n) in NUMBERS.iter() {
+        group.bench_with_input(BenchmarkId::new("is_prime", name), &n, |b, &n| {
+            b.iter(|| black_box(is_prime(n)))
+        });
+        group.bench_with_input(BenchmarkId::new("is_prime_u64", name), &n, |b, &n| {
+            b.iter(|| black_box(is_prime_u64(n)))
+        });
+        group.bench_with_input(BenchmarkId::new("miller_rabin", name), &n, |b, &n| {
+            b.iter(|| black_box(miller_rabin(n, WitnessSet::Sinclair)))
+        });
+        if n <= u32::MAX as u64 {
+            group.bench_with_input(BenchmarkId::new("is_prime_u32", name), &n, |b, &n| {
+                b.iter(|| black_box(is_prime_u32(n as u32)))
+            });
+        }
+    }
+    group.finish();
+}
+
+// A run of odd candidates, nearly all composite, as a random prime search would see
+fn bench_candidates(c: &mut Criterion) {
+    let mut group = c.benchmark_group("candidates");
+    let start = (1 << 62) + 1;
+    group.throughput(Throughput::Elements(10_000));
+    group.bench_function("is_prime_u64", |b| {
+        b.iter(|| (0..10_000).filter(|&i| is_prime_u64(start + 2 * i)).count())
+    });
+    group.bench_function("staged_miller_rabin", |b| {
+        b.iter(|| {
+            (0..10_000)
+                .filter(|&i| StagedMillerRabin.test(start + 2 * i) == primes::Primality::Prime)
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_primes,
+    bench_factors,
+    bench_is_prime,
+    bench_candidates
+);
 criterion_main!(benches);
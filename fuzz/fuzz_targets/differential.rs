@@ -0,0 +1,169 @@
+//! Cross-checks `factors`, `factors_with`, `factors_with_limit`, `is_prime_u64` and
+//! `totient_range` against slow, independent oracles.
+//!
+//! Run with `cargo fuzz run differential` from the repository root.
+//!
+//! The first input byte picks how the rest is turned into a `u64`: either used as-is, or as a
+//! small offset from a boundary where overflow bugs tend to live (`u64::MAX`, powers of two, and
+//! squares of primes around 2^32). Factoring by trial division takes seconds for a number with
+//! two prime factors near 2^32, so `factors` is only called below 2^40, and above that
+//! `factors_with` is only called once trial division up to 2^20 has left a prime cofactor.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use primes::{factors, factors_with, factors_with_limit, is_prime_u64, totient_range, Bpsw};
+
+// Plain trial division is only affordable below this
+const TRIAL_DIVISION_LIMIT: u64 = 1 << 40;
+
+// Trial division up to here finishes off any number below `TRIAL_DIVISION_LIMIT`
+const SMALL_FACTOR_LIMIT: u64 = 1 << 20;
+
+// Primes on either side of 2^32, whose squares sit just below and just past 2^64
+const PRIMES_NEAR_2_32: [u64; 4] = [4_294_967_279, 4_294_967_291, 4_294_967_311, 4_294_967_357];
+
+fn input_value(data: &[u8]) -> Option<u64> {
+    let (&mode, rest) = data.split_first()?;
+    let mut bytes = [0u8; 8];
+    let len = rest.len().min(8);
+    bytes[..len].copy_from_slice(&rest[..len]);
+    let raw = u64::from_le_bytes(bytes);
+    let offset = raw % 4096;
+    Some(match mode % 5 {
+        0 => raw,
+        1 => u64::MAX - offset,
+        2 => (1u64 << (raw % 64))
+            .wrapping_add(offset % 64)
+            .wrapping_sub(32),
+        3 => {
+            let p = PRIMES_NEAR_2_32[(raw % 4) as usize];
+            p.wrapping_mul(p).wrapping_add(offset % 64).wrapping_sub(32)
+        }
+        _ => raw % TRIAL_DIVISION_LIMIT,
+    })
+}
+
+// Modular arithmetic written out separately from the crate's, so a bug there can't hide itself
+fn oracle_mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+fn oracle_pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = oracle_mul_mod(result, base, m);
+        }
+        base = oracle_mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+// Miller-Rabin with the first 12 primes as witnesses, which is deterministic for every u64
+fn oracle_is_prime(n: u64) -> bool {
+    if n < TRIAL_DIVISION_LIMIT {
+        return n >= 2
+            && (2..)
+                .take_while(|d| d * d <= n)
+                .all(|d| !n.is_multiple_of(d));
+    }
+    if n.is_multiple_of(2) {
+        return false;
+    }
+    let (mut d, mut s) = (n - 1, 0);
+    while d % 2 == 0 {
+        d /= 2;
+        s += 1;
+    }
+    [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37]
+        .iter()
+        .all(|&a| {
+            let mut x = oracle_pow_mod(a, d, n);
+            if x == 1 || x == n - 1 {
+                return true;
+            }
+            for _ in 1..s {
+                x = oracle_mul_mod(x, x, n);
+                if x == n - 1 {
+                    return true;
+                }
+            }
+            false
+        })
+}
+
+// Check that `fs` is `n` split into primes, in increasing order, except for `cofactor`
+fn check_factorization(name: &str, n: u64, fs: &[u64], cofactor: u64) {
+    if n <= 1 {
+        assert!(fs.is_empty(), "{}({}) = {:?}", name, n, fs);
+        return;
+    }
+    assert!(
+        fs.windows(2).all(|w| w[0] <= w[1]),
+        "{}({}) = {:?}",
+        name,
+        n,
+        fs
+    );
+    let product = fs.iter().try_fold(cofactor, |acc, &p| acc.checked_mul(p));
+    assert_eq!(product, Some(n), "{}({}) = {:?}", name, n, fs);
+    for &p in fs {
+        assert!(oracle_is_prime(p), "{}({}) has composite {}", name, n, p);
+    }
+}
+
+fn check_factors(n: u64) {
+    if n < TRIAL_DIVISION_LIMIT {
+        check_factorization("factors", n, &factors(n), 1);
+        return;
+    }
+    let (small, cofactor) = factors_with_limit(n, SMALL_FACTOR_LIMIT);
+    check_factorization("factors_with_limit", n, &small, cofactor);
+    assert!(
+        small.iter().all(|&p| p <= SMALL_FACTOR_LIMIT),
+        "factors_with_limit({}) = {:?}",
+        n,
+        small
+    );
+    if cofactor == 1 || oracle_is_prime(cofactor) {
+        // Finding `small` is all the trial division `factors_with` needs to do
+        check_factorization("factors_with", n, &factors_with(n, &Bpsw), 1);
+    }
+}
+
+fn check_totient(n: u64) {
+    if n == 0 || n >= TRIAL_DIVISION_LIMIT {
+        return;
+    }
+    // φ(n) = n ∏ (1 - 1/p), with the primes found by trial division
+    let mut expected = n;
+    let mut rem = n;
+    let mut d = 2;
+    while d * d <= rem {
+        if rem.is_multiple_of(d) {
+            expected -= expected / d;
+            while rem.is_multiple_of(d) {
+                rem /= d;
+            }
+        }
+        d += 1;
+    }
+    if rem > 1 {
+        expected -= expected / rem;
+    }
+    let got: Vec<(u64, u64)> = totient_range(n, n).collect();
+    assert_eq!(got, vec![(n, expected)]);
+}
+
+fuzz_target!(|data: &[u8]| {
+    let n = match input_value(data) {
+        Some(n) => n,
+        None => return,
+    };
+    assert_eq!(is_prime_u64(n), oracle_is_prime(n), "is_prime_u64({})", n);
+    check_factors(n);
+    check_totient(n);
+});
@@ -0,0 +1,851 @@
+//! Arithmetic functions, evaluated for single numbers or sieved over whole ranges.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::segment::SegmentedPrimes;
+use crate::{
+    factors_with, first_primes, gcd, iroot, is_prime_u64, isqrt, mul_mod, Bpsw, DefaultPrimeSet,
+    Factorization, PrimeSet,
+};
+
+/// Count the distinct prime factors ω(k) of every `k` from 0 to `n`, in a single sieve pass.
+///
+/// Entry `k` of the result is ω(k), with ω(0) = ω(1) = 0. No `u64` has more than 15 distinct
+/// prime factors, so a `u8` per entry is plenty.
+///
+/// ```
+/// use primes::omega_sieve;
+///
+/// assert_eq!(omega_sieve(12), vec![0, 0, 1, 1, 1, 1, 2, 1, 1, 1, 2, 1, 2]);
+/// ```
+pub fn omega_sieve(n: usize) -> Vec<u8> {
+    let mut omega = vec![0u8; n + 1];
+    for p in 2..=n {
+        if omega[p] != 0 {
+            // Already marked by a smaller prime, so not prime
+            continue;
+        }
+        for multiple in (p..=n).step_by(p) {
+            omega[multiple] += 1;
+        }
+    }
+    omega
+}
+
+/// The most values `totient_summatory` will sieve directly; above that, it recurses instead.
+const SUMMATORY_SIEVE_MAX: u64 = 1 << 20;
+
+/// Compute the totient summatory function Φ(x) = φ(1) + φ(2) + ... + φ(x) in roughly `O(x^(2/3))`
+/// time.
+///
+/// φ is sieved directly up to about `x^(2/3)`, and the remaining values of Φ at `x / k` come from
+/// the identity `Σ_{d=1..v} Φ(v / d) = v(v + 1) / 2`. Φ(x) grows like `3x² / π²`, so it is
+/// returned as a `u128`.
+///
+/// The sieve is capped at 2^20 values (8 MiB), past which the time grows linearly in `x` instead.
+/// The values of Φ at `x / k` take another 16 bytes each, about `x / 2^16` bytes at the cap, and
+/// both tables grow as `sqrt(x)` once `x` passes 2^40.
+///
+/// ```
+/// use primes::totient_summatory;
+///
+/// assert_eq!(totient_summatory(10), 32);
+/// assert_eq!(totient_summatory(1_000_000), 303_963_552_392);
+/// ```
+pub fn totient_summatory(x: u64) -> u128 {
+    if x == 0 {
+        return 0;
+    }
+    let root = isqrt(x);
+    let limit = ((x as f64).powf(2.0 / 3.0) as u64)
+        .min(SUMMATORY_SIEVE_MAX)
+        .max(root)
+        .min(x);
+
+    // small[v] is φ(v), then Φ(v) once summed. Φ(limit) fits a u64, since limit < 2^32.
+    let mut small: Vec<u64> = (0..=limit).collect();
+    for p in 2..=limit as usize {
+        if small[p] == p as u64 {
+            // Not reduced by a smaller prime, so p is prime
+            for multiple in (p..=limit as usize).step_by(p) {
+                small[multiple] -= small[multiple] / p as u64;
+            }
+        }
+    }
+    for v in 1..small.len() {
+        small[v] += small[v - 1];
+    }
+
+    // large[k] is Φ(x / k), for the k where x / k > limit
+    let last_k = x / (limit + 1);
+    let mut large = vec![0u128; last_k as usize + 1];
+    for k in (1..=last_k).rev() {
+        let v = x / k;
+        let mut total = v as u128 * (v as u128 + 1) / 2;
+        // Each d up to v / (s + 1) on its own, where v / d > s
+        let s = isqrt(v);
+        for d in 2..=v / (s + 1) {
+            let q = v / d;
+            total -= if q <= limit {
+                small[q as usize] as u128
+            } else {
+                large[(k * d) as usize]
+            };
+        }
+        // The rest grouped by q = v / d <= s, which is at most limit
+        let mut d_hi = v;
+        for q in 1..=s {
+            let d_lo = v / (q + 1);
+            total -= (d_hi - d_lo) as u128 * small[q as usize] as u128;
+            d_hi = d_lo;
+        }
+        large[k as usize] = total;
+    }
+
+    if last_k == 0 {
+        small[x as usize] as u128
+    } else {
+        large[1]
+    }
+}
+
+/// Compute the divisor summatory function D(x) = d(1) + d(2) + ... + d(x), where d(n) counts the
+/// divisors of n, in `O(sqrt(x))` time.
+///
+/// This is Dirichlet's hyperbola method: D(x) counts the lattice points under `ab = x`, which is
+/// twice the count with `a <= sqrt(x)`, less the square counted twice.
+///
+/// ```
+/// use primes::divisor_summatory;
+///
+/// assert_eq!(divisor_summatory(10), 27);
+/// assert_eq!(divisor_summatory(1_000_000_000), 20_877_697_634);
+/// ```
+pub fn divisor_summatory(x: u64) -> u128 {
+    let root = isqrt(x);
+    let below_root: u128 = (1..=root).map(|a| (x / a) as u128).sum();
+    2 * below_root - root as u128 * root as u128
+}
+
+/**
+An iterator over the highly composite numbers: those with more divisors than any smaller
+positive integer. Created by `highly_composite_numbers`.
+
+The exponents in the factorization of a highly composite number never increase from one prime
+to the next, so only those candidates need to be considered. They are generated in batches of
+increasing size, and the iterator ends after the last highly composite number that fits in a
+`u64`.
+**/
+pub struct HighlyComposite {
+    primes: Vec<u64>,
+    // Every candidate up to 'done' has been examined
+    done: u64,
+    record: u64,
+    batch: std::vec::IntoIter<u64>,
+}
+
+/// Iterate over the highly composite numbers, starting from 1.
+///
+/// ```
+/// use primes::highly_composite_numbers;
+///
+/// let hcn: Vec<u64> = highly_composite_numbers().take(10).collect();
+/// assert_eq!(hcn, vec![1, 2, 4, 6, 12, 24, 36, 48, 60, 120]);
+/// ```
+pub fn highly_composite_numbers() -> HighlyComposite {
+    // The product of the first 16 primes doesn't fit in a u64, so these are all we can need
+    let primes = DefaultPrimeSet::new().iter().take(16).collect();
+    HighlyComposite {
+        primes,
+        done: 0,
+        record: 0,
+        batch: Vec::new().into_iter(),
+    }
+}
+
+impl HighlyComposite {
+    // Push every candidate in (lo, hi] with non-increasing exponents onto 'found', along with its
+    // number of divisors
+    fn search(
+        &self,
+        ix: usize,
+        value: u64,
+        divisors: u64,
+        max_exp: u32,
+        (lo, hi): (u64, u64),
+        found: &mut Vec<(u64, u64)>,
+    ) {
+        if value > lo {
+            found.push((value, divisors));
+        }
+        if ix >= self.primes.len() {
+            return;
+        }
+        let p = self.primes[ix];
+        let mut next = value;
+        for e in 1..=max_exp {
+            next = match next.checked_mul(p) {
+                Some(n) if n <= hi => n,
+                _ => break,
+            };
+            self.search(ix + 1, next, divisors * (e as u64 + 1), e, (lo, hi), found);
+        }
+    }
+}
+
+impl Iterator for HighlyComposite {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            if let Some(n) = self.batch.next() {
+                return Some(n);
+            }
+            if self.done == u64::MAX {
+                return None;
+            }
+            let lo = self.done;
+            let hi = lo.saturating_mul(16).max(16);
+
+            let mut found = Vec::new();
+            self.search(0, 1, 1, u32::MAX, (lo, hi), &mut found);
+            found.sort_unstable();
+
+            let mut batch = Vec::new();
+            for (n, divisors) in found {
+                if divisors > self.record {
+                    self.record = divisors;
+                    batch.push(n);
+                }
+            }
+            self.batch = batch.into_iter();
+            self.done = hi;
+        }
+    }
+}
+
+/// Test whether `n` is a practical number: every positive integer up to `n` is a sum of distinct
+/// divisors of `n`.
+///
+/// This uses Stewart and Sierpiński's characterization: with the prime factors of `n` in
+/// increasing order, each prime must be at most one more than the sum of divisors of the product
+/// of the prime powers before it.
+///
+/// ```
+/// use primes::is_practical;
+///
+/// let practical: Vec<u64> = (1..30).filter(|&n| is_practical(n)).collect();
+/// assert_eq!(practical, vec![1, 2, 4, 6, 8, 12, 16, 18, 20, 24, 28]);
+/// ```
+pub fn is_practical(n: u64) -> bool {
+    if n == 0 {
+        return false;
+    }
+    let mut sigma: u128 = 1;
+    for &(p, e) in Factorization::of(n).pairs() {
+        if p as u128 > sigma + 1 {
+            return false;
+        }
+        // Multiply in 1 + p + ... + p^e
+        let mut term: u128 = 1;
+        let mut power: u128 = 1;
+        for _ in 0..e {
+            power *= p as u128;
+            term += power;
+        }
+        sigma *= term;
+    }
+    true
+}
+
+/// Test whether `n` is powerful: every prime that divides `n` divides it at least twice. By
+/// convention 1 is powerful, and 0 is not.
+///
+/// ```
+/// use primes::is_powerful;
+///
+/// assert!(is_powerful(72));
+/// assert!(!is_powerful(12));
+/// ```
+pub fn is_powerful(n: u64) -> bool {
+    n != 0 && Factorization::of(n).pairs().iter().all(|&(_, e)| e >= 2)
+}
+
+/// Test whether `n` is an Achilles number: powerful, but not a perfect power.
+///
+/// ```
+/// use primes::is_achilles;
+///
+/// assert!(is_achilles(72)); // 2^3 * 3^2
+/// assert!(!is_achilles(36)); // 6^2
+/// ```
+pub fn is_achilles(n: u64) -> bool {
+    n > 1 && is_achilles_exponents(Factorization::of(n).pairs().iter().map(|&(_, e)| e))
+}
+
+fn is_achilles_exponents<I: Iterator<Item = u32>>(exponents: I) -> bool {
+    let mut exponent_gcd = 0;
+    for e in exponents {
+        if e < 2 {
+            return false;
+        }
+        exponent_gcd = gcd(exponent_gcd, e as u64);
+    }
+    exponent_gcd == 1
+}
+
+/**
+An iterator over the powerful numbers in increasing order, starting from 1. Created by
+`powerful_numbers`.
+
+Every powerful number can be written uniquely as `a^2 * b^3` with `b` squarefree. For each such
+`b`, the values `a^2 * b^3` form an increasing stream, and these streams are merged with a heap,
+starting a new stream only once `b^3` is the smallest candidate left. The iterator ends at the
+largest powerful number that fits in a `u64`.
+**/
+pub struct PowerfulNumbers {
+    // Entries (a^2 * b^3, a, b)
+    heap: BinaryHeap<Reverse<(u64, u64, u64)>>,
+    next_b: Option<u64>,
+}
+
+/// Iterate over the powerful numbers in increasing order.
+///
+/// ```
+/// use primes::powerful_numbers;
+///
+/// let powerful: Vec<u64> = powerful_numbers().take(8).collect();
+/// assert_eq!(powerful, vec![1, 4, 8, 9, 16, 25, 27, 32]);
+/// ```
+pub fn powerful_numbers() -> PowerfulNumbers {
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((1, 1, 1)));
+    PowerfulNumbers {
+        heap,
+        next_b: Some(2),
+    }
+}
+
+impl PowerfulNumbers {
+    // The next powerful number 'n', along with 'a' and 'b' such that n = a^2 * b^3
+    fn next_with_roots(&mut self) -> Option<(u64, u64, u64)> {
+        while let Some(b) = self.next_b {
+            let cube = match b.checked_pow(3) {
+                Some(cube) => cube,
+                None => {
+                    self.next_b = None;
+                    break;
+                }
+            };
+            match self.heap.peek() {
+                Some(&Reverse((smallest, _, _))) if smallest < cube => break,
+                _ => (),
+            }
+            if Factorization::of(b).pairs().iter().all(|&(_, e)| e == 1) {
+                self.heap.push(Reverse((cube, 1, b)));
+            }
+            self.next_b = Some(b + 1);
+        }
+
+        let Reverse((n, a, b)) = self.heap.pop()?;
+        let next = (a + 1)
+            .checked_mul(a + 1)
+            .and_then(|a2| a2.checked_mul(b * b * b));
+        if let Some(next) = next {
+            self.heap.push(Reverse((next, a + 1, b)));
+        }
+        Some((n, a, b))
+    }
+}
+
+impl Iterator for PowerfulNumbers {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        self.next_with_roots().map(|(n, _, _)| n)
+    }
+}
+
+/// An iterator over the Achilles numbers in increasing order. Created by `achilles_numbers`.
+pub struct AchillesNumbers {
+    powerful: PowerfulNumbers,
+}
+
+/// Iterate over the Achilles numbers in increasing order.
+///
+/// Each candidate comes from `powerful_numbers` as `a^2 * b^3`, so only the small numbers `a` and
+/// `b` need to be factored to rule out perfect powers.
+///
+/// ```
+/// use primes::achilles_numbers;
+///
+/// let achilles: Vec<u64> = achilles_numbers().take(5).collect();
+/// assert_eq!(achilles, vec![72, 108, 200, 288, 392]);
+/// ```
+pub fn achilles_numbers() -> AchillesNumbers {
+    AchillesNumbers {
+        powerful: powerful_numbers(),
+    }
+}
+
+impl Iterator for AchillesNumbers {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            let (n, a, b) = self.powerful.next_with_roots()?;
+            let f = Factorization::of(a).pow(2) * Factorization::of(b).pow(3);
+            if n > 1 && is_achilles_exponents(f.pairs().iter().map(|&(_, e)| e)) {
+                return Some(n);
+            }
+        }
+    }
+}
+
+/// If `n` is a power of a prime, `p^k` with `k >= 1`, return `Some((p, k))`.
+///
+/// Each possible exponent is tried, from `k = 1` up to `log2(n)`, by taking an integer `k`-th root
+/// and testing it with `is_prime_u64`. 1 is not a prime power.
+///
+/// ```
+/// use primes::is_prime_power;
+///
+/// assert_eq!(is_prime_power(7), Some((7, 1)));
+/// assert_eq!(is_prime_power(1 << 40), Some((2, 40)));
+/// assert_eq!(is_prime_power(36), None);
+/// assert_eq!(is_prime_power(1), None);
+/// ```
+pub fn is_prime_power(n: u64) -> Option<(u64, u32)> {
+    if n < 2 {
+        return None;
+    }
+    (1..64 - n.leading_zeros()).find_map(|k| {
+        let root = iroot(n, k);
+        if root.pow(k) == n && is_prime_u64(root) {
+            Some((root, k))
+        } else {
+            None
+        }
+    })
+}
+
+/// The von Mangoldt function Λ(n): `ln(p)` if `n` is a power of the prime `p`, and 0 otherwise.
+///
+/// ```
+/// use primes::mangoldt;
+///
+/// assert_eq!(mangoldt(8), 2f64.ln());
+/// assert_eq!(mangoldt(6), 0.0);
+/// assert_eq!(mangoldt(1), 0.0);
+/// ```
+pub fn mangoldt(n: u64) -> f64 {
+    match is_prime_power(n) {
+        Some((p, _)) => (p as f64).ln(),
+        None => 0.0,
+    }
+}
+
+/// The largest number no greater than `limit` whose prime factors all come from `primes`, or
+/// `None` if `limit == 0`. With no usable primes, this is 1.
+///
+/// This is a depth-first search over the exponents of each prime, largest prime first. The
+/// smallest prime is handled last and in closed form, by taking as high a power of it as fits,
+/// and the search stops as soon as it hits `limit` exactly. Useful for picking FFT lengths
+/// (`primes = &[2, 3, 5, 7]`) or other sizes that need to factor nicely.
+///
+/// Panics if any of `primes` is less than 2. They are trusted to be prime, although composite
+/// entries only add redundant branches.
+///
+/// ```
+/// use primes::max_below_with_factors;
+///
+/// assert_eq!(max_below_with_factors(1_000, &[2, 3, 5]), Some(1_000));
+/// assert_eq!(max_below_with_factors(1_000_003, &[2, 3, 5, 7]), Some(1_000_000));
+/// assert_eq!(max_below_with_factors(100, &[3, 7]), Some(81));
+/// assert_eq!(max_below_with_factors(100, &[]), Some(1));
+/// ```
+pub fn max_below_with_factors(limit: u64, primes: &[u64]) -> Option<u64> {
+    if limit == 0 {
+        return None;
+    }
+    assert!(primes.iter().all(|&p| p >= 2), "primes must be at least 2");
+    let mut primes = primes.to_vec();
+    primes.sort_unstable_by(|a, b| b.cmp(a));
+    primes.dedup();
+
+    // `primes` is decreasing, so the last prime is the smallest
+    fn search(primes: &[u64], current: u64, limit: u64, best: &mut u64) {
+        match *primes {
+            [] => *best = (*best).max(current),
+            [p] => {
+                let mut value = current;
+                while value <= limit / p {
+                    value *= p;
+                }
+                *best = (*best).max(value);
+            }
+            [q, ref others @ ..] => {
+                let mut value = current;
+                loop {
+                    search(others, value, limit, best);
+                    if *best == limit || value > limit / q {
+                        return;
+                    }
+                    value *= q;
+                }
+            }
+        }
+    }
+
+    let mut best = 1;
+    search(&primes, 1, limit, &mut best);
+    Some(best)
+}
+
+/// The Möbius function μ(n): 0 if a square greater than 1 divides `n`, and otherwise 1 or -1 as
+/// `n` has an even or odd number of prime factors. See `mobius_range` for whole windows.
+///
+/// Panics if `n == 0`.
+///
+/// ```
+/// use primes::mobius;
+///
+/// assert_eq!(mobius(1), 1);
+/// assert_eq!(mobius(30), -1);
+/// assert_eq!(mobius(12), 0);
+/// ```
+pub fn mobius(n: u64) -> i8 {
+    let f = Factorization::of(n);
+    if f.pairs().iter().any(|&(_, e)| e > 1) {
+        0
+    } else if f.pairs().len().is_multiple_of(2) {
+        1
+    } else {
+        -1
+    }
+}
+
+/// The Liouville function λ(n): 1 or -1 as `n` has an even or odd number of prime factors,
+/// counted with multiplicity. See `liouville_range` for whole windows.
+///
+/// Panics if `n == 0`.
+///
+/// ```
+/// use primes::liouville;
+///
+/// assert_eq!(liouville(1), 1);
+/// assert_eq!(liouville(12), -1);
+/// ```
+pub fn liouville(n: u64) -> i8 {
+    let big_omega: u32 = Factorization::of(n).pairs().iter().map(|&(_, e)| e).sum();
+    if big_omega.is_multiple_of(2) {
+        1
+    } else {
+        -1
+    }
+}
+
+/// An iterator over the prime powers `p^k`, `k >= 1`, in increasing order. Created by
+/// `prime_powers`.
+pub struct PrimePowers {
+    // The primes themselves, in order
+    primes: SegmentedPrimes,
+    next_prime: Option<u64>,
+    // The primes whose squares haven't been reached yet, in order
+    bases: SegmentedPrimes,
+    next_base: Option<u64>,
+    // Entries (p^k, p) with k >= 2, for the primes already taken from `bases`
+    heap: BinaryHeap<Reverse<(u64, u64)>>,
+}
+
+/// Iterate over the prime powers `p^k` with `k >= 1` in increasing order, up to the largest that
+/// fits in a `u64`.
+///
+/// The primes come straight from a segmented sieve, and each prime `p` starts a stream
+/// `p^2, p^3, ...` once `p^2` is reached; the streams are merged with a heap. So the heap only
+/// ever holds the primes up to the square root of the current value.
+///
+/// ```
+/// use primes::prime_powers;
+///
+/// let powers: Vec<u64> = prime_powers().take(10).collect();
+/// assert_eq!(powers, vec![2, 3, 4, 5, 7, 8, 9, 11, 13, 16]);
+/// ```
+pub fn prime_powers() -> PrimePowers {
+    let mut primes = SegmentedPrimes::new(2, u64::MAX);
+    let next_prime = primes.next();
+    let mut bases = SegmentedPrimes::new(2, u32::MAX as u64);
+    let next_base = bases.next();
+    PrimePowers {
+        primes,
+        next_prime,
+        bases,
+        next_base,
+        heap: BinaryHeap::new(),
+    }
+}
+
+impl Iterator for PrimePowers {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let smallest_power = self.heap.peek().map(|&Reverse((n, _))| n);
+        let upcoming = match (self.next_prime, smallest_power) {
+            (Some(p), Some(n)) => p.min(n),
+            (Some(p), None) => p,
+            (None, Some(n)) => n,
+            (None, None) => return None,
+        };
+        if let Some(q) = self.next_base {
+            // Every base is below 2^32, so this can't overflow
+            if q * q <= upcoming {
+                self.heap.push(Reverse((q * q, q)));
+                self.next_base = self.bases.next();
+            }
+        }
+
+        let smallest_power = self.heap.peek().map(|&Reverse((n, _))| n);
+        match (self.next_prime, smallest_power) {
+            (Some(p), Some(n)) if p < n => (),
+            (Some(_), None) => (),
+            _ => {
+                let Reverse((n, p)) = self.heap.pop()?;
+                if let Some(next) = n.checked_mul(p) {
+                    self.heap.push(Reverse((next, p)));
+                }
+                return Some(n);
+            }
+        }
+        let p = self.next_prime?;
+        self.next_prime = self.primes.next();
+        Some(p)
+    }
+}
+
+/// The product of all primes up to and including `x`, often written `x#` (the primorial of `x`).
+///
+/// This outgrows a `u128` once `x` passes 102, so it is returned as a `BigUint`. Primes are
+/// multiplied into a `u64` until it would overflow, and only then into the `BigUint`.
+///
+/// ```
+/// use num_bigint::BigUint;
+/// use primes::product_of_primes_below;
+///
+/// assert_eq!(product_of_primes_below(10), BigUint::from(210u32));
+/// assert_eq!(product_of_primes_below(1), BigUint::from(1u32));
+/// ```
+#[cfg(feature = "bigint")]
+pub fn product_of_primes_below(x: u64) -> num_bigint::BigUint {
+    let mut product = num_bigint::BigUint::from(1u32);
+    let mut chunk = 1u64;
+    for p in SegmentedPrimes::new(2, x) {
+        chunk = match chunk.checked_mul(p) {
+            Some(next) => next,
+            None => {
+                product *= chunk;
+                p
+            }
+        };
+    }
+    product * chunk
+}
+
+/// The natural logarithm of the product of all primes up to and including `x`, which is the
+/// Chebyshev function θ(x). It grows like `x`, so unlike the product itself it fits in an `f64`
+/// for any `x`.
+///
+/// ```
+/// use primes::log_product_of_primes_below;
+///
+/// assert!((log_product_of_primes_below(10) - 210f64.ln()).abs() < 1e-12);
+/// assert_eq!(log_product_of_primes_below(1), 0.0);
+/// ```
+pub fn log_product_of_primes_below(x: u64) -> f64 {
+    SegmentedPrimes::new(2, x).map(|p| (p as f64).ln()).sum()
+}
+
+/// The sum of `1 / p` over all primes `p` up to and including `x`.
+///
+/// By Mertens' second theorem this is close to `ln(ln(x)) + M`, where `M ≈ 0.2615` is the
+/// Meissel-Mertens constant.
+///
+/// ```
+/// use primes::sum_reciprocal_primes_below;
+///
+/// let expected = 1.0 / 2.0 + 1.0 / 3.0 + 1.0 / 5.0 + 1.0 / 7.0;
+/// assert!((sum_reciprocal_primes_below(10) - expected).abs() < 1e-12);
+/// let mertens = sum_reciprocal_primes_below(1_000_000) - (1e6f64).ln().ln();
+/// assert!((mertens - 0.2615).abs() < 1e-4);
+/// ```
+pub fn sum_reciprocal_primes_below(x: u64) -> f64 {
+    SegmentedPrimes::new(2, x).map(|p| 1.0 / p as f64).sum()
+}
+
+/// The product of `1 - 1 / p` over all primes `p` up to and including `x`, which is the density
+/// of the numbers with no prime factor up to `x`.
+///
+/// By Mertens' third theorem this is close to `e^-γ / ln(x)`, where `γ` is the Euler-Mascheroni
+/// constant.
+///
+/// ```
+/// use primes::prod_one_minus_inv_p_below;
+///
+/// assert!((prod_one_minus_inv_p_below(10) - 8.0 / 35.0).abs() < 1e-12);
+/// let ratio = prod_one_minus_inv_p_below(1_000_000) * (1e6f64).ln();
+/// assert!((ratio - (-0.5772156649f64).exp()).abs() < 1e-4);
+/// ```
+pub fn prod_one_minus_inv_p_below(x: u64) -> f64 {
+    SegmentedPrimes::new(2, x)
+        .map(|p| 1.0 - 1.0 / p as f64)
+        .product()
+}
+
+// The Riemann zeta function ζ(s) for real `s > 1`, by Euler-Maclaurin summation from 10 terms
+fn zeta(s: f64) -> f64 {
+    // B_2k / (2k)! for k = 1..=6
+    const COEFFS: [f64; 6] = [
+        1.0 / 12.0,
+        -1.0 / 720.0,
+        1.0 / 30_240.0,
+        -1.0 / 1_209_600.0,
+        1.0 / 47_900_160.0,
+        -691.0 / 1_307_674_368_000.0,
+    ];
+    const TERMS: f64 = 10.0;
+    let head: f64 = (1..TERMS as u32).map(|n| (n as f64).powf(-s)).sum();
+    let mut tail = TERMS.powf(1.0 - s) / (s - 1.0) + TERMS.powf(-s) / 2.0;
+    // s (s + 1) ... (s + 2k - 2) * TERMS^(-s - 2k + 1)
+    let mut factor = s * TERMS.powf(-s - 1.0);
+    for (k, &coeff) in COEFFS.iter().enumerate() {
+        tail += coeff * factor;
+        let j = 2.0 * k as f64;
+        factor *= (s + j + 1.0) * (s + j + 2.0) / (TERMS * TERMS);
+    }
+    head + tail
+}
+
+/// The prime zeta function `P(s)`, the sum of `p^-s` over all primes `p`, for real `s > 1`.
+///
+/// The primes below 100 are summed directly. The rest comes from the Möbius inversion of
+/// `ln ζ(s) = Σ P(ks) / k`, applied to the zeta function with those primes' Euler factors removed,
+/// whose series in `k` then shrinks like `101^-ks`; so this is accurate to within a few units in
+/// the last place for any `s > 1`, though `P(s)` itself grows like `ln(1 / (s - 1))` near 1.
+///
+/// Panics if `s <= 1` (or is NaN), where the sum diverges.
+///
+/// ```
+/// use primes::prime_zeta;
+///
+/// assert!((prime_zeta(2.0) - 0.452_247_420_041_065_5).abs() < 1e-14);
+/// assert!((prime_zeta(3.0) - 0.174_762_639_299_443_5).abs() < 1e-14);
+/// // Dominated by its first term for large s
+/// assert!(((prime_zeta(60.0) - 2f64.powi(-60)) / 2f64.powi(-60)).abs() < 1e-9);
+/// ```
+pub fn prime_zeta(s: f64) -> f64 {
+    assert!(s > 1.0, "the prime zeta function diverges for s <= 1");
+    const SMALL: [u64; 25] = first_primes::<25>();
+    let direct: f64 = SMALL.iter().map(|&p| (p as f64).powf(-s)).sum();
+
+    // Past this, 101^-ks is too small to change the sum
+    let mut rest = 0.0;
+    let mut k: u64 = 1;
+    while 101f64.powf(-s * k as f64) > 1e-20 {
+        let mu = mobius(k);
+        if mu != 0 {
+            let t = s * k as f64;
+            // ln of ζ(t) with the Euler factors of the small primes taken out
+            let log_zeta: f64 = zeta(t).ln()
+                + SMALL
+                    .iter()
+                    .map(|&p| (-(p as f64).powf(-t)).ln_1p())
+                    .sum::<f64>();
+            rest += mu as f64 * log_zeta / k as f64;
+        }
+        k += 1;
+    }
+    direct + rest
+}
+
+/// The product of the primes in the inclusive range `[lo, hi]`, modulo `m`, found in a single
+/// segmented sieve pass.
+///
+/// Panics if `m == 0`.
+///
+/// ```
+/// use primes::product_of_primes_mod;
+///
+/// assert_eq!(product_of_primes_mod(1, 10, 1_000), 210);
+/// assert_eq!(product_of_primes_mod(10, 20, 100), (11 * 13 * 17 * 19) % 100);
+/// assert_eq!(product_of_primes_mod(24, 28, 7), 1);
+/// ```
+pub fn product_of_primes_mod(lo: u64, hi: u64, m: u64) -> u64 {
+    assert!(m != 0, "modulus must be nonzero");
+    SegmentedPrimes::new(lo, hi).fold(1 % m, |acc, p| mul_mod(acc, p, m))
+}
+
+/// Where the aliquot sequence of a number ends up. Returned by `classify_aliquot`.
+///
+/// The cycle a sequence falls into need not contain the starting number: 95 → 25 → 6 → 6 → ...
+/// is `Perfect(6)`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Aliquot {
+    /// The sequence reaches 0, through a prime and then 1
+    Terminates,
+    /// The sequence reaches a perfect number, which it then repeats
+    Perfect(u64),
+    /// The sequence reaches an amicable pair, smaller number first
+    Amicable(u64, u64),
+    /// The sequence reaches a cycle of three or more sociable numbers, starting from the smallest
+    Sociable(Vec<u64>),
+    /// No end was found within the step limit, or a term's divisor sum didn't fit in a `u64`
+    Unknown,
+}
+
+/// Follow the aliquot sequence `n, s(n), s(s(n)), ...`, where `s(n) = σ(n) - n` is the sum of the
+/// proper divisors, for up to `max_steps` steps, and report whether it terminates or falls into a
+/// cycle.
+///
+/// Each term is factored with trial division up to its second-largest prime factor, which is
+/// quick for most terms but can be slow for a term with two large ones.
+///
+/// Panics if `n == 0`.
+///
+/// ```
+/// use primes::{classify_aliquot, Aliquot};
+///
+/// assert_eq!(classify_aliquot(28, 10), Aliquot::Perfect(28));
+/// assert_eq!(classify_aliquot(284, 10), Aliquot::Amicable(220, 284));
+/// assert_eq!(classify_aliquot(12, 10), Aliquot::Terminates);
+/// assert_eq!(classify_aliquot(95, 10), Aliquot::Perfect(6));
+/// let cycle = vec![12_496, 14_288, 15_472, 14_536, 14_264];
+/// assert_eq!(classify_aliquot(14_264, 10), Aliquot::Sociable(cycle));
+/// // 276 is the smallest number whose sequence is still unresolved
+/// assert_eq!(classify_aliquot(276, 50), Aliquot::Unknown);
+/// ```
+pub fn classify_aliquot(n: u64, max_steps: u32) -> Aliquot {
+    assert!(n != 0, "the aliquot sequence of 0 is undefined");
+    let mut sequence = vec![n];
+    let mut seen = HashMap::new();
+    seen.insert(n, 0);
+    for _ in 0..max_steps {
+        let current = *sequence.last().unwrap();
+        let sigma = Factorization::from_factors(&factors_with(current, &Bpsw)).sum_divisors();
+        let next = match sigma {
+            Some(sigma) => sigma - current,
+            None => return Aliquot::Unknown,
+        };
+        if next == 0 {
+            return Aliquot::Terminates;
+        }
+        if let Some(&start) = seen.get(&next) {
+            let mut cycle = sequence.split_off(start);
+            let smallest = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+            cycle.rotate_left(smallest);
+            return match *cycle.as_slice() {
+                [p] => Aliquot::Perfect(p),
+                [a, b] => Aliquot::Amicable(a, b),
+                _ => Aliquot::Sociable(cycle),
+            };
+        }
+        seen.insert(next, sequence.len());
+        sequence.push(next);
+    }
+    Aliquot::Unknown
+}
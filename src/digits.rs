@@ -0,0 +1,265 @@
+//! Primes defined by their digits, built up a digit at a time rather than by filtering every prime.
+//!
+//! Everything here works in base 10 unless it takes a `base`.
+
+use crate::is_prime_u64;
+
+/// The digits of `n` in the given base, most significant first. Zero has the single digit 0.
+///
+/// Panics if `base < 2`.
+///
+/// ```
+/// use primes::digits::to_digits;
+///
+/// assert_eq!(to_digits(2024, 10), vec![2, 0, 2, 4]);
+/// assert_eq!(to_digits(5, 2), vec![1, 0, 1]);
+/// ```
+pub fn to_digits(n: u64, base: u64) -> Vec<u64> {
+    assert!(base >= 2, "base must be at least 2");
+    let mut digits = vec![n % base];
+    let mut rest = n / base;
+    while rest > 0 {
+        digits.push(rest % base);
+        rest /= base;
+    }
+    digits.reverse();
+    digits
+}
+
+/// The number with the given digits in the given base, most significant first, or `None` if it
+/// doesn't fit in a `u64` or a digit is out of range.
+///
+/// ```
+/// use primes::digits::from_digits;
+///
+/// assert_eq!(from_digits(&[1, 0, 1], 2), Some(5));
+/// assert_eq!(from_digits(&[1, 2], 2), None);
+/// ```
+pub fn from_digits(digits: &[u64], base: u64) -> Option<u64> {
+    digits.iter().try_fold(0u64, |n, &d| {
+        if d >= base {
+            return None;
+        }
+        n.checked_mul(base)?.checked_add(d)
+    })
+}
+
+/// The sum of the digits of `n` in the given base.
+///
+/// ```
+/// use primes::digits::digit_sum;
+///
+/// assert_eq!(digit_sum(1_999, 10), 28);
+/// ```
+pub fn digit_sum(n: u64, base: u64) -> u64 {
+    to_digits(n, base).iter().sum()
+}
+
+/// Test whether `n` is a right-truncatable prime: it stays prime as digits are removed from the
+/// right, down to a single digit.
+///
+/// ```
+/// use primes::digits::is_right_truncatable_prime;
+///
+/// assert!(is_right_truncatable_prime(7_393)); // 739, 73 and 7 are all prime
+/// assert!(!is_right_truncatable_prime(113)); // 1 isn't prime
+/// ```
+pub fn is_right_truncatable_prime(n: u64) -> bool {
+    let mut rest = n;
+    while rest > 0 {
+        if !is_prime_u64(rest) {
+            return false;
+        }
+        rest /= 10;
+    }
+    n > 0
+}
+
+/// Test whether `n` is a left-truncatable prime: it has no zero digits, and stays prime as digits
+/// are removed from the left, down to a single digit.
+///
+/// ```
+/// use primes::digits::is_left_truncatable_prime;
+///
+/// assert!(is_left_truncatable_prime(9_137)); // 137, 37 and 7 are all prime
+/// assert!(!is_left_truncatable_prime(103)); // has a zero
+/// ```
+pub fn is_left_truncatable_prime(n: u64) -> bool {
+    let digits = to_digits(n, 10);
+    if digits.contains(&0) {
+        return false;
+    }
+    (0..digits.len()).all(|start| is_prime_u64(from_digits(&digits[start..], 10).unwrap()))
+}
+
+/// Every right-truncatable prime, in increasing order. There are only 83 of them.
+///
+/// Each one is found by appending a digit to a shorter one, so nothing else is ever tested.
+///
+/// ```
+/// use primes::digits::right_truncatable_primes;
+///
+/// let primes = right_truncatable_primes();
+/// assert_eq!(primes.len(), 83);
+/// assert_eq!(primes.last(), Some(&73_939_133));
+/// ```
+pub fn right_truncatable_primes() -> Vec<u64> {
+    let mut found = Vec::new();
+    let mut level = vec![2, 3, 5, 7];
+    while !level.is_empty() {
+        found.extend_from_slice(&level);
+        level = level
+            .iter()
+            .flat_map(|&p| [1, 3, 7, 9].iter().map(move |&d| p * 10 + d))
+            .filter(|&n| is_prime_u64(n))
+            .collect();
+    }
+    found.sort_unstable();
+    found
+}
+
+/// Every left-truncatable prime that fits in a `u64`, in increasing order.
+///
+/// Each one is found by prepending a digit to a shorter one, so nothing else is ever tested.
+///
+/// ```
+/// use primes::digits::left_truncatable_primes;
+///
+/// let primes = left_truncatable_primes();
+/// assert_eq!(&primes[..8], &[2, 3, 5, 7, 13, 17, 23, 37]);
+/// ```
+pub fn left_truncatable_primes() -> Vec<u64> {
+    let mut found = Vec::new();
+    let mut level = vec![2, 3, 5, 7];
+    let mut place = 1u64;
+    while !level.is_empty() {
+        found.extend_from_slice(&level);
+        place = match place.checked_mul(10) {
+            Some(place) => place,
+            None => break,
+        };
+        level = level
+            .iter()
+            .flat_map(|&p| (1..10).filter_map(move |d: u64| d.checked_mul(place)?.checked_add(p)))
+            .filter(|&n| is_prime_u64(n))
+            .collect();
+    }
+    found.sort_unstable();
+    found
+}
+
+/// An iterator over the primes all of whose digits are prime (2, 3, 5 or 7), in increasing
+/// order. Created by `prime_digit_primes`.
+pub struct PrimeDigitPrimes {
+    // Indices into `PRIME_DIGITS` for each digit, most significant first
+    digits: Vec<usize>,
+    done: bool,
+}
+
+const PRIME_DIGITS: [u64; 4] = [2, 3, 5, 7];
+
+/// Iterate over the primes whose digits are all prime, in increasing order, up to the largest
+/// that fits in a `u64`.
+///
+/// Candidates are built from the digits 2, 3, 5 and 7 directly, so only a tiny fraction of
+/// numbers are ever tested.
+///
+/// ```
+/// use primes::digits::prime_digit_primes;
+///
+/// let primes: Vec<u64> = prime_digit_primes().take(8).collect();
+/// assert_eq!(primes, vec![2, 3, 5, 7, 23, 37, 53, 73]);
+/// ```
+pub fn prime_digit_primes() -> PrimeDigitPrimes {
+    PrimeDigitPrimes {
+        digits: vec![0],
+        done: false,
+    }
+}
+
+impl PrimeDigitPrimes {
+    // Step `digits` to the next candidate, adding a digit when every shorter one has been seen
+    fn advance(&mut self) {
+        for ix in (0..self.digits.len()).rev() {
+            if self.digits[ix] + 1 < PRIME_DIGITS.len() {
+                self.digits[ix] += 1;
+                return;
+            }
+            self.digits[ix] = 0;
+        }
+        self.digits.push(0);
+    }
+}
+
+impl Iterator for PrimeDigitPrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        while !self.done {
+            let digits: Vec<u64> = self.digits.iter().map(|&ix| PRIME_DIGITS[ix]).collect();
+            self.advance();
+            match from_digits(&digits, 10) {
+                Some(n) if is_prime_u64(n) => return Some(n),
+                Some(_) => (),
+                // Every candidate from here on is at least this long
+                None => self.done = true,
+            }
+        }
+        None
+    }
+}
+
+/// All primes up to and including `limit` whose digits add up to `sum`, in increasing order.
+///
+/// Numbers with that digit sum are built directly, digit by digit, so only they are tested.
+/// When `sum` is a multiple of 3, every such number is too, so 3 is the only possibility.
+///
+/// ```
+/// use primes::digits::primes_with_digit_sum;
+///
+/// assert_eq!(primes_with_digit_sum(4, 1_000), vec![13, 31, 103, 211]);
+/// ```
+pub fn primes_with_digit_sum(sum: u64, limit: u64) -> Vec<u64> {
+    let mut found = Vec::new();
+    if sum.is_multiple_of(3) {
+        if sum == 3 && limit >= 3 {
+            found.push(3);
+        }
+        return found;
+    }
+    let max_len = to_digits(limit, 10).len() as u64;
+    for len in 1..=max_len {
+        collect_with_digit_sum(0, len, sum, limit, &mut found);
+    }
+    found
+}
+
+// Push the primes up to `limit` made by appending `len` more digits adding to `sum` to `prefix`,
+// in increasing order
+fn collect_with_digit_sum(prefix: u64, len: u64, sum: u64, limit: u64, found: &mut Vec<u64>) {
+    if len == 0 {
+        if sum == 0 && is_prime_u64(prefix) {
+            found.push(prefix);
+        }
+        return;
+    }
+    let first = if prefix == 0 { 1 } else { 0 };
+    for d in first..=9u64.min(sum) {
+        // The remaining digits can add up to at most 9 each
+        if sum - d > 9 * (len - 1) {
+            continue;
+        }
+        let n = match prefix.checked_mul(10).and_then(|n| n.checked_add(d)) {
+            Some(n) => n,
+            None => return,
+        };
+        // Every number with this prefix is at least this big
+        match 10u64
+            .checked_pow(len as u32 - 1)
+            .and_then(|p| n.checked_mul(p))
+        {
+            Some(smallest) if smallest <= limit => (),
+            _ => return,
+        }
+        collect_with_digit_sum(n, len - 1, sum - d, limit, found);
+    }
+}
@@ -0,0 +1,244 @@
+//! Adapting prime generators from elsewhere to the `PrimeSet` traits.
+
+use std::iter::Peekable;
+
+use crate::{assert_all_prime, is_prime_u64, PrimeSetBasics, LARGEST_U64_PRIME};
+
+/**
+A `PrimeSetBasics` backed by an outside source of primes, such as another crate's sieve.
+
+`next` is called each time one more prime is needed, and must return the consecutive primes
+after those already in the list, starting from 2 if the list is empty. All the `PrimeSet` methods
+then work as they would with `Sieve` or `TrialDivision`. Each prime is checked with
+`is_prime_u64` as it arrives, which costs little next to generating it.
+
+```
+use primes::{ExternalPrimes, PrimeSet};
+
+// Any iterator over the primes will do; here, one from this crate standing in for another's
+let mut source = primes::Sieve::new();
+let mut it = source.iter();
+let mut pset = ExternalPrimes::new(move || it.next().unwrap());
+assert_eq!(pset.get(99), 541);
+assert!(pset.is_prime(541));
+```
+**/
+pub struct ExternalPrimes<F: FnMut() -> u64> {
+    primes: Vec<u64>,
+    next: F,
+    // The largest prime `next` can supply
+    limit: u64,
+}
+
+impl<F: FnMut() -> u64> ExternalPrimes<F> {
+    /// Generate every prime with `next`, starting from 2. The first prime is requested
+    /// immediately, since the `PrimeSet` methods expect the list to be non-empty.
+    pub fn new(next: F) -> ExternalPrimes<F> {
+        ExternalPrimes::with_primes(Vec::new(), next)
+    }
+
+    /// Start from a sorted list of consecutive primes beginning with 2, such as a slice taken from
+    /// another sieve, and call `next` for any primes after those (including 2, if the list is
+    /// empty).
+    ///
+    /// Panics if `primes` doesn't start with 2, isn't strictly increasing, or holds a number
+    /// that isn't prime.
+    pub fn with_primes(primes: Vec<u64>, next: F) -> ExternalPrimes<F> {
+        if let Some(&first) = primes.first() {
+            assert_eq!(first, 2, "primes must start with 2");
+        }
+        assert!(
+            primes.windows(2).all(|w| w[0] < w[1]),
+            "primes must be strictly increasing"
+        );
+        assert_all_prime(&primes);
+        let mut pset = ExternalPrimes {
+            primes,
+            next,
+            limit: LARGEST_U64_PRIME,
+        };
+        if pset.primes.is_empty() {
+            pset.expand();
+        }
+        pset
+    }
+
+    /// Declare that the source has no primes past `limit`, so that `PrimeSet::try_find` and
+    /// `PrimeSet::try_get` return an error instead of asking it for more.
+    ///
+    /// ```
+    /// use primes::{ExternalPrimes, PrimeSet};
+    ///
+    /// let mut source = primes::Sieve::new();
+    /// let mut it = source.iter();
+    /// let mut pset = ExternalPrimes::new(move || it.next().unwrap()).with_limit(97);
+    /// assert_eq!(pset.try_get(24), Ok(97));
+    /// assert!(pset.try_get(25).is_err());
+    /// assert_eq!(pset.iter().count(), 25);
+    /// ```
+    pub fn with_limit(mut self, limit: u64) -> ExternalPrimes<F> {
+        self.limit = limit;
+        self
+    }
+}
+
+impl ExternalPrimes<fn() -> u64> {
+    /// Use a fixed list of consecutive primes beginning with 2, such as a slice taken from another
+    /// sieve, with nothing to extend it.
+    ///
+    /// The last prime in the list is its `expandable_limit`, so `PrimeSet::try_find` and
+    /// `PrimeSet::try_get` return an error past it, and iterators stop there.
+    ///
+    /// Panics if `primes` is empty, doesn't start with 2, isn't strictly increasing or holds a
+    /// number that isn't prime, and when anything else needs a prime past the end of the list.
+    pub fn from_slice(primes: &[u64]) -> ExternalPrimes<fn() -> u64> {
+        fn exhausted() -> u64 {
+            panic!("ran out of primes in an ExternalPrimes built from a slice")
+        }
+        let limit = *primes.last().expect("primes must not be empty");
+        ExternalPrimes::with_primes(primes.to_vec(), exhausted as fn() -> u64).with_limit(limit)
+    }
+}
+
+impl<F: FnMut() -> u64> PrimeSetBasics for ExternalPrimes<F> {
+    /// Asks the source for one more prime, and adds it to the list.
+    ///
+    /// Panics if the source's prime isn't greater than the last one in the list, or isn't prime.
+    fn expand(&mut self) {
+        let p = (self.next)();
+        match self.primes.last() {
+            Some(&last) => assert!(p > last, "external primes must be increasing"),
+            None => assert_eq!(p, 2, "external primes must start with 2"),
+        }
+        assert!(is_prime_u64(p), "{} is not prime", p);
+        self.primes.push(p);
+    }
+
+    fn list(&self) -> &[u64] {
+        &self.primes
+    }
+
+    /// Every prime was checked on the way in
+    fn primes_verified(&self) -> bool {
+        true
+    }
+
+    /// The limit set by `with_limit`, or the last prime of a list given to `from_slice`
+    fn expandable_limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+/**
+A read-only `PrimeSetBasics` over an iterator of the consecutive primes starting from 2, such as
+another crate's prime iterator. This is the iterator counterpart of `ExternalPrimes`, and can be
+made from one with `From`/`Into`.
+
+Unlike the source of an `ExternalPrimes`, the iterator may end. Its last prime then becomes the
+`expandable_limit`, so `PrimeSet::try_find` and `PrimeSet::try_get` return an error past it and
+iterators stop there. The iterator is always kept one prime ahead of the list, to know whether
+it has ended. As with `ExternalPrimes`, each prime is checked with `is_prime_u64`.
+
+```
+use primes::{IterPrimes, PrimeSet};
+
+// Another crate's iterator would do; here, a list of the primes below 100
+let mut pset: IterPrimes<_> = primes::Sieve::new()
+    .iter()
+    .take_while(|&p| p < 100)
+    .collect::<Vec<_>>()
+    .into_iter()
+    .into();
+assert_eq!(pset.get(24), 97);
+assert!(pset.is_prime(89));
+assert!(pset.try_get(25).is_err());
+```
+**/
+pub struct IterPrimes<I: Iterator<Item = u64>> {
+    primes: Vec<u64>,
+    source: Peekable<I>,
+    limit: u64,
+}
+
+impl<I: Iterator<Item = u64>> IterPrimes<I> {
+    /// Take the primes from `source`, which must yield consecutive primes starting from 2. The
+    /// first prime is requested immediately, since the `PrimeSet` methods expect the list to be
+    /// non-empty.
+    ///
+    /// Panics if `source` is empty or doesn't start with 2.
+    pub fn new(source: I) -> IterPrimes<I> {
+        let mut pset = IterPrimes {
+            primes: Vec::new(),
+            source: source.peekable(),
+            limit: LARGEST_U64_PRIME,
+        };
+        pset.expand();
+        pset
+    }
+}
+
+impl<I: Iterator<Item = u64>> From<I> for IterPrimes<I> {
+    fn from(source: I) -> IterPrimes<I> {
+        IterPrimes::new(source)
+    }
+}
+
+impl<I: Iterator<Item = u64>> PrimeSetBasics for IterPrimes<I> {
+    /// Takes one more prime from the iterator, and adds it to the list.
+    ///
+    /// Panics if the iterator has ended, or if its prime isn't greater than the last one in the
+    /// list or isn't prime.
+    fn expand(&mut self) {
+        let p = self
+            .source
+            .next()
+            .expect("ran out of primes in an IterPrimes");
+        match self.primes.last() {
+            Some(&last) => assert!(p > last, "iterated primes must be increasing"),
+            None => assert_eq!(p, 2, "iterated primes must start with 2"),
+        }
+        assert!(is_prime_u64(p), "{} is not prime", p);
+        self.primes.push(p);
+        if self.source.peek().is_none() {
+            self.limit = p;
+        }
+    }
+
+    fn list(&self) -> &[u64] {
+        &self.primes
+    }
+
+    /// The iterator's last prime once it has ended, and `LARGEST_U64_PRIME` until then
+    fn expandable_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Every prime was checked on the way in
+    fn primes_verified(&self) -> bool {
+        true
+    }
+}
+
+/// Collect the consecutive primes starting from 2, such as those from another crate's sieve,
+/// into any `PrimeSet` that can resume from a list, such as `Sieve` or `TrialDivision`. The
+/// result then carries on finding primes after the last one.
+///
+/// Each value is checked with `is_prime_u64`. Panics if any isn't prime, or if the primes don't
+/// start with 2, 3 (and 5, for a `Sieve`) or aren't strictly increasing. They are trusted to be
+/// consecutive, with none missing.
+///
+/// ```
+/// use primes::{collect_into_primeset, PrimeSet, Sieve};
+///
+/// let small = vec![2, 3, 5, 7, 11, 13];
+/// let mut sieve: Sieve = collect_into_primeset(small);
+/// assert_eq!(sieve.len(), 6);
+/// assert_eq!(sieve.get(6), 17);
+/// ```
+pub fn collect_into_primeset<P, I>(primes: I) -> P
+where
+    P: From<Vec<u64>>,
+    I: IntoIterator<Item = u64>,
+{
+    P::from(primes.into_iter().collect())
+}
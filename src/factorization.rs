@@ -0,0 +1,851 @@
+//! Prime factorizations as `(prime, exponent)` pairs.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Div, Mul, MulAssign};
+
+use crate::{factors, firstfac, gcd};
+
+/**
+The prime factorization of a positive integer, stored as `(prime, exponent)` pairs in increasing
+order of prime.
+
+```
+use primes::Factorization;
+
+let f = Factorization::of(360);
+assert_eq!(f.pairs(), &[(2, 3), (3, 2), (5, 1)]);
+assert_eq!(f.num_divisors(), 24);
+assert_eq!(f.value(), Some(360));
+```
+**/
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Factorization {
+    pairs: Vec<(u64, u32)>,
+}
+
+impl Factorization {
+    /// The factorization of 1, with no prime factors
+    pub fn one() -> Factorization {
+        Factorization { pairs: Vec::new() }
+    }
+
+    /// Factorize `n` by trial division
+    ///
+    /// Panics if `n == 0`, which has no prime factorization.
+    pub fn of(n: u64) -> Factorization {
+        assert!(n != 0, "0 has no prime factorization");
+        Factorization::from_factors(&factors(n))
+    }
+
+    /// Build a factorization from a list of prime factors, in any order and including repeats
+    pub fn from_factors(factors: &[u64]) -> Factorization {
+        let mut sorted = factors.to_vec();
+        sorted.sort_unstable();
+        let mut pairs: Vec<(u64, u32)> = Vec::new();
+        for p in sorted {
+            match pairs.last_mut() {
+                Some(&mut (q, ref mut e)) if q == p => *e += 1,
+                _ => pairs.push((p, 1)),
+            }
+        }
+        Factorization { pairs }
+    }
+
+    /// The `(prime, exponent)` pairs, in increasing order of prime
+    pub fn pairs(&self) -> &[(u64, u32)] {
+        &self.pairs
+    }
+
+    /// Whether this is the factorization of 1
+    pub fn is_one(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// The number this is a factorization of, or `None` if it doesn't fit in a `u64`
+    pub fn value(&self) -> Option<u64> {
+        self.pairs
+            .iter()
+            .try_fold(1u64, |acc, &(p, e)| acc.checked_mul(p.checked_pow(e)?))
+    }
+
+    /// The number of divisors, often written d(n) or τ(n)
+    pub fn num_divisors(&self) -> u64 {
+        self.pairs.iter().map(|&(_, e)| e as u64 + 1).product()
+    }
+
+    /// The sum of all divisors, often written σ(n), or `None` if it doesn't fit in a `u64`
+    pub fn sum_divisors(&self) -> Option<u64> {
+        self.pairs.iter().try_fold(1u64, |acc, &(p, e)| {
+            // 1 + p + p^2 + ... + p^e
+            let mut term = 1u64;
+            let mut power = 1u64;
+            for _ in 0..e {
+                power = power.checked_mul(p)?;
+                term = term.checked_add(power)?;
+            }
+            acc.checked_mul(term)
+        })
+    }
+
+    /// All divisors, in increasing order
+    ///
+    /// Panics if the value doesn't fit in a `u64`.
+    pub fn divisors(&self) -> Vec<u64> {
+        let mut divisors = vec![1u64];
+        for &(p, e) in &self.pairs {
+            let count = divisors.len();
+            let mut power = 1u64;
+            for _ in 0..e {
+                power = power.checked_mul(p).expect("divisor overflowed u64");
+                for i in 0..count {
+                    let d = divisors[i]
+                        .checked_mul(power)
+                        .expect("divisor overflowed u64");
+                    divisors.push(d);
+                }
+            }
+        }
+        divisors.sort_unstable();
+        divisors
+    }
+}
+
+impl Factorization {
+    // Combine two factorizations prime by prime, with exponent 0 standing in for primes missing
+    // from one side. Primes whose combined exponent is 0 are dropped.
+    fn merge_with<F: Fn(u32, u32) -> u32>(&self, other: &Factorization, f: F) -> Factorization {
+        let (a, b) = (&self.pairs, &other.pairs);
+        let (mut i, mut j) = (0, 0);
+        let mut pairs = Vec::with_capacity(a.len() + b.len());
+        while i < a.len() || j < b.len() {
+            let (p, e) = match (a.get(i), b.get(j)) {
+                (Some(&(p, e1)), Some(&(q, e2))) if p == q => {
+                    i += 1;
+                    j += 1;
+                    (p, f(e1, e2))
+                }
+                (Some(&(p, e1)), Some(&(q, _))) if p < q => {
+                    i += 1;
+                    (p, f(e1, 0))
+                }
+                (Some(&(p, e1)), None) => {
+                    i += 1;
+                    (p, f(e1, 0))
+                }
+                (_, Some(&(q, e2))) => {
+                    j += 1;
+                    (q, f(0, e2))
+                }
+                (None, None) => unreachable!(),
+            };
+            if e > 0 {
+                pairs.push((p, e));
+            }
+        }
+        Factorization { pairs }
+    }
+
+    /// Whether the number this factorizes divides the one `other` factorizes
+    pub fn divides(&self, other: &Factorization) -> bool {
+        self.pairs
+            .iter()
+            .all(|&(p, e)| other.pairs.iter().any(|&(q, f)| p == q && e <= f))
+    }
+
+    /// The factorization of `self / other`, or `None` if `other` doesn't divide `self`
+    pub fn checked_div(&self, other: &Factorization) -> Option<Factorization> {
+        if !other.divides(self) {
+            return None;
+        }
+        Some(self.merge_with(other, |e1, e2| e1 - e2))
+    }
+
+    /// The factorization of the greatest common divisor
+    pub fn gcd(&self, other: &Factorization) -> Factorization {
+        self.merge_with(other, u32::min)
+    }
+
+    /// The factorization of the least common multiple
+    pub fn lcm(&self, other: &Factorization) -> Factorization {
+        self.merge_with(other, u32::max)
+    }
+
+    /// The factorization of `self` raised to the power `exp`.
+    ///
+    /// Panics if an exponent of the result would overflow a `u32`.
+    pub fn pow(&self, exp: u32) -> Factorization {
+        if exp == 0 {
+            return Factorization::one();
+        }
+        let raise = |e: u32| e.checked_mul(exp).expect("exponent overflows a u32");
+        Factorization {
+            pairs: self.pairs.iter().map(|&(p, e)| (p, raise(e))).collect(),
+        }
+    }
+
+    /// The factorization of Euler's totient φ(n), the count of numbers up to `n` coprime to it.
+    ///
+    /// φ(p^e) is p^(e-1)·(p - 1), so this only needs to factor `p - 1` for each prime factor `p`,
+    /// which is much cheaper than factoring φ(n) itself.
+    ///
+    /// ```
+    /// use primes::Factorization;
+    ///
+    /// assert_eq!(Factorization::of(36).totient().value(), Some(12));
+    /// assert_eq!(Factorization::of(1).totient().value(), Some(1));
+    /// ```
+    pub fn totient(&self) -> Factorization {
+        self.totient_with(&mut HashMap::new())
+    }
+
+    // `totient`, looking up and storing the factorizations of `p - 1` in `cache`
+    fn totient_with(&self, cache: &mut HashMap<u64, Factorization>) -> Factorization {
+        let mut result = Factorization::one();
+        for &(p, e) in &self.pairs {
+            let below = cache.entry(p).or_insert_with(|| Factorization::of(p - 1));
+            result *= below;
+            if e > 1 {
+                result *= &Factorization {
+                    pairs: vec![(p, e - 1)],
+                };
+            }
+        }
+        result
+    }
+}
+
+/// Multiplication adds exponents, so it never overflows until the result is evaluated with
+/// `value`.
+///
+/// ```
+/// use primes::Factorization;
+///
+/// // 30! / (15! * 15!) = C(30, 15), computed without evaluating the factorials
+/// let factorial = |n: u64| {
+///     (2..=n).fold(Factorization::one(), |acc, k| acc * Factorization::of(k))
+/// };
+/// let choose = factorial(30)
+///     .checked_div(&factorial(15).pow(2))
+///     .unwrap();
+/// assert_eq!(choose.value(), Some(155_117_520));
+/// assert_eq!(factorial(30).value(), None);
+/// ```
+impl Mul for Factorization {
+    type Output = Factorization;
+    fn mul(self, rhs: Factorization) -> Factorization {
+        self.merge_with(&rhs, |e1, e2| e1 + e2)
+    }
+}
+
+impl<'a> Mul<&'a Factorization> for &'a Factorization {
+    type Output = Factorization;
+    fn mul(self, rhs: &Factorization) -> Factorization {
+        self.merge_with(rhs, |e1, e2| e1 + e2)
+    }
+}
+
+impl MulAssign<&Factorization> for Factorization {
+    fn mul_assign(&mut self, rhs: &Factorization) {
+        *self = self.merge_with(rhs, |e1, e2| e1 + e2);
+    }
+}
+
+/// Reduce the fraction `num / den` to lowest terms.
+///
+/// Panics if `den == 0`. A zero numerator reduces to `(0, 1)`.
+///
+/// ```
+/// use primes::reduce_fraction;
+///
+/// assert_eq!(reduce_fraction(84, 36), (7, 3));
+/// ```
+pub fn reduce_fraction(num: u64, den: u64) -> (u64, u64) {
+    assert!(den != 0, "denominator must be nonzero");
+    let g = gcd(num, den);
+    (num / g, den / g)
+}
+
+/**
+A positive rational number, with its numerator and denominator kept in lowest terms as
+factorizations.
+
+Products and quotients only add and subtract exponents, so long chains of them (binomial
+coefficients, probabilities over many factorials, ...) stay exact and can't overflow until the
+result is evaluated.
+
+```
+use primes::FactoredRatio;
+
+// The probability that 5 people all have distinct birthdays: 365 * 364 * ... * 361 / 365^5
+let mut p = FactoredRatio::new(1, 1);
+for k in 0..5 {
+    p = p * FactoredRatio::new(365 - k, 365);
+}
+assert_eq!(p.value(), Some((17_267_274_024, 17_748_900_625)));
+assert!((p.to_f64() - 0.9728644).abs() < 1e-7);
+```
+**/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FactoredRatio {
+    num: Factorization,
+    den: Factorization,
+}
+
+impl FactoredRatio {
+    /// The ratio `num / den`, reduced to lowest terms
+    ///
+    /// Panics if either is zero.
+    pub fn new(num: u64, den: u64) -> FactoredRatio {
+        FactoredRatio::from_factorizations(Factorization::of(num), Factorization::of(den))
+    }
+
+    /// The ratio of two factorizations, reduced to lowest terms
+    pub fn from_factorizations(num: Factorization, den: Factorization) -> FactoredRatio {
+        let common = num.gcd(&den);
+        FactoredRatio {
+            num: num.checked_div(&common).unwrap(),
+            den: den.checked_div(&common).unwrap(),
+        }
+    }
+
+    /// The reduced numerator
+    pub fn numerator(&self) -> &Factorization {
+        &self.num
+    }
+
+    /// The reduced denominator
+    pub fn denominator(&self) -> &Factorization {
+        &self.den
+    }
+
+    /// The reciprocal, `den / num`
+    pub fn recip(&self) -> FactoredRatio {
+        FactoredRatio {
+            num: self.den.clone(),
+            den: self.num.clone(),
+        }
+    }
+
+    /// Raise to the power `exp`.
+    ///
+    /// Panics if an exponent of the result would overflow a `u32`.
+    pub fn pow(&self, exp: u32) -> FactoredRatio {
+        FactoredRatio {
+            num: self.num.pow(exp),
+            den: self.den.pow(exp),
+        }
+    }
+
+    /// The reduced `(numerator, denominator)`, or `None` if either doesn't fit in a `u64`
+    pub fn value(&self) -> Option<(u64, u64)> {
+        Some((self.num.value()?, self.den.value()?))
+    }
+
+    /// The approximate value as a float, which is finite even when `value` would overflow
+    pub fn to_f64(&self) -> f64 {
+        let log2 = |f: &Factorization| -> f64 {
+            f.pairs()
+                .iter()
+                .map(|&(p, e)| e as f64 * (p as f64).log2())
+                .sum()
+        };
+        (log2(&self.num) - log2(&self.den)).exp2()
+    }
+}
+
+impl Mul for FactoredRatio {
+    type Output = FactoredRatio;
+    fn mul(self, rhs: FactoredRatio) -> FactoredRatio {
+        FactoredRatio::from_factorizations(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl Div for FactoredRatio {
+    type Output = FactoredRatio;
+    fn div(self, rhs: FactoredRatio) -> FactoredRatio {
+        FactoredRatio::from_factorizations(self.num * rhs.den, self.den * rhs.num)
+    }
+}
+
+/// The least common multiple of all the given numbers, or `None` if it doesn't fit in a `u128`.
+///
+/// Each number is factorized, and the LCM is built by taking the largest exponent of each prime,
+/// so nothing can overflow until the single final evaluation. The LCM of no numbers is 1, and of
+/// any set including 0 is 0.
+///
+/// ```
+/// use primes::lcm_all;
+///
+/// assert_eq!(lcm_all(1..=20), Some(232_792_560));
+/// assert_eq!(lcm_all(1..=50), Some(3_099_044_504_245_996_706_400));
+/// assert_eq!(lcm_all(1..=100), None);
+/// ```
+pub fn lcm_all<I: IntoIterator<Item = u64>>(values: I) -> Option<u128> {
+    let mut lcm = Factorization::one();
+    for n in values {
+        if n == 0 {
+            return Some(0);
+        }
+        lcm = lcm.lcm(&Factorization::of(n));
+    }
+    lcm.pairs.iter().try_fold(1u128, |acc, &(p, e)| {
+        acc.checked_mul((p as u128).checked_pow(e)?)
+    })
+}
+
+/// The greatest common divisor of all the given numbers. The GCD of no numbers is 0.
+///
+/// ```
+/// use primes::gcd_all;
+///
+/// assert_eq!(gcd_all(vec![84, 126, 210]), 42);
+/// ```
+pub fn gcd_all<I: IntoIterator<Item = u64>>(values: I) -> u64 {
+    let mut result = 0;
+    for n in values {
+        result = gcd(result, n);
+        if result == 1 {
+            break;
+        }
+    }
+    result
+}
+
+/// Count the integers in `1..n` that are coprime to `m`.
+///
+/// This is inclusion-exclusion over the distinct prime factors of `m`: at most 15 of them for a
+/// `u64`, and products that exceed `n` are pruned early.
+///
+/// Panics if `m == 0`.
+///
+/// ```
+/// use primes::count_coprime_below;
+///
+/// assert_eq!(count_coprime_below(10, 6), 3); // 1, 5, 7
+/// assert_eq!(count_coprime_below(101, 100), 40); // φ(100)
+/// ```
+pub fn count_coprime_below(n: u64, m: u64) -> u64 {
+    let primes: Vec<u64> = Factorization::of(m).pairs.iter().map(|&(p, _)| p).collect();
+    let below = n.saturating_sub(1);
+
+    // Count the values up to `below` that are multiples of `d`, but not of any of `primes`
+    fn count(primes: &[u64], d: u64, below: u64) -> i128 {
+        let mut total = (below / d) as i128;
+        for (ix, &p) in primes.iter().enumerate() {
+            match d.checked_mul(p) {
+                Some(dp) if dp <= below => total -= count(&primes[ix + 1..], dp, below),
+                // The primes are increasing, so later ones won't fit either
+                _ => break,
+            }
+        }
+        total
+    }
+    count(&primes, 1, below) as u64
+}
+
+/// An iterator adaptor that keeps only the values coprime to a fixed modulus. Created by
+/// `coprime_to`.
+#[derive(Debug, Clone)]
+pub struct CoprimeTo<I> {
+    iter: I,
+    primes: Vec<u64>,
+}
+
+/// Filter `values` down to those coprime to `m`.
+///
+/// `m` is factorized once up front, so each value only costs a remainder per distinct prime
+/// factor of `m`, rather than a full `gcd`.
+///
+/// Panics if `m == 0`.
+///
+/// ```
+/// use primes::coprime_to;
+///
+/// let reduced: Vec<u64> = coprime_to(12, 0..12).collect();
+/// assert_eq!(reduced, vec![1, 5, 7, 11]);
+/// ```
+pub fn coprime_to<I: IntoIterator<Item = u64>>(m: u64, values: I) -> CoprimeTo<I::IntoIter> {
+    CoprimeTo {
+        iter: values.into_iter(),
+        primes: Factorization::of(m).pairs.iter().map(|&(p, _)| p).collect(),
+    }
+}
+
+impl<I: Iterator<Item = u64>> Iterator for CoprimeTo<I> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let primes = &self.primes;
+        self.iter.find(|&n| primes.iter().all(|&p| n % p != 0))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/**
+The recursive splitting of a number into its prime factors, as a binary tree. Created by
+`factor_tree`.
+
+Each composite node splits into its smallest prime factor and the cofactor, and the leaves are
+primes. Formatting it with `Display` draws the tree:
+
+```
+use primes::factor_tree;
+
+let tree = factor_tree(60);
+assert_eq!(tree.leaves(), vec![2, 2, 3, 5]);
+assert_eq!(
+    tree.to_string(),
+    "60\n├── 2\n└── 30\n    ├── 2\n    └── 15\n        ├── 3\n        └── 5\n"
+);
+```
+**/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FactorTree {
+    value: u64,
+    split: Option<Box<(FactorTree, FactorTree)>>,
+}
+
+impl FactorTree {
+    /// The number at this node
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// The two factors this node splits into, or `None` for a leaf: a prime, 0 or 1
+    pub fn children(&self) -> Option<(&FactorTree, &FactorTree)> {
+        self.split.as_ref().map(|split| (&split.0, &split.1))
+    }
+
+    /// The values at the leaves, from left to right. For `n > 1`, these are the prime factors of
+    /// `n` in increasing order.
+    pub fn leaves(&self) -> Vec<u64> {
+        let mut leaves = Vec::new();
+        let mut node = self;
+        // Only the right child ever splits further, so this walks down the right spine
+        while let Some((left, right)) = node.children() {
+            leaves.extend(left.leaves());
+            node = right;
+        }
+        leaves.push(node.value);
+        leaves
+    }
+
+    fn fmt_children(&self, f: &mut fmt::Formatter, prefix: &str) -> fmt::Result {
+        if let Some((left, right)) = self.children() {
+            writeln!(f, "{}├── {}", prefix, left.value)?;
+            left.fmt_children(f, &format!("{}│   ", prefix))?;
+            writeln!(f, "{}└── {}", prefix, right.value)?;
+            right.fmt_children(f, &format!("{}    ", prefix))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FactorTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.value)?;
+        self.fmt_children(f, "")
+    }
+}
+
+/// Build the tree of splits that factoring `n` goes through: `n` splits into its smallest prime
+/// factor and `n` divided by it, and so on until only primes are left.
+///
+/// ```
+/// use primes::factor_tree;
+///
+/// let tree = factor_tree(12);
+/// let (left, right) = tree.children().unwrap();
+/// assert_eq!((left.value(), right.value()), (2, 6));
+/// assert!(factor_tree(13).children().is_none());
+/// ```
+pub fn factor_tree(n: u64) -> FactorTree {
+    let split = if n <= 1 {
+        None
+    } else {
+        let p = firstfac(n);
+        if p == n {
+            None
+        } else {
+            Some(Box::new((factor_tree(p), factor_tree(n / p))))
+        }
+    };
+    FactorTree { value: n, split }
+}
+
+/// An iterator over the ways to write a number as a product of two factors. Created by
+/// `divisor_pairs`.
+#[derive(Debug, Clone)]
+pub struct DivisorPairs {
+    n: u64,
+    divisors: std::vec::IntoIter<u64>,
+}
+
+/// Iterate over the pairs `(d, n / d)` with `d <= n / d`, in increasing order of `d`.
+///
+/// The divisors come from the factorization of `n`, so this takes no more than one trial
+/// division pass, however many divisors there are.
+///
+/// Panics if `n == 0`.
+///
+/// ```
+/// use primes::divisor_pairs;
+///
+/// let pairs: Vec<(u64, u64)> = divisor_pairs(36).collect();
+/// assert_eq!(pairs, vec![(1, 36), (2, 18), (3, 12), (4, 9), (6, 6)]);
+/// ```
+pub fn divisor_pairs(n: u64) -> DivisorPairs {
+    DivisorPairs {
+        n,
+        divisors: Factorization::of(n).divisors().into_iter(),
+    }
+}
+
+impl Iterator for DivisorPairs {
+    type Item = (u64, u64);
+    fn next(&mut self) -> Option<(u64, u64)> {
+        let d = self.divisors.next()?;
+        let cofactor = self.n / d;
+        if d > cofactor {
+            // Past the square root: the rest are the pairs already seen, swapped
+            self.divisors = Vec::new().into_iter();
+            return None;
+        }
+        Some((d, cofactor))
+    }
+}
+
+/// The number of ways to write `n` as an ordered product of `k` factors, allowing factors of 1,
+/// or `None` if it doesn't fit in a `u64`.
+///
+/// Each prime's exponent `e` is split among the `k` factors independently, in
+/// `binomial(e + k - 1, k - 1)` ways, and the count is the product of those. For `k = 2` this is
+/// the number of divisors.
+///
+/// Panics if `n == 0`.
+///
+/// ```
+/// use primes::count_representations_as_product;
+///
+/// // 12 = 1·1·12 = 1·2·6 = 2·2·3 = ..., in any order
+/// assert_eq!(count_representations_as_product(12, 3), Some(18));
+/// assert_eq!(count_representations_as_product(12, 2), Some(6));
+/// assert_eq!(count_representations_as_product(12, 1), Some(1));
+/// assert_eq!(count_representations_as_product(12, 0), Some(0));
+/// assert_eq!(count_representations_as_product(1, 0), Some(1));
+/// ```
+pub fn count_representations_as_product(n: u64, k: u64) -> Option<u64> {
+    let f = Factorization::of(n);
+    if k == 0 {
+        // The empty product is 1
+        return Some(if f.is_one() { 1 } else { 0 });
+    }
+    f.pairs.iter().try_fold(1u64, |acc, &(_, e)| {
+        // binomial(e + k - 1, e), built up one step at a time so every intermediate is exact
+        let mut ways = 1u128;
+        for i in 1..=e as u128 {
+            ways = ways.checked_mul(k as u128 - 1 + i)? / i;
+            if ways > u64::MAX as u128 {
+                return None;
+            }
+        }
+        acc.checked_mul(ways as u64)
+    })
+}
+
+// Every exponent vector componentwise at most `e`, as an odometer over the ranges `0..=e[i]`
+fn exponent_vectors(e: &[u32]) -> Vec<Vec<u32>> {
+    let mut all = Vec::new();
+    let mut v = vec![0; e.len()];
+    loop {
+        all.push(v.clone());
+        let mut i = 0;
+        loop {
+            if i == e.len() {
+                return all;
+            }
+            if v[i] < e[i] {
+                v[i] += 1;
+                break;
+            }
+            v[i] = 0;
+            i += 1;
+        }
+    }
+}
+
+/// The number of ways to write `n` as an ordered product of factors greater than 1, often
+/// written H(n), or `None` if it doesn't fit in a `u64`. By convention H(1) = 1.
+///
+/// H(n) is the sum of H(d) over the proper divisors `d` of `n`, and only depends on the
+/// exponents in the factorization of `n`, so the recursion is memoized on the sorted exponents.
+///
+/// Panics if `n == 0`.
+///
+/// ```
+/// use primes::ordered_factorizations;
+///
+/// // 12, 2·6, 6·2, 3·4, 4·3, 2·2·3, 2·3·2, 3·2·2
+/// assert_eq!(ordered_factorizations(12), Some(8));
+/// assert_eq!(ordered_factorizations(1), Some(1));
+/// assert_eq!(ordered_factorizations(1 << 63), Some(1 << 62));
+/// ```
+pub fn ordered_factorizations(n: u64) -> Option<u64> {
+    fn count(exponents: Vec<u32>, memo: &mut HashMap<Vec<u32>, u128>) -> u128 {
+        if exponents.iter().all(|&e| e == 0) {
+            return 1;
+        }
+        if let Some(&h) = memo.get(&exponents) {
+            return h;
+        }
+        let mut total = 0;
+        for mut divisor in exponent_vectors(&exponents) {
+            if divisor == exponents {
+                continue;
+            }
+            divisor.sort_unstable();
+            total += count(divisor, memo);
+        }
+        memo.insert(exponents, total);
+        total
+    }
+
+    let mut exponents: Vec<u32> = Factorization::of(n).pairs.iter().map(|&(_, e)| e).collect();
+    exponents.sort_unstable();
+    let h = count(exponents, &mut HashMap::new());
+    if h > u64::MAX as u128 {
+        return None;
+    }
+    Some(h as u64)
+}
+
+/// The number of ways to write `n` as an unordered product of factors greater than 1, so that
+/// `2·6` and `6·2` count once. By convention this is 1 for `n = 1`.
+///
+/// This is counted like making change, with the divisors greater than 1 as the coins: the count
+/// for each divisor `m` of `n` is built up from the counts for `m / d`, one coin `d` at a time.
+/// That takes one step for each pair `d | m | n`, about 14 million for the product of the first
+/// 15 primes.
+///
+/// Panics if `n == 0`.
+///
+/// ```
+/// use primes::multiplicative_partitions;
+///
+/// // 12, 2·6, 3·4, 2·2·3
+/// assert_eq!(multiplicative_partitions(12), 4);
+/// assert_eq!(multiplicative_partitions(1), 1);
+/// // The same as the number of partitions of 10
+/// assert_eq!(multiplicative_partitions(1 << 10), 42);
+/// ```
+pub fn multiplicative_partitions(n: u64) -> u64 {
+    let exponents: Vec<usize> = Factorization::of(n)
+        .pairs
+        .iter()
+        .map(|&(_, e)| e as usize)
+        .collect();
+    // Divisors are indexed by their exponent vectors, in mixed radix with the first prime's
+    // exponent as the lowest digit, so that d | m gives index(m) = index(d) + index(m / d)
+    let mut strides = Vec::with_capacity(exponents.len());
+    let mut divisor_count = 1;
+    for &e in &exponents {
+        strides.push(divisor_count);
+        divisor_count *= e + 1;
+    }
+
+    // ways[index(m)] counts the partitions of m into the coins used so far
+    let mut ways = vec![0u64; divisor_count];
+    ways[0] = 1;
+    let mut coin = vec![0; exponents.len()];
+    for coin_index in 1..divisor_count {
+        // The next exponent vector, in index order
+        for (digit, &e) in coin.iter_mut().zip(&exponents) {
+            if *digit < e {
+                *digit += 1;
+                break;
+            }
+            *digit = 0;
+        }
+        // Each cofactor k of n / coin, in increasing index order so that ways[index(k)] already
+        // counts this coin
+        let mut k = vec![0; exponents.len()];
+        let mut k_index = 0;
+        loop {
+            ways[coin_index + k_index] += ways[k_index];
+            // Step to the next k, or stop after the last
+            let mut i = 0;
+            while i < k.len() && k[i] == exponents[i] - coin[i] {
+                k_index -= k[i] * strides[i];
+                k[i] = 0;
+                i += 1;
+            }
+            if i == k.len() {
+                break;
+            }
+            k[i] += 1;
+            k_index += strides[i];
+        }
+    }
+    ways[divisor_count - 1]
+}
+
+/// An iterator over the totient chain of a number: `n`, φ(n), φ(φ(n)), ..., down to 1. Created by
+/// `totient_chain`.
+#[derive(Debug, Clone)]
+pub struct TotientChain {
+    current: Option<Factorization>,
+    // Factorizations of p - 1 for the primes p seen so far
+    cache: HashMap<u64, Factorization>,
+}
+
+/// Iterate over `n`, φ(n), φ(φ(n)), ..., ending with 1.
+///
+/// Each step works from the previous factorization (see `Factorization::totient`), and the
+/// factorizations of `p - 1` are cached along the way, so only `n` itself is factored from
+/// scratch.
+///
+/// Panics if `n == 0`.
+///
+/// ```
+/// use primes::totient_chain;
+///
+/// let chain: Vec<u64> = totient_chain(100).collect();
+/// assert_eq!(chain, vec![100, 40, 16, 8, 4, 2, 1]);
+/// ```
+pub fn totient_chain(n: u64) -> TotientChain {
+    TotientChain {
+        current: Some(Factorization::of(n)),
+        cache: HashMap::new(),
+    }
+}
+
+impl Iterator for TotientChain {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let f = self.current.take()?;
+        if !f.is_one() {
+            self.current = Some(f.totient_with(&mut self.cache));
+        }
+        // φ(n) <= n, so every value fits
+        f.value()
+    }
+}
+
+/// The number of times φ has to be applied to `n` to reach 1, i.e. the length of
+/// `totient_chain(n)` not counting `n` itself.
+///
+/// Panics if `n == 0`.
+///
+/// ```
+/// use primes::phi_iterations_to_one;
+///
+/// assert_eq!(phi_iterations_to_one(1), 0);
+/// assert_eq!(phi_iterations_to_one(2), 1);
+/// assert_eq!(phi_iterations_to_one(100), 6);
+/// ```
+pub fn phi_iterations_to_one(n: u64) -> u32 {
+    totient_chain(n).count() as u32 - 1
+}
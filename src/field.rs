@@ -0,0 +1,232 @@
+//! Arithmetic in the finite field of integers modulo a prime.
+
+use crate::modular::{mul_mod, pow_mod};
+use crate::{factors_uniq, is_prime_u64, Prime};
+
+/// Find a square root of `a` modulo the prime `p` with the Tonelli-Shanks algorithm, or `None` if
+/// `a` is not a square modulo `p`.
+///
+/// Returns the smaller of the two roots `r` and `p - r`.
+///
+/// ```
+/// use primes::{sqrt_mod_prime, Prime};
+///
+/// let p = Prime::try_new(13).unwrap();
+/// assert_eq!(sqrt_mod_prime(10, p), Some(6));
+/// assert_eq!(sqrt_mod_prime(5, p), None);
+/// ```
+pub fn sqrt_mod_prime(a: u64, p: Prime) -> Option<u64> {
+    let p = p.get();
+    let a = a % p;
+    if a == 0 || p == 2 {
+        return Some(a);
+    }
+    if pow_mod(a, (p - 1) / 2, p) != 1 {
+        return None;
+    }
+
+    let root = if p % 4 == 3 {
+        pow_mod(a, (p + 1) / 4, p)
+    } else {
+        let s = (p - 1).trailing_zeros();
+        let q = (p - 1) >> s;
+        let z = (2..p)
+            .find(|&z| pow_mod(z, (p - 1) / 2, p) == p - 1)
+            .unwrap();
+
+        let mut m = s;
+        let mut c = pow_mod(z, q, p);
+        let mut t = pow_mod(a, q, p);
+        let mut r = pow_mod(a, q / 2 + 1, p);
+        while t != 1 {
+            // The least i with t^(2^i) == 1
+            let mut i = 0;
+            let mut t2i = t;
+            while t2i != 1 {
+                t2i = mul_mod(t2i, t2i, p);
+                i += 1;
+            }
+            let b = pow_mod(c, 1 << (m - i - 1), p);
+            m = i;
+            c = mul_mod(b, b, p);
+            t = mul_mod(t, c, p);
+            r = mul_mod(r, b, p);
+        }
+        r
+    };
+    Some(root.min(p - root))
+}
+
+/// Find the smallest prime `p >= min` with `p ≡ 1 (mod 2^order)`, together with a primitive
+/// `2^order`-th root of unity modulo `p`: the pair a number-theoretic transform of length up to
+/// `2^order` needs.
+///
+/// The root is `g^((p - 1) / 2^order)` for the smallest quadratic non-residue `g`, which has the
+/// same order as it would for a full primitive root but doesn't need `p - 1` factored. Returns
+/// `None` if there is no such prime below `2^64`.
+///
+/// ```
+/// use primes::find_ntt_prime;
+///
+/// // 998244353 = 119 * 2^23 + 1
+/// let (p, root) = find_ntt_prime(900_000_000, 23).unwrap();
+/// assert_eq!(p, 998_244_353);
+/// assert_eq!(primes::pow_mod(root, 1 << 23, p), 1);
+/// assert_eq!(primes::pow_mod(root, 1 << 22, p), p - 1);
+/// ```
+pub fn find_ntt_prime(min: u64, order: u32) -> Option<(u64, u64)> {
+    if order >= 64 {
+        return None;
+    }
+    let step = 1u64 << order;
+    // The first k with k * 2^order + 1 >= min, skipping p = 1
+    let below = min.saturating_sub(1);
+    let mut k = ((below >> order) + (below & (step - 1) != 0) as u64).max(1);
+    loop {
+        let p = k.checked_mul(step)?.checked_add(1)?;
+        if is_prime_u64(p) {
+            let field = PrimeField::new(Prime::new_unchecked(p));
+            let g = if p == 2 {
+                1
+            } else {
+                (2..p).find(|&g| field.legendre(g) == -1).unwrap()
+            };
+            return Some((p, field.pow(g, (p - 1) >> order)));
+        }
+        k += 1;
+    }
+}
+
+/**
+The finite field GF(p) of integers modulo a prime `p`.
+
+A `PrimeField` can only be created from a `Prime`, so its methods can rely on every nonzero
+element having an inverse.
+
+```
+use primes::{Prime, PrimeField};
+
+let field = PrimeField::new(Prime::try_new(101).unwrap());
+assert_eq!(field.inv(5), Some(81));
+assert_eq!(field.legendre(5), 1);
+assert_eq!(field.sqrt(5), Some(45));
+assert_eq!(field.primitive_root(), 2);
+```
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PrimeField {
+    p: u64,
+}
+
+impl PrimeField {
+    /// The field of integers modulo `p`
+    pub fn new(p: Prime) -> PrimeField {
+        PrimeField { p: p.get() }
+    }
+
+    /// The prime modulus
+    pub fn modulus(&self) -> u64 {
+        self.p
+    }
+
+    /// `a + b` in the field
+    pub fn add(&self, a: u64, b: u64) -> u64 {
+        let (a, b) = (a % self.p, b % self.p);
+        let (sum, overflowed) = a.overflowing_add(b);
+        if overflowed || sum >= self.p {
+            sum.wrapping_sub(self.p)
+        } else {
+            sum
+        }
+    }
+
+    /// `a * b` in the field
+    pub fn mul(&self, a: u64, b: u64) -> u64 {
+        mul_mod(a, b, self.p)
+    }
+
+    /// `a^exp` in the field
+    pub fn pow(&self, a: u64, exp: u64) -> u64 {
+        pow_mod(a, exp, self.p)
+    }
+
+    /// The multiplicative inverse of `a`, or `None` if `a` is zero in the field
+    pub fn inv(&self, a: u64) -> Option<u64> {
+        if a.is_multiple_of(self.p) {
+            return None;
+        }
+        Some(pow_mod(a, self.p - 2, self.p))
+    }
+
+    /// Invert every element of `values` with a single modular exponentiation, using Montgomery's
+    /// batch inversion trick. Returns `None` if any element is zero in the field.
+    ///
+    /// ```
+    /// use primes::{Prime, PrimeField};
+    ///
+    /// let field = PrimeField::new(Prime::try_new(7).unwrap());
+    /// assert_eq!(field.batch_inv(&[1, 2, 3, 4, 5, 6]), Some(vec![1, 4, 5, 2, 3, 6]));
+    /// assert_eq!(field.batch_inv(&[1, 7]), None);
+    /// ```
+    pub fn batch_inv(&self, values: &[u64]) -> Option<Vec<u64>> {
+        // prefix[i] is the product of values[..i]
+        let mut prefix = Vec::with_capacity(values.len() + 1);
+        prefix.push(1);
+        for &v in values {
+            if v % self.p == 0 {
+                return None;
+            }
+            prefix.push(self.mul(*prefix.last().unwrap(), v));
+        }
+
+        let mut inverses = vec![0; values.len()];
+        // The inverse of the product of values[..=i], updated as 'i' counts down
+        let mut running = self.inv(*prefix.last().unwrap())?;
+        for i in (0..values.len()).rev() {
+            inverses[i] = self.mul(running, prefix[i]);
+            running = self.mul(running, values[i]);
+        }
+        Some(inverses)
+    }
+
+    /// The Legendre symbol `(a / p)`: 0 if `a` is zero in the field, 1 if `a` is a nonzero
+    /// square, and -1 otherwise
+    pub fn legendre(&self, a: u64) -> i8 {
+        if a.is_multiple_of(self.p) {
+            return 0;
+        }
+        if self.p == 2 {
+            return 1;
+        }
+        if pow_mod(a, (self.p - 1) / 2, self.p) == 1 {
+            1
+        } else {
+            -1
+        }
+    }
+
+    /// A square root of `a`, or `None` if `a` isn't a square. See `sqrt_mod_prime`.
+    pub fn sqrt(&self, a: u64) -> Option<u64> {
+        sqrt_mod_prime(a, Prime::new_unchecked(self.p))
+    }
+
+    /// The smallest primitive root: a generator of the multiplicative group of the field.
+    ///
+    /// This needs the prime factors of `p - 1`, found by trial division.
+    pub fn primitive_root(&self) -> u64 {
+        if self.p == 2 {
+            return 1;
+        }
+        let order = self.p - 1;
+        let factors = factors_uniq(order);
+        (2..self.p)
+            .find(|&g| factors.iter().all(|&q| pow_mod(g, order / q, self.p) != 1))
+            .unwrap()
+    }
+}
+
+impl From<Prime> for PrimeField {
+    fn from(p: Prime) -> PrimeField {
+        PrimeField::new(p)
+    }
+}
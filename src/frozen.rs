@@ -0,0 +1,105 @@
+//! An immutable, cheaply cloneable snapshot of found primes.
+
+use std::ops::Index;
+use std::sync::Arc;
+
+/**
+An immutable list of consecutive primes starting from 2, created by `PrimeSet::freeze`.
+
+Cloning only bumps a reference count, so a single snapshot can be shared across threads after
+the "grow" phase is done, and queried without needing `&mut` access:
+
+```
+use primes::{PrimeSet, Sieve};
+use std::thread;
+
+let mut pset = Sieve::new();
+pset.find(10_000);
+let frozen = pset.freeze();
+
+let handles: Vec<_> = (0..4)
+    .map(|k| {
+        let frozen = frozen.clone();
+        thread::spawn(move || frozen.rank(2_500 * k))
+    })
+    .collect();
+let ranks: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+assert_eq!(ranks, vec![Some(0), Some(367), Some(669), Some(950)]);
+```
+**/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FrozenPrimes {
+    primes: Arc<[u64]>,
+}
+
+impl FrozenPrimes {
+    /// Freeze a list of consecutive primes starting from 2
+    pub(crate) fn new(primes: &[u64]) -> FrozenPrimes {
+        FrozenPrimes {
+            primes: primes.into(),
+        }
+    }
+
+    /// All primes in the snapshot
+    pub fn as_slice(&self) -> &[u64] {
+        &self.primes
+    }
+
+    /// Number of primes in the snapshot
+    pub fn len(&self) -> usize {
+        self.primes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.primes.is_empty()
+    }
+
+    /// The largest prime in the snapshot
+    pub fn last(&self) -> Option<u64> {
+        self.primes.last().copied()
+    }
+
+    /// Get the prime at the given index, if the snapshot includes it
+    pub fn get(&self, index: usize) -> Option<u64> {
+        self.primes.get(index).copied()
+    }
+
+    /// Check whether `n` is prime, or return `None` if `n` is beyond the largest prime in the
+    /// snapshot
+    pub fn is_prime_cached(&self, n: u64) -> Option<bool> {
+        if n > self.last()? {
+            return None;
+        }
+        Some(self.primes.binary_search(&n).is_ok())
+    }
+
+    /// Count the primes less than or equal to `n`, or return `None` if `n` is beyond the largest
+    /// prime in the snapshot
+    pub fn rank(&self, n: u64) -> Option<usize> {
+        if n > self.last().unwrap_or(0) {
+            return None;
+        }
+        Some(self.primes.partition_point(|&p| p <= n))
+    }
+
+    /// Find the next largest prime from a number, if it is in the snapshot
+    ///
+    /// Returns `(idx, prime)`, the same as `PrimeSet::find_vec`.
+    pub fn find(&self, n: u64) -> Option<(usize, u64)> {
+        let ix = self.primes.partition_point(|&p| p < n);
+        self.get(ix).map(|p| (ix, p))
+    }
+}
+
+impl AsRef<[u64]> for FrozenPrimes {
+    fn as_ref(&self) -> &[u64] {
+        &self.primes
+    }
+}
+
+impl Index<usize> for FrozenPrimes {
+    type Output = u64;
+    fn index(&self, index: usize) -> &u64 {
+        &self.primes[index]
+    }
+}
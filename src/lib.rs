@@ -65,17 +65,161 @@ case, but slower in the long term as they do not use any caching of primes.
 use std::cmp::Ordering::{Equal, Greater, Less};
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
+use std::error;
+use std::fmt;
 use std::ops::Index;
 use std::slice;
 
+use crate::segment::SegmentedPrimes;
+
+mod arith;
+pub mod digits;
+mod external;
+mod factorization;
+mod field;
+mod frozen;
+mod modular;
+mod output;
+mod primality;
+mod prime;
+mod proof;
+mod query;
+mod random;
+mod segment;
+mod shared;
+pub mod small;
+pub mod special;
+pub mod viz;
+mod wheel;
+
+#[cfg(feature = "bigint")]
+pub use crate::arith::product_of_primes_below;
+pub use crate::arith::{
+    achilles_numbers, classify_aliquot, divisor_summatory, highly_composite_numbers, is_achilles,
+    is_powerful, is_practical, is_prime_power, liouville, log_product_of_primes_below, mangoldt,
+    max_below_with_factors, mobius, omega_sieve, powerful_numbers, prime_powers, prime_zeta,
+    prod_one_minus_inv_p_below, product_of_primes_mod, sum_reciprocal_primes_below,
+    totient_summatory, AchillesNumbers, Aliquot, HighlyComposite, PowerfulNumbers, PrimePowers,
+};
+pub use crate::external::{collect_into_primeset, ExternalPrimes, IterPrimes};
+pub use crate::factorization::{
+    coprime_to, count_coprime_below, count_representations_as_product, divisor_pairs, factor_tree,
+    gcd_all, lcm_all, multiplicative_partitions, ordered_factorizations, phi_iterations_to_one,
+    reduce_fraction, totient_chain, CoprimeTo, DivisorPairs, FactorTree, FactoredRatio,
+    Factorization, TotientChain,
+};
+pub use crate::field::{find_ntt_prime, sqrt_mod_prime, PrimeField};
+pub use crate::frozen::FrozenPrimes;
+pub use crate::modular::{factorial_mod, mul_mod, pow_mod, wilson_quotient, ModInt};
+pub use crate::output::{
+    read_primes, read_primes_iter, write_primes, PrimeFileIter, PrimeFormat, ReadPrimesError,
+};
+#[cfg(feature = "bigint")]
+pub use crate::primality::is_prime_bpsw_big;
+pub use crate::primality::{
+    has_small_factor, is_prime_bpsw, is_prime_u64, miller_rabin, Bpsw, Primality, PrimalityTest,
+    StagedMillerRabin, TrialDivisionTest, WitnessSet,
+};
+pub use crate::prime::Prime;
+pub use crate::proof::{
+    prove_prime_n_minus_1, prove_prime_n_plus_1, LucasCertificate, PocklingtonCertificate,
+};
+pub use crate::query::PrimeQuery;
+pub use crate::random::is_blum_prime;
+#[cfg(all(feature = "rand", feature = "bigint"))]
+pub use crate::random::random_strong_prime_big;
+#[cfg(feature = "rand")]
+pub use crate::random::{
+    random_blum_integer, random_strong_prime, random_strong_prime_with, sample_primes_in,
+};
+pub use crate::segment::{
+    brun_constant_partial, for_each_prime_in, gap_after, gap_histogram_below, iterate_index,
+    large_primes_from, liouville_range, mangoldt_range, max_gap_below, maximal_gaps,
+    maximal_gaps_below, mobius_range, prime_count_in_range, prime_indexed_primes, prime_pi,
+    prime_pi_sublinear, spf_range, squarefree_in_range, totient_range, twin_prime_count,
+    verify_goldbach, IteratedPrimes, LargePrimes, MangoldtRange, MaximalGaps, PiCheckpoints,
+    SegmentIter, SpfRange, TotientRange,
+};
+pub use crate::shared::{SharedPrimes, SharedPrimesReader};
+pub use crate::wheel::{Wheel, WheelCandidates, WheelFilter};
+
 pub trait PrimeSetBasics {
     /// Finds one more prime, and adds it to the list
     fn expand(&mut self);
 
     /// Return all primes found so far as a slice
     fn list(&self) -> &[u64];
+
+    /// Finds one more prime, adds it to the list, and returns it
+    ///
+    /// ```
+    /// use primes::{PrimeSetBasics, TrialDivision};
+    ///
+    /// let mut pset = TrialDivision::new();
+    /// assert_eq!(pset.list(), &[2, 3]);
+    /// assert_eq!(pset.expand_next(), 5);
+    /// assert_eq!(pset.expand_next(), 7);
+    /// ```
+    fn expand_next(&mut self) -> u64 {
+        self.expand();
+        *self.list().last().expect("expand should add a prime")
+    }
+
+    /// The largest number that has been settled as prime or not: every prime up to and
+    /// including it is in `list()`.
+    ///
+    /// By default this is the largest prime found, or 0 if there are none; generators that look
+    /// past their last prime, like `Sieve`, can report more.
+    fn largest_guaranteed_checked(&self) -> u64 {
+        self.list().last().copied().unwrap_or(0)
+    }
+
+    /// The largest prime this generator can ever find. `PrimeSet::try_find` and
+    /// `PrimeSet::try_get` return an error rather than ask for anything past it.
+    ///
+    /// By default this is `LARGEST_U64_PRIME`; backends with a fixed supply of primes, such as a
+    /// table or a memory-mapped file, should report their last one.
+    fn expandable_limit(&self) -> u64 {
+        LARGEST_U64_PRIME
+    }
+
+    /// Whether every number in `list()` is known to be prime, because the generator found or
+    /// checked it itself. `PrimeSetIter::typed` tests each prime from a set that isn't before
+    /// wrapping it in a `Prime`.
+    ///
+    /// By default this is `false`; `Sieve` and `TrialDivision` report `true`.
+    fn primes_verified(&self) -> bool {
+        false
+    }
+
+    /// Find primes until the largest one found is at least `limit`.
+    ///
+    /// By default this calls `expand_next` until it gets there; generators with a faster way of
+    /// covering a long stretch at once, like `Sieve`, can use it instead.
+    fn expand_to(&mut self, limit: u64) {
+        let mut largest = self.list().last().copied().unwrap_or(0);
+        while largest < limit {
+            largest = self.expand_next();
+        }
+    }
 }
 
+/// The `PrimeSet` used by this crate's free functions when they need to generate primes, and a
+/// reasonable choice for downstream code that doesn't care which backend it gets.
+///
+/// This is `Sieve`, unless the `default-trialdivision` feature is enabled, in which case it is
+/// `TrialDivision`.
+#[cfg(not(feature = "default-trialdivision"))]
+pub type DefaultPrimeSet = Sieve;
+
+/// The `PrimeSet` used by this crate's free functions when they need to generate primes, and a
+/// reasonable choice for downstream code that doesn't care which backend it gets.
+///
+/// This is `TrialDivision`, because the `default-trialdivision` feature is enabled; otherwise it
+/// is `Sieve`.
+#[cfg(feature = "default-trialdivision")]
+pub type DefaultPrimeSet = TrialDivision;
+
 /**
 A prime generator, using the Trial Division method.
 
@@ -96,6 +240,23 @@ struct Wheel30 {
 }
 
 impl Wheel30 {
+    // A wheel whose `next` value is the first wheel value greater than `n`
+    fn after(n: u64) -> Wheel30 {
+        let base = n - n % 30;
+        match WHEEL30.iter().position(|&r| base + r > n) {
+            Some(ix) => Wheel30 { base, ix },
+            None => Wheel30 {
+                base: base + 30,
+                ix: 0,
+            },
+        }
+    }
+
+    // The value `next` will return, without moving
+    fn peek(&self) -> u64 {
+        self.base + WHEEL30[self.ix]
+    }
+
     pub fn next(&mut self) -> u64 {
         let value = self.base + WHEEL30[self.ix];
         self.ix += 1;
@@ -105,6 +266,17 @@ impl Wheel30 {
         }
         value
     }
+
+    // The inverse of `next`: steps back one position and returns the value there
+    fn prev(&mut self) -> u64 {
+        if self.ix == 0 {
+            self.ix = WHEEL30.len() - 1;
+            self.base -= 30;
+        } else {
+            self.ix -= 1;
+        }
+        self.base + WHEEL30[self.ix]
+    }
 }
 
 /**
@@ -127,6 +299,44 @@ pub struct Sieve {
     sieve: BinaryHeap<Reverse<(u64, u64)>>,
 }
 
+/**
+The complete internal state of a `Sieve`, created by `Sieve::checkpoint`.
+
+Restoring with `Sieve::restore` picks up exactly where the checkpointed sieve left off, without
+re-sieving to rebuild the pending composites. With the `serde` feature enabled, this can be
+serialized to save long runs to disk.
+
+`Sieve::restore` trusts these fields, so they should only come from `Sieve::checkpoint`.
+**/
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SieveState {
+    /// All primes found so far
+    pub primes: Vec<u64>,
+    /// The multiple of 30 the mod-30 wheel is currently at
+    pub wheel_base: u64,
+    /// The index of the wheel's next spoke, from 0 to 7
+    pub wheel_index: usize,
+    /// Pairs `(composite, prime)` of the next multiple to cross off for each sieving prime
+    pub pending: Vec<(u64, u64)>,
+}
+
+/// The error returned when a `PrimeSet` is asked for a prime past its
+/// `PrimeSetBasics::expandable_limit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded {
+    /// The largest prime the `PrimeSet` can find
+    pub limit: u64,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no primes past {} are available", self.limit)
+    }
+}
+
+impl error::Error for LimitExceeded {}
+
 /// An iterator over generated primes. Created by `PrimeSet::iter` or
 /// `PrimeSet::generator`
 pub struct PrimeSetIter<'a, P: PrimeSet> {
@@ -140,11 +350,42 @@ impl TrialDivision {
     pub fn new() -> TrialDivision {
         TrialDivision { lst: vec![2, 3] }
     }
+
+    /// Resume from a list of consecutive primes starting with 2, as produced by `list()` of any
+    /// `PrimeSet`. Lists shorter than `[2, 3]` give the same result as `TrialDivision::new()`.
+    ///
+    /// Panics if `primes` doesn't start with 2, 3 or isn't strictly increasing. The remaining
+    /// values are trusted to be the consecutive primes they claim to be, and `PrimeSetIter::typed`
+    /// relies on that; converting with `From` checks them instead.
+    pub fn from_primes(primes: Vec<u64>) -> TrialDivision {
+        let td = TrialDivision::new();
+        let n = primes.len().min(td.lst.len());
+        assert!(primes[..n] == td.lst[..n], "primes must start with 2, 3");
+        assert!(
+            primes.windows(2).all(|w| w[0] < w[1]),
+            "primes must be strictly increasing"
+        );
+        if primes.len() <= td.lst.len() {
+            return td;
+        }
+        TrialDivision { lst: primes }
+    }
+
+    /// Start from a copy of `seed`, a list of consecutive primes starting with 2, such as one
+    /// made by `static_primes!`. See `TrialDivision::from_primes`.
+    pub fn with_seed_primes(seed: &[u64]) -> TrialDivision {
+        TrialDivision::from_primes(seed.to_vec())
+    }
 }
 
 impl PrimeSetBasics for TrialDivision {
     /// Finds one more prime, and adds it to the list
     fn expand(&mut self) {
+        self.expand_next();
+    }
+
+    /// Finds one more prime, adds it to the list, and returns it
+    fn expand_next(&mut self) -> u64 {
         let mut l: u64 = self.lst.last().unwrap() + 2;
         let mut remainder = 0;
         loop {
@@ -157,7 +398,7 @@ impl PrimeSetBasics for TrialDivision {
 
             if remainder != 0 {
                 self.lst.push(l);
-                break;
+                return l;
             };
 
             l += 2;
@@ -168,6 +409,10 @@ impl PrimeSetBasics for TrialDivision {
     fn list(&self) -> &[u64] {
         &self.lst[..]
     }
+
+    fn primes_verified(&self) -> bool {
+        true
+    }
 }
 
 impl Sieve {
@@ -180,6 +425,222 @@ impl Sieve {
         }
     }
 
+    /// Resume sieving from a list of consecutive primes starting with 2, as produced by
+    /// `list()` of any `PrimeSet`.
+    ///
+    /// The internal sieve state is rebuilt so that the next call to `expand` finds the prime
+    /// after the last one in `primes`, without redoing any of the work that produced them. Lists
+    /// shorter than `[2, 3, 5]` give the same result as `Sieve::new()`.
+    ///
+    /// Panics if `primes` doesn't start with 2, 3, 5 or isn't strictly increasing. The remaining
+    /// values are trusted to be the consecutive primes they claim to be, and `PrimeSetIter::typed`
+    /// relies on that; converting with `From` checks them instead.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, PrimeSetBasics, Sieve, TrialDivision};
+    ///
+    /// let mut td = TrialDivision::new();
+    /// td.find(1_000);
+    /// let mut sieve = Sieve::from_primes(td.list().to_vec());
+    /// assert_eq!(sieve.get(1_000), 7_927);
+    /// ```
+    pub fn from_primes(primes: Vec<u64>) -> Sieve {
+        let mut sieve = Sieve::new();
+        let n = primes.len().min(sieve.primes.len());
+        assert!(
+            primes[..n] == sieve.primes[..n],
+            "primes must start with 2, 3, 5"
+        );
+        assert!(
+            primes.windows(2).all(|w| w[0] < w[1]),
+            "primes must be strictly increasing"
+        );
+        if primes.len() <= sieve.primes.len() {
+            return sieve;
+        }
+
+        sieve.primes = primes;
+        sieve.resume_from_list();
+        sieve
+    }
+
+    /// Find every prime up to `limit` (and the first one past it), sieving disjoint stretches of
+    /// the range on rayon's thread pool and appending them to the list in order.
+    ///
+    /// The result is the same as `expand_to`, and the sieve carries on incrementally from there
+    /// afterwards.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, PrimeSetBasics, Sieve};
+    ///
+    /// let mut sieve = Sieve::new();
+    /// sieve.expand_to_parallel(1_000_000);
+    /// assert_eq!(sieve.last_found(), Some(1_000_003));
+    /// assert_eq!(sieve.len(), 78_499);
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn expand_to_parallel(&mut self, limit: u64) {
+        use rayon::prelude::*;
+
+        let last = *self.primes.last().unwrap();
+        if limit > last {
+            let width = limit - last;
+            let chunks = (rayon::current_num_threads() as u64 * 4)
+                .min(width / PARALLEL_CHUNK_MIN)
+                .max(1);
+            let step = width / chunks + 1;
+            let found: Vec<Vec<u64>> = (0..chunks)
+                .into_par_iter()
+                .map(|i| {
+                    let lo = last + 1 + i * step;
+                    let hi = (lo + step - 1).min(limit);
+                    SegmentedPrimes::new(lo, hi).collect()
+                })
+                .collect();
+            for chunk in found {
+                self.primes.extend(chunk);
+            }
+            self.resume_from_list();
+        }
+        while *self.primes.last().unwrap() < limit {
+            self.expand();
+        }
+    }
+
+    // Rebuild the pending composites and the wheel from `primes` alone, so that sieving picks up
+    // just after the last prime in it
+    fn resume_from_list(&mut self) {
+        let last = *self.primes.last().unwrap();
+        let pending: Vec<Reverse<(u64, u64)>> = self.primes[3..]
+            .iter()
+            .map(|&p| {
+                if p * p > last {
+                    return Reverse((p * p, p));
+                }
+                // The smallest odd multiple of 'p' past 'last'
+                let mut m = last / p + 1;
+                if m.is_multiple_of(2) {
+                    m += 1;
+                }
+                Reverse((m * p, p))
+            })
+            .collect();
+        self.sieve = BinaryHeap::from(pending);
+        self.wheel = Wheel30::after(last);
+    }
+
+    /// Capture the full state of this sieve, including its pending composites
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut sieve = Sieve::new();
+    /// sieve.find(10_000);
+    /// let state = sieve.checkpoint();
+    ///
+    /// let mut restored = Sieve::restore(state);
+    /// assert_eq!(restored.get(5_000), sieve.get(5_000));
+    /// ```
+    pub fn checkpoint(&self) -> SieveState {
+        SieveState {
+            primes: self.primes.clone(),
+            wheel_base: self.wheel.base,
+            wheel_index: self.wheel.ix,
+            pending: self.sieve.iter().map(|&Reverse(entry)| entry).collect(),
+        }
+    }
+
+    /// Recreate a sieve from a state captured by `checkpoint`
+    ///
+    /// Panics if `state.wheel_index` is out of range.
+    pub fn restore(state: SieveState) -> Sieve {
+        assert!(state.wheel_index < WHEEL30.len(), "invalid wheel index");
+        Sieve {
+            primes: state.primes,
+            wheel: Wheel30 {
+                base: state.wheel_base,
+                ix: state.wheel_index,
+            },
+            sieve: state.pending.into_iter().map(Reverse).collect(),
+        }
+    }
+
+    /// Start from a copy of `seed`, a list of consecutive primes starting with 2, such as one
+    /// made by `static_primes!`. See `Sieve::from_primes`.
+    ///
+    /// ```
+    /// use primes::{static_primes, PrimeSet, Sieve};
+    ///
+    /// static SEED: [u64; 100] = static_primes!(100);
+    /// let mut sieve = Sieve::with_seed_primes(&SEED);
+    /// assert_eq!(sieve.len(), 100);
+    /// assert_eq!(sieve.get(100), 547);
+    /// ```
+    pub fn with_seed_primes(seed: &[u64]) -> Sieve {
+        Sieve::from_primes(seed.to_vec())
+    }
+
+    /// Take on the progress of another `Sieve`, if it has found more primes than this one.
+    ///
+    /// Every `Sieve` holds a list of consecutive primes starting from 2, so the one with more
+    /// primes has strictly more information, and this keeps it. This lets independent workers
+    /// share whichever cache has progressed furthest.
+    ///
+    /// Panics if the two lists differ where they overlap, which means they can't both be the
+    /// consecutive primes from 2.
+    pub fn merge(&mut self, other: &Sieve) {
+        let n = self.primes.len().min(other.primes.len());
+        assert!(
+            self.primes[..n] == other.primes[..n],
+            "merged sieves must share their primes"
+        );
+        if other.primes.len() > self.primes.len() {
+            self.clone_from(other);
+        }
+    }
+
+    /// The number of sieving primes waiting to cross off their next composite. Every prime found
+    /// so far past 2, 3 and 5, which the wheel skips, has one.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut sieve = Sieve::new();
+    /// sieve.get(99);
+    /// assert_eq!(sieve.pending_composites(), 100 - 3);
+    /// ```
+    pub fn pending_composites(&self) -> usize {
+        self.sieve.len()
+    }
+
+    /// The next candidate the mod-30 wheel will offer to `expand`. Candidates are the numbers
+    /// coprime to 30, so this is always 1, 7, 11, 13, 17, 19, 23 or 29 mod 30.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut sieve = Sieve::new();
+    /// assert_eq!(sieve.get(9), 29);
+    /// assert_eq!(sieve.current_wheel_position(), 31);
+    /// ```
+    pub fn current_wheel_position(&self) -> u64 {
+        self.wheel.peek()
+    }
+
+    /// The largest number the sieve has settled: every prime up to and including it is in
+    /// `list()`, and nothing larger has been looked at yet.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut sieve = Sieve::new();
+    /// assert_eq!(sieve.get(9), 29);
+    /// assert_eq!(sieve.largest_checked(), 30);
+    /// ```
+    pub fn largest_checked(&self) -> u64 {
+        self.wheel.peek() - 1
+    }
+
     // insert a prime and its composite. If the composite is already occupied, we'll increase
     // the composite by prime and put it there, repeating as necessary.
     fn insert(&mut self, prime: u64, composite: u64) {
@@ -190,13 +651,18 @@ impl Sieve {
 impl PrimeSetBasics for Sieve {
     /// Finds one more prime, and adds it to the list
     fn expand(&mut self) {
+        self.expand_next();
+    }
+
+    /// Finds one more prime, adds it to the list, and returns it
+    fn expand_next(&mut self) -> u64 {
         let mut nextp = self.wheel.next();
         loop {
             let (composite, factor) = match self.sieve.peek() {
                 None => {
                     self.insert(nextp, nextp * nextp);
                     self.primes.push(nextp);
-                    return;
+                    return nextp;
                 }
                 Some(&Reverse(v)) => v,
             };
@@ -215,7 +681,7 @@ impl PrimeSetBasics for Sieve {
                     // nextp is prime!
                     self.insert(nextp, nextp * nextp);
                     self.primes.push(nextp);
-                    return;
+                    return nextp;
                 }
             }
         }
@@ -225,6 +691,84 @@ impl PrimeSetBasics for Sieve {
     fn list(&self) -> &[u64] {
         &self.primes[..]
     }
+
+    /// The same as `Sieve::largest_checked`
+    fn largest_guaranteed_checked(&self) -> u64 {
+        self.largest_checked()
+    }
+
+    fn primes_verified(&self) -> bool {
+        true
+    }
+
+    /// When `PI_POWERS_OF_TEN` shows that there are many primes to find before `limit`, they
+    /// are found with a segmented sieve in one pass, and the incremental sieve is rebuilt to carry
+    /// on from there. Otherwise, they are found one at a time as usual.
+    fn expand_to(&mut self, limit: u64) {
+        let last = *self.primes.last().unwrap();
+        let (_, pi_floor) = pi_floor_power_of_ten(limit);
+        if limit > last && pi_floor >= self.primes.len() as u64 + SIEVE_JUMP_MIN_PRIMES {
+            self.primes
+                .reserve((pi_floor - self.primes.len() as u64) as usize);
+            self.primes.extend(SegmentedPrimes::new(last + 1, limit));
+            self.resume_from_list();
+        }
+        while *self.primes.last().unwrap() < limit {
+            self.expand();
+        }
+    }
+}
+
+/// π(10^k), the number of primes up to each power of ten that fits in a `u64`
+const PI_POWERS_OF_TEN: [u64; 20] = [
+    0,
+    4,
+    25,
+    168,
+    1_229,
+    9_592,
+    78_498,
+    664_579,
+    5_761_455,
+    50_847_534,
+    455_052_511,
+    4_118_054_813,
+    37_607_912_018,
+    346_065_536_839,
+    3_204_941_750_802,
+    29_844_570_422_669,
+    279_238_341_033_925,
+    2_623_557_157_654_233,
+    24_739_954_287_740_860,
+    234_057_667_276_344_607,
+];
+
+/// The fewest values `Sieve::expand_to_parallel` gives each thread to sieve
+#[cfg(feature = "rayon")]
+const PARALLEL_CHUNK_MIN: u64 = 1 << 20;
+
+/// How many primes `Sieve::expand_to` must be sure of finding before it switches to a segmented
+/// sieve
+const SIEVE_JUMP_MIN_PRIMES: u64 = 1 << 12;
+
+// The largest power of ten at most `n` (or 1, for `n == 0`), and the number of primes up to it
+fn pi_floor_power_of_ten(n: u64) -> (u64, u64) {
+    let k = PI_POWERS_OF_TEN.len() - 1;
+    let k = (0..=k).rfind(|&k| 10u64.pow(k as u32) <= n).unwrap_or(0);
+    (10u64.pow(k as u32), PI_POWERS_OF_TEN[k])
+}
+
+// A number no larger than the `n`th prime, counting 2 as the first: the larger of the last power
+// of ten with fewer than `n` primes up to it, and Dusart's bound `n (ln n + ln ln n - 1)`
+fn nth_prime_lower_bound(n: u64) -> u64 {
+    let k = PI_POWERS_OF_TEN.partition_point(|&pi| pi < n) - 1;
+    let power = 10u64.pow(k as u32);
+    if n < 2 {
+        return power;
+    }
+    let x = n as f64;
+    let dusart = x * (x.ln() + x.ln().ln() - 1.0);
+    power.max(dusart as u64)
 }
 
 pub trait PrimeSet: PrimeSetBasics + Sized {
@@ -237,8 +781,13 @@ pub trait PrimeSet: PrimeSetBasics + Sized {
         self.list().is_empty()
     }
 
+    /// The largest prime found so far, or `None` if there are none
+    fn last_found(&self) -> Option<u64> {
+        self.list().last().copied()
+    }
+
     /// Iterator over all primes not yet found
-    fn generator(&mut self) -> PrimeSetIter<Self> {
+    fn generator(&mut self) -> PrimeSetIter<'_, Self> {
         let myn = self.len();
         PrimeSetIter {
             p: self,
@@ -249,7 +798,7 @@ pub trait PrimeSet: PrimeSetBasics + Sized {
 
     /// Iterator over all primes, starting with 2. If you don't care about the "state" of the
     /// `PrimeSet`, this is what you want!
-    fn iter(&mut self) -> PrimeSetIter<Self> {
+    fn iter(&mut self) -> PrimeSetIter<'_, Self> {
         PrimeSetIter {
             p: self,
             n: 0,
@@ -257,27 +806,162 @@ pub trait PrimeSet: PrimeSetBasics + Sized {
         }
     }
 
+    /// Iterator over all primes paired with their index, starting with `(0, 2)`.
+    ///
+    /// The index comes from the position in the list of found primes, so unlike
+    /// `iter().enumerate()`, it stays correct after `skip` or when starting from `generator()`
+    /// (see `PrimeSetIter::indexed`).
+    fn iter_indexed(&mut self) -> IndexedPrimeSetIter<'_, Self> {
+        self.iter().indexed()
+    }
+
+    /// Iterator over all primes paired with their squares, starting with `(2, Some(4))`.
+    ///
+    /// The square is `None` once it no longer fits in a `u64`, which also means it is larger than
+    /// any `u64`: loops that stop when `p * p > n` can stop on `None` as well, without
+    /// overflowing for primes past 2^32.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// let first: Vec<(u64, Option<u64>)> = pset.iter_with_squares().take(3).collect();
+    /// assert_eq!(first, vec![(2, Some(4)), (3, Some(9)), (5, Some(25))]);
+    /// ```
+    fn iter_with_squares(&mut self) -> PrimeSquaresIter<'_, Self> {
+        self.iter().map(|p| (p, p.checked_mul(p)))
+    }
+
+    /// Iterator over every run of `K` consecutive primes, as arrays, starting with the run that
+    /// begins at 2. This is `slice::windows` over the (unbounded) list of primes.
+    ///
+    /// Panics if `K == 0`.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// // Prime triplets of the form (p, p + 2, p + 6)
+    /// let triplets: Vec<[u64; 3]> = pset
+    ///     .windows::<3>()
+    ///     .filter(|&[a, b, c]| b == a + 2 && c == a + 6)
+    ///     .take(3)
+    ///     .collect();
+    /// assert_eq!(triplets, vec![[5, 7, 11], [11, 13, 17], [17, 19, 23]]);
+    /// ```
+    fn windows<const K: usize>(&mut self) -> PrimeWindows<'_, Self, K> {
+        assert!(K > 0, "window length must be at least 1");
+        PrimeWindows { p: self, n: 0 }
+    }
+
+    /// Start building a query over a range or class of primes; see `PrimeQuery`
+    fn primes(&mut self) -> PrimeQuery<'_, Self> {
+        PrimeQuery::new(self)
+    }
+
+    /// Iterator over all primes less than or equal to `limit`, generating them first if needed.
+    ///
+    /// Since all the primes are known before iteration starts, this iterator knows its length
+    /// and can be reversed.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// let primes = pset.iter_to(100);
+    /// assert_eq!(primes.len(), 25);
+    /// let largest: Vec<u64> = primes.rev().take(3).collect();
+    /// assert_eq!(largest, vec![97, 89, 83]);
+    /// ```
+    fn iter_to(&mut self, limit: u64) -> std::iter::Copied<slice::Iter<'_, u64>> {
+        self.expand_to(limit);
+        let count = self.list().partition_point(|&p| p <= limit);
+        self.list()[..count].iter().copied()
+    }
+
     /// Iterator over just the primes found so far
-    fn iter_vec(&self) -> slice::Iter<u64> {
+    fn iter_vec(&self) -> slice::Iter<'_, u64> {
         self.list().iter()
     }
 
+    /// Take an immutable snapshot of the primes found so far, which can be cheaply cloned and
+    /// shared across threads
+    fn freeze(&self) -> FrozenPrimes {
+        FrozenPrimes::new(self.list())
+    }
+
     /// Find the next largest prime from a number
     ///
     /// Returns `(idx, prime)`
     ///
     /// Note that if `n` is prime, then the output will be `(idx, n)`
     fn find(&mut self, n: u64) -> (usize, u64) {
-        while n > *(self.list().last().unwrap_or(&0)) {
-            self.expand();
+        self.try_find(n).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Find the next largest prime from a number, or an error if it would be past
+    /// `expandable_limit()`
+    ///
+    /// Returns `(idx, prime)`, the same as `find`.
+    ///
+    /// ```
+    /// use primes::{ExternalPrimes, LimitExceeded, PrimeSet};
+    ///
+    /// let mut pset = ExternalPrimes::from_slice(&[2, 3, 5, 7, 11]);
+    /// assert_eq!(pset.try_find(8), Ok((4, 11)));
+    /// assert_eq!(pset.try_find(12), Err(LimitExceeded { limit: 11 }));
+    /// ```
+    fn try_find(&mut self, n: u64) -> Result<(usize, u64), LimitExceeded> {
+        if n > self.last_found().unwrap_or(0) {
+            let limit = self.expandable_limit();
+            if n > limit {
+                return Err(LimitExceeded { limit });
+            }
+            self.expand_to(n);
         }
-        self.find_vec(n).unwrap()
+        Ok(self.find_vec(n).unwrap())
+    }
+
+    /// Find the smallest prime that is at least `n`, like `find`, but without generating any
+    /// primes: up to `largest_guaranteed_checked()` it is looked up in the list, and past that,
+    /// candidates are checked with `tester`. Returns `None` if there is no such `u64`.
+    ///
+    /// With a probabilistic tester, a result past the list may be a pseudoprime.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve, StagedMillerRabin};
+    ///
+    /// let mut pset = Sieve::new();
+    /// pset.find(100);
+    /// assert_eq!(pset.find_with(90, &StagedMillerRabin), Some(97));
+    /// assert_eq!(pset.find_with(1_000_000_000_000, &StagedMillerRabin), Some(1_000_000_000_039));
+    /// assert_eq!(pset.find_with(u64::MAX, &StagedMillerRabin), None);
+    /// ```
+    fn find_with<T: PrimalityTest>(&self, n: u64, tester: &T) -> Option<u64> {
+        if n <= self.largest_guaranteed_checked() {
+            if let Some((_, p)) = self.find_vec(n) {
+                return Some(p);
+            }
+        }
+        next_prime_with(n.saturating_sub(1), tester)
     }
 
     /// Check if a number is prime
     ///
-    /// Note that this only requires primes up to `n.sqrt()` to be generated, and will generate
-    /// them as necessary on its own.
+    /// Numbers up to `largest_guaranteed_checked()` are looked up in the list. Past that, numbers
+    /// below 2^20 are checked by trial division, generating primes up to `n.sqrt()` as needed,
+    /// and anything larger gets the deterministic Miller-Rabin test of `is_prime_u64`, which
+    /// needs no primes from the set and takes microseconds for any `u64`.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, PrimeSetBasics, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// assert!(pset.is_prime(18_446_744_073_709_551_557));
+    /// assert!(!pset.is_prime(4_294_967_291 * 4_294_967_279));
+    /// // Nothing was generated to answer those
+    /// assert!(pset.list().len() < 100);
+    /// ```
     fn is_prime(&mut self, n: u64) -> bool {
         if n <= 1 {
             return false;
@@ -285,24 +969,33 @@ pub trait PrimeSet: PrimeSetBasics + Sized {
         if n == 2 {
             return true;
         } // otherwise we get 2 % 2 == 0!
-        for m in self.iter() {
-            if n % m == 0 {
+        if n <= self.largest_guaranteed_checked() {
+            return self.find_exact(n).is_some();
+        }
+        if n >= TRIAL_DIVISION_LIMIT {
+            return is_prime_u64(n);
+        }
+        for (m, square) in self.iter_with_squares() {
+            if n.is_multiple_of(m) {
                 return false;
             };
-            if m * m > n {
-                return true;
-            };
+            match square {
+                Some(square) if square <= n => (),
+                _ => return true,
+            }
         }
         unreachable!("This iterator should not be empty.");
     }
 
     /// Find the next largest prime from a number, if it is within the already-found list
     ///
-    /// Returns `(idx, prime)`
+    /// Returns `(idx, prime)` for the smallest found prime that is at least `n`, or `None` if `n`
+    /// is larger than every prime found so far. Nothing is generated.
     ///
-    /// Note that if `n` is prime, then the output will be `(idx, n)`
+    /// Note that if `n` is prime, then the output will be `(idx, n)`. To look up `n` itself, use
+    /// `find_exact`; for the primes on either side of `n`, use `find_neighbors`.
     fn find_vec(&self, n: u64) -> Option<(usize, u64)> {
-        if n > *(self.list().last().unwrap_or(&0)) {
+        if n > self.last_found().unwrap_or(0) {
             return None;
         }
 
@@ -325,12 +1018,162 @@ pub trait PrimeSet: PrimeSetBasics + Sized {
         Some((base, self.list()[base]))
     }
 
+    /// The index of `n` in the list of primes found so far, or `None` if `n` is not prime or has
+    /// not been found yet. Nothing is generated.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// pset.find(100);
+    /// assert_eq!(pset.find_exact(97), Some(24));
+    /// assert_eq!(pset.find_exact(91), None);
+    /// assert_eq!(pset.find_exact(1_009), None);
+    /// ```
+    fn find_exact(&self, n: u64) -> Option<usize> {
+        self.list().binary_search(&n).ok()
+    }
+
+    /// The primes on either side of `n` among those found so far, as `(prev, next)`: `prev` is
+    /// the largest prime less than `n`, or `None` if there isn't one, and `next` is the smallest
+    /// prime greater than `n`. `n` itself is never returned, whether prime or not.
+    ///
+    /// Returns `None` if no prime greater than `n` has been found yet. Nothing is generated.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// pset.find(100);
+    /// assert_eq!(pset.find_neighbors(90), Some((Some(89), 97)));
+    /// assert_eq!(pset.find_neighbors(89), Some((Some(83), 97)));
+    /// assert_eq!(pset.find_neighbors(2), Some((None, 3)));
+    /// assert_eq!(pset.find_neighbors(101), None);
+    /// ```
+    fn find_neighbors(&self, n: u64) -> Option<(Option<u64>, u64)> {
+        let list = self.list();
+        let below = list.partition_point(|&p| p < n);
+        let above = list.partition_point(|&p| p <= n);
+        let next = *list.get(above)?;
+        let prev = below.checked_sub(1).map(|ix| list[ix]);
+        Some((prev, next))
+    }
+
+    /// Find the next largest prime from a number, starting the search near `hint_index`
+    ///
+    /// Returns `(idx, prime)`, the same as `find`. The search gallops outward from the hint, so
+    /// it takes `O(log d)` steps when the answer is `d` places away. This makes a series of
+    /// queries on increasing values cheap when each result's index is passed as the next hint.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// let mut hint = 0;
+    /// for n in (1_000..1_100).step_by(10) {
+    ///     let (ix, p) = pset.find_from_hint(n, hint);
+    ///     assert_eq!((ix, p), pset.find(n));
+    ///     hint = ix;
+    /// }
+    /// ```
+    fn find_from_hint(&mut self, n: u64, hint_index: usize) -> (usize, u64) {
+        let mut largest = self.last_found().unwrap_or(0);
+        while n > largest {
+            largest = self.expand_next();
+        }
+        let list = self.list();
+        let hint = hint_index.min(list.len() - 1);
+
+        // Find a window (lo, hi] of indices containing the answer
+        let (mut lo, mut hi) = if list[hint] < n {
+            let mut step = 1;
+            let mut lo = hint;
+            while lo + step < list.len() && list[lo + step] < n {
+                lo += step;
+                step *= 2;
+            }
+            (lo + 1, (lo + step).min(list.len() - 1))
+        } else {
+            let mut step = 1;
+            let mut hi = hint;
+            while hi >= step && list[hi - step] >= n {
+                hi -= step;
+                step *= 2;
+            }
+            (hi.saturating_sub(step), hi)
+        };
+
+        // Binary search for the first index in [lo, hi] whose prime is at least 'n'
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if list[mid] < n {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        (lo, list[lo])
+    }
+
     /// Get the nth prime, even if we haven't yet found it
     fn get(&mut self, index: usize) -> u64 {
-        for _ in 0..(index as isize) + 1 - (self.list().len() as isize) {
-            self.expand();
+        self.try_get(index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Get the nth prime, even if we haven't yet found it, or an error if it is past
+    /// `expandable_limit()`
+    ///
+    /// ```
+    /// use primes::{ExternalPrimes, LimitExceeded, PrimeSet};
+    ///
+    /// let mut pset = ExternalPrimes::from_slice(&[2, 3, 5, 7, 11]);
+    /// assert_eq!(pset.try_get(4), Ok(11));
+    /// assert_eq!(pset.try_get(5), Err(LimitExceeded { limit: 11 }));
+    /// ```
+    fn try_get(&mut self, index: usize) -> Result<u64, LimitExceeded> {
+        if index < self.len() {
+            return Ok(self.list()[index]);
+        }
+        let limit = self.expandable_limit();
+        // Jump to just short of it, then go the rest of the way one prime at a time
+        let lower_bound = nth_prime_lower_bound(index as u64 + 1);
+        if lower_bound > limit {
+            return Err(LimitExceeded { limit });
+        }
+        self.expand_to(lower_bound);
+        while self.len() <= index {
+            if self.last_found().unwrap_or(0) >= limit {
+                return Err(LimitExceeded { limit });
+            }
+            self.expand_next();
+        }
+        Ok(self.list()[index])
+    }
+
+    /// Iterator over all composite numbers, starting with 4, generating primes as necessary to
+    /// tell them apart
+    fn composites(&mut self) -> Composites<'_, Self> {
+        Composites {
+            p: self,
+            n: 4,
+            ix: 2,
+        }
+    }
+
+    /// Iterator over all composite numbers, starting with 4, each paired with its smallest prime
+    /// factor
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// let first: Vec<(u64, u64)> = pset.composites_with_spf().take(5).collect();
+    /// assert_eq!(first, vec![(4, 2), (6, 2), (8, 2), (9, 3), (10, 2)]);
+    /// ```
+    fn composites_with_spf(&mut self) -> CompositesWithSpf<'_, Self> {
+        CompositesWithSpf {
+            composites: self.composites(),
         }
-        self.list()[index]
     }
 
     /// Get the prime factors of a number, starting from 2, including repeats
@@ -340,8 +1183,8 @@ pub trait PrimeSet: PrimeSetBasics + Sized {
         }
         let mut curn = n;
         let mut lst: Vec<u64> = Vec::new();
-        for p in self.iter() {
-            while curn % p == 0 {
+        for (p, square) in self.iter_with_squares() {
+            while curn.is_multiple_of(p) {
                 lst.push(p);
                 curn /= p;
                 if curn == 1 {
@@ -349,13 +1192,105 @@ pub trait PrimeSet: PrimeSetBasics + Sized {
                 }
             }
 
-            if p * p > curn {
+            match square {
+                Some(square) if square <= curn => (),
+                _ => {
+                    lst.push(curn);
+                    return lst;
+                }
+            }
+        }
+        unreachable!("This should be unreachable.");
+    }
+
+    /// Get the prime factors of a number, like `prime_factors`, but check what is left after each
+    /// factor with `tester`, so that trial division stops as soon as it is prime.
+    ///
+    /// When `n` has a large prime factor, this only generates primes up to the second largest,
+    /// rather than up to the square root of the largest. With a probabilistic tester, a
+    /// `Primality::Probable` cofactor is taken to be prime.
+    ///
+    /// ```
+    /// use primes::{Bpsw, PrimeSet, PrimeSetBasics, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// let n = 6 * 1_000_000_000_039;
+    /// assert_eq!(pset.prime_factors_with(n, &Bpsw), vec![2, 3, 1_000_000_000_039]);
+    /// assert!(pset.list().len() < 10);
+    /// ```
+    fn prime_factors_with<T: PrimalityTest>(&mut self, n: u64, tester: &T) -> Vec<u64> {
+        let mut lst: Vec<u64> = Vec::new();
+        if n <= 1 {
+            return lst;
+        }
+        let mut curn = n;
+        // Whether curn has changed since it was last tested
+        let mut untested = true;
+        for (p, square) in self.iter_with_squares() {
+            if untested && tester.test(curn) != Primality::Composite {
                 lst.push(curn);
                 return lst;
             }
+            untested = false;
+            while curn.is_multiple_of(p) {
+                lst.push(p);
+                curn /= p;
+                untested = true;
+                if curn == 1 {
+                    return lst;
+                }
+            }
+
+            match square {
+                Some(square) if square <= curn => (),
+                _ => {
+                    lst.push(curn);
+                    return lst;
+                }
+            }
         }
         unreachable!("This should be unreachable.");
     }
+
+    /// Find the smallest prime up to `limit` that divides the big integer `n`, or `None` if none
+    /// of them do, generating primes up to `limit` first if needed.
+    ///
+    /// The primes are multiplied together into `u64`s as far as they fit, so `n` is only divided
+    /// once per product, and each prime in it is checked against the small remainder.
+    ///
+    /// ```
+    /// use num_bigint::BigUint;
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// // 2^128 + 1 = 59649589127497217 * 5704689200685129054721
+    /// let n = (BigUint::from(1u32) << 128) + 1u32;
+    /// assert_eq!(pset.divides_any(&n, 1_000_000), None);
+    /// assert_eq!(pset.divides_any(&(n * 1_000_003u32), 2_000_000), Some(1_000_003));
+    /// ```
+    #[cfg(feature = "bigint")]
+    fn divides_any(&mut self, n: &num_bigint::BigUint, limit: u64) -> Option<u64> {
+        let count = self.iter_to(limit).len();
+        let primes = &self.list()[..count];
+        let mut start = 0;
+        while start < primes.len() {
+            let mut product = primes[start];
+            let mut end = start + 1;
+            while let Some(next) = primes.get(end).and_then(|&p| product.checked_mul(p)) {
+                product = next;
+                end += 1;
+            }
+            let remainder = (n % product).iter_u64_digits().next().unwrap_or(0);
+            if let Some(&p) = primes[start..end]
+                .iter()
+                .find(|&&p| remainder.is_multiple_of(p))
+            {
+                return Some(p);
+            }
+            start = end;
+        }
+        None
+    }
 }
 
 impl<P: PrimeSetBasics> PrimeSet for P {}
@@ -367,33 +1302,212 @@ impl Index<usize> for TrialDivision {
     }
 }
 
+impl Index<usize> for Sieve {
+    type Output = u64;
+    fn index(&self, index: usize) -> &u64 {
+        &self.list()[index]
+    }
+}
+
+// These deliberately stop short of `Deref<Target = [u64]>`, whose slice methods (`len`, `iter`,
+// `get`, ...) would be shadowed by the `PrimeSet` methods of the same names but different meaning.
+impl AsRef<[u64]> for TrialDivision {
+    fn as_ref(&self) -> &[u64] {
+        self.list()
+    }
+}
+
+impl AsRef<[u64]> for Sieve {
+    fn as_ref(&self) -> &[u64] {
+        self.list()
+    }
+}
+
+// Panics on the first of `primes` that isn't prime, for lists from outside that can't be trusted
+pub(crate) fn assert_all_prime(primes: &[u64]) {
+    if let Some(&n) = primes.iter().find(|&&n| !is_prime_u64(n)) {
+        panic!("{} is not prime", n);
+    }
+}
+
+/// `TrialDivision::from_primes`, after checking each value with `is_prime_u64`. Panics on one
+/// that isn't prime.
+impl From<Vec<u64>> for TrialDivision {
+    fn from(primes: Vec<u64>) -> TrialDivision {
+        assert_all_prime(&primes);
+        TrialDivision::from_primes(primes)
+    }
+}
+
+/// `Sieve::from_primes`, after checking each value with `is_prime_u64`. Panics on one that isn't
+/// prime.
+impl From<Vec<u64>> for Sieve {
+    fn from(primes: Vec<u64>) -> Sieve {
+        assert_all_prime(&primes);
+        Sieve::from_primes(primes)
+    }
+}
+
+impl<'a, P: PrimeSet> PrimeSetIter<'a, P> {
+    /// Pair each prime with its index in the `PrimeSet`.
+    ///
+    /// ```
+    /// use primes::{PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// pset.find(100);
+    /// let (ix, p) = pset.generator().indexed().next().unwrap();
+    /// assert_eq!((ix, p), (26, 103));
+    /// ```
+    pub fn indexed(self) -> IndexedPrimeSetIter<'a, P> {
+        IndexedPrimeSetIter { iter: self }
+    }
+
+    /// Yield each prime as a `Prime`, for passing to functions that require one.
+    ///
+    /// Unless the `PrimeSet` reports `primes_verified`, each prime is first tested with
+    /// `is_prime_u64`, and this panics on any that isn't prime.
+    ///
+    /// ```
+    /// use primes::{PrimeField, PrimeSet, Sieve};
+    ///
+    /// let mut pset = Sieve::new();
+    /// for p in pset.iter().typed().take(10) {
+    ///     let field = PrimeField::new(p);
+    ///     assert_eq!(field.pow(field.primitive_root(), p.get() - 1), 1);
+    /// }
+    /// ```
+    pub fn typed(self) -> std::iter::Map<Self, fn(u64) -> Prime> {
+        let wrap: fn(u64) -> Prime = if self.p.primes_verified() {
+            Prime::new_unchecked
+        } else {
+            |n| Prime::try_new(n).unwrap_or_else(|| panic!("{} is not prime", n))
+        };
+        self.map(wrap)
+    }
+}
+
 impl<'a, P: PrimeSet> Iterator for PrimeSetIter<'a, P> {
     type Item = u64;
     fn next(&mut self) -> Option<u64> {
-        while self.n >= self.p.list().len() {
-            if self.expand {
-                self.p.expand()
+        if self.n < self.p.len() {
+            self.n += 1;
+            return Some(self.p.list()[self.n - 1]);
+        }
+        if !self.expand || self.p.last_found().unwrap_or(0) >= self.p.expandable_limit() {
+            return None;
+        }
+        // Generate up to and including the prime at index 'n'
+        let mut p = self.p.expand_next();
+        while self.p.len() <= self.n {
+            p = self.p.expand_next();
+        }
+        self.n += 1;
+        Some(p)
+    }
+}
+
+/// An iterator over generated primes and their squares. Created by `PrimeSet::iter_with_squares`
+pub type PrimeSquaresIter<'a, P> =
+    std::iter::Map<PrimeSetIter<'a, P>, fn(u64) -> (u64, Option<u64>)>;
+
+/// An iterator over generated primes and their indices. Created by `PrimeSet::iter_indexed` or
+/// `PrimeSetIter::indexed`
+pub struct IndexedPrimeSetIter<'a, P: PrimeSet> {
+    iter: PrimeSetIter<'a, P>,
+}
+
+impl<'a, P: PrimeSet> Iterator for IndexedPrimeSetIter<'a, P> {
+    type Item = (usize, u64);
+    fn next(&mut self) -> Option<(usize, u64)> {
+        let p = self.iter.next()?;
+        Some((self.iter.n - 1, p))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<(usize, u64)> {
+        self.iter.n += n;
+        self.next()
+    }
+}
+
+/// An iterator over runs of `K` consecutive primes. Created by `PrimeSet::windows`
+pub struct PrimeWindows<'a, P: PrimeSet, const K: usize> {
+    p: &'a mut P,
+    // Index of the first prime in the next window
+    n: usize,
+}
+
+impl<'a, P: PrimeSet, const K: usize> Iterator for PrimeWindows<'a, P, K> {
+    type Item = [u64; K];
+    fn next(&mut self) -> Option<[u64; K]> {
+        while self.p.list().len() < self.n + K {
+            self.p.expand();
+        }
+        let mut window = [0; K];
+        window.copy_from_slice(&self.p.list()[self.n..self.n + K]);
+        self.n += 1;
+        Some(window)
+    }
+}
+
+/// An iterator over composite numbers. Created by `PrimeSet::composites`
+pub struct Composites<'a, P: PrimeSet> {
+    p: &'a mut P,
+    n: u64,
+    // Index of the smallest prime not less than 'n'
+    ix: usize,
+}
+
+impl<'a, P: PrimeSet> Iterator for Composites<'a, P> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            while self.ix >= self.p.list().len() {
+                self.p.expand();
+            }
+            let n = self.n;
+            self.n += 1;
+            if n == self.p.list()[self.ix] {
+                self.ix += 1;
             } else {
-                return None;
+                return Some(n);
             }
         }
-        self.n += 1;
+    }
+}
 
-        let m = self.p.list()[self.n - 1];
+/// An iterator over composite numbers and their smallest prime factors. Created by
+/// `PrimeSet::composites_with_spf`
+pub struct CompositesWithSpf<'a, P: PrimeSet> {
+    composites: Composites<'a, P>,
+}
 
-        Some(m)
+impl<'a, P: PrimeSet> Iterator for CompositesWithSpf<'a, P> {
+    type Item = (u64, u64);
+    fn next(&mut self) -> Option<(u64, u64)> {
+        let n = self.composites.next()?;
+        // Every prime below 'n' has been found, so this includes its smallest factor
+        let spf = self
+            .composites
+            .p
+            .list()
+            .iter()
+            .copied()
+            .find(|&p| n % p == 0)
+            .unwrap();
+        Some((n, spf))
     }
 }
 
 /// Find the first factor (other than 1) of a number
-fn firstfac(x: u64) -> u64 {
-    if x % 2 == 0 {
+pub(crate) fn firstfac(x: u64) -> u64 {
+    if x.is_multiple_of(2) {
         return 2;
     };
     // TODO: return to step_by
     // for n in (3..).step_by(2).take_while(|m| m*m <= x) {
     for n in (1..).map(|m| 2 * m + 1).take_while(|m| m * m <= x) {
-        if x % n == 0 {
+        if x.is_multiple_of(n) {
             return n;
         };
     }
@@ -421,6 +1535,72 @@ pub fn factors(x: u64) -> Vec<u64> {
     lst
 }
 
+/// Find all prime factors of a number, in increasing order with repeats, checking each remaining
+/// cofactor with `tester` so that trial division can stop as soon as what's left is prime.
+///
+/// This is much faster than `factors` when `x` has a large prime factor. With a probabilistic
+/// tester, a `Primality::Probable` cofactor is taken to be prime.
+///
+/// ```
+/// use primes::{factors_with, Bpsw};
+///
+/// assert_eq!(factors_with(12, &Bpsw), vec![2, 2, 3]);
+/// assert_eq!(factors_with(2 * 1_000_000_000_039, &Bpsw), vec![2, 1_000_000_000_039]);
+/// ```
+pub fn factors_with<T: PrimalityTest>(x: u64, tester: &T) -> Vec<u64> {
+    let mut lst = Vec::new();
+    let mut curn = x;
+    while curn > 1 {
+        if tester.test(curn) != Primality::Composite {
+            lst.push(curn);
+            break;
+        }
+        let m = firstfac(curn);
+        lst.push(m);
+        curn /= m;
+    }
+    lst
+}
+
+/// Find the prime factors of a number that are at most `trial_limit`, by trial division.
+///
+/// Returns `(factors, cofactor)`, where `factors` holds the small prime factors in increasing
+/// order, including repeats, and `cofactor` is what remains of `x` after dividing them all out.
+/// The cofactor has no prime factors at or below `trial_limit`, and is `1` if `x` was fully
+/// factored.
+///
+/// ```
+/// use primes::factors_with_limit;
+///
+/// assert_eq!(factors_with_limit(360, 10), (vec![2, 2, 2, 3, 3, 5], 1));
+/// assert_eq!(factors_with_limit(2 * 3 * 1_000_003, 100), (vec![2, 3], 1_000_003));
+/// ```
+pub fn factors_with_limit(x: u64, trial_limit: u64) -> (Vec<u64>, u64) {
+    let mut lst: Vec<u64> = Vec::new();
+    if x <= 1 {
+        return (lst, x);
+    }
+    let mut curn = x;
+    for n in (2..3).chain((1..).map(|m| 2 * m + 1)) {
+        if n > trial_limit {
+            break;
+        }
+        if n > curn / n {
+            // What's left is either 1 or prime
+            if curn > 1 && curn <= trial_limit {
+                lst.push(curn);
+                curn = 1;
+            }
+            break;
+        }
+        while curn.is_multiple_of(n) {
+            lst.push(n);
+            curn /= n;
+        }
+    }
+    (lst, curn)
+}
+
 /// Find all unique prime factors of a number
 pub fn factors_uniq(x: u64) -> Vec<u64> {
     if x <= 1 {
@@ -434,7 +1614,7 @@ pub fn factors_uniq(x: u64) -> Vec<u64> {
         if curn == m {
             break;
         }
-        while curn % m == 0 {
+        while curn.is_multiple_of(m) {
             curn /= m;
         }
         if curn == 1 {
@@ -444,10 +1624,393 @@ pub fn factors_uniq(x: u64) -> Vec<u64> {
     lst
 }
 
-/// Test whether a number is prime. Checks every odd number up to `sqrt(n)`.
+/// The sum of the prime factors of a number, including repeats (sopfr). Zero for 0 and 1.
+///
+/// ```
+/// use primes::sopfr;
+///
+/// assert_eq!(sopfr(12), 2 + 2 + 3);
+/// ```
+pub fn sopfr(x: u64) -> u64 {
+    factors(x).iter().sum()
+}
+
+/// The sum of the distinct prime factors of a number (sopf). Zero for 0 and 1.
+///
+/// ```
+/// use primes::sopf;
+///
+/// assert_eq!(sopf(12), 2 + 3);
+/// ```
+pub fn sopf(x: u64) -> u64 {
+    factors_uniq(x).iter().sum()
+}
+
+/// The smallest prime factor of a number, or `None` for 0 and 1
+pub fn smallest_prime_factor(x: u64) -> Option<u64> {
+    if x <= 1 {
+        return None;
+    }
+    Some(firstfac(x))
+}
+
+/// The largest prime factor of a number, or `None` for 0 and 1
+pub fn greatest_prime_factor(x: u64) -> Option<u64> {
+    factors(x).last().copied()
+}
+
+// Bit `n % 64` of entry `n / 64` is set when `n` is prime, for `n < 2^16`
+const SMALL_PRIME_TABLE: [u64; 1 << 10] = small_prime_table();
+
+const fn small_prime_table() -> [u64; 1 << 10] {
+    let mut composite = [0u64; 1 << 10];
+    composite[0] = 0b11;
+    let mut p = 2;
+    while p * p < 1 << 16 {
+        if composite[p / 64] >> (p % 64) & 1 == 0 {
+            let mut m = p * p;
+            while m < 1 << 16 {
+                composite[m / 64] |= 1 << (m % 64);
+                m += p;
+            }
+        }
+        p += 1;
+    }
+    let mut ix = 0;
+    while ix < composite.len() {
+        composite[ix] = !composite[ix];
+        ix += 1;
+    }
+    composite
+}
+
+// Below this, trial division on the mod-30 wheel beats Miller-Rabin
+const TRIAL_DIVISION_LIMIT: u64 = 1 << 20;
+
+/// Test whether a number is prime.
+///
+/// Numbers below 2^16 are looked up in a table built at compile time, numbers below 2^20 are
+/// checked by trial division on a mod-30 wheel, and anything larger gets the deterministic
+/// Miller-Rabin test of `is_prime_u64`. So this is fast for every `u64`.
+///
+/// ```
+/// use primes::is_prime;
+///
+/// assert!(is_prime(65_521));
+/// assert!(is_prime(1_000_003));
+/// assert!(is_prime(18_446_744_073_709_551_557));
+/// assert!(!is_prime(1));
+/// ```
 pub fn is_prime(n: u64) -> bool {
-    if n <= 1 {
+    if n < 1 << 16 {
+        SMALL_PRIME_TABLE[(n / 64) as usize] >> (n % 64) & 1 == 1
+    } else if n < TRIAL_DIVISION_LIMIT {
+        is_prime_const(n)
+    } else {
+        is_prime_u64(n)
+    }
+}
+
+/// Test whether a number is prime, in a `const` context.
+///
+/// This uses trial division by candidates on a mod-30 wheel, so it takes `O(sqrt(n))` steps; use
+/// it for compile-time checks on modest values, and `is_prime_u64` at runtime.
+///
+/// ```
+/// use primes::is_prime_const;
+///
+/// const MODULUS: u64 = 1_000_000_007;
+/// const _: () = assert!(is_prime_const(MODULUS));
+///
+/// static SMALL: [bool; 8] = {
+///     let mut flags = [false; 8];
+///     let mut n = 0;
+///     while n < 8 {
+///         flags[n] = is_prime_const(n as u64);
+///         n += 1;
+///     }
+///     flags
+/// };
+/// assert_eq!(SMALL, [false, false, true, true, false, true, false, true]);
+/// ```
+pub const fn is_prime_const(n: u64) -> bool {
+    if n < 2 {
         return false;
     }
-    firstfac(n) == n
+    if n.is_multiple_of(2) || n.is_multiple_of(3) || n.is_multiple_of(5) {
+        return n == 2 || n == 3 || n == 5;
+    }
+    let mut base = 0;
+    let mut ix = 1;
+    loop {
+        let d = base + WHEEL30[ix];
+        if d > n / d {
+            return true;
+        }
+        if n.is_multiple_of(d) {
+            return false;
+        }
+        ix += 1;
+        if ix == WHEEL30.len() {
+            ix = 0;
+            base += 30;
+        }
+    }
+}
+
+/// The first `N` primes, computed in a `const` context. This backs `static_primes!`.
+pub const fn first_primes<const N: usize>() -> [u64; N] {
+    let mut primes = [0u64; N];
+    let mut found = 0;
+    let mut n = 2;
+    while found < N {
+        if is_prime_const(n) {
+            primes[found] = n;
+            found += 1;
+        }
+        n += 1;
+    }
+    primes
+}
+
+/// Expands to a `[u64; N]` of the first `N` primes, computed at compile time.
+///
+/// The table can seed the runtime generators with `Sieve::with_seed_primes` or
+/// `TrialDivision::with_seed_primes`.
+///
+/// ```
+/// use primes::static_primes;
+///
+/// const PRIMES: [u64; 10] = static_primes!(10);
+/// assert_eq!(PRIMES, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+/// ```
+#[macro_export]
+macro_rules! static_primes {
+    ($n:expr) => {{
+        const PRIMES: [u64; $n] = $crate::first_primes::<{ $n }>();
+        PRIMES
+    }};
+}
+
+// The greatest common divisor of `a` and `b`, with gcd(0, 0) = 0
+pub(crate) fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+// The largest integer whose square is at most `n`
+pub(crate) fn isqrt(n: u64) -> u64 {
+    let mut root = (n as f64).sqrt() as u64;
+    while root as u128 * root as u128 > n as u128 {
+        root -= 1;
+    }
+    while (root as u128 + 1) * (root as u128 + 1) <= n as u128 {
+        root += 1;
+    }
+    root
+}
+
+// Bit `r` is set when `r` is a square modulo `m`, for `m <= 64`
+const fn square_residues(m: u64) -> u64 {
+    let mut mask = 0;
+    let mut x = 0;
+    while x < m {
+        mask |= 1 << (x * x % m);
+        x += 1;
+    }
+    mask
+}
+
+const SQUARES_MOD_64: u64 = square_residues(64);
+const SQUARES_MOD_63: u64 = square_residues(63);
+
+/// Test whether `n` is a perfect square.
+///
+/// Only 12 of the 64 residues mod 64 are squares, and 16 of the 63 mod 63, so most non-squares
+/// are rejected by two table lookups. The rest are confirmed with an exact integer square root,
+/// which unlike a plain `f64` square root is still right above 2^53.
+///
+/// ```
+/// use primes::is_square;
+///
+/// assert!(is_square(0));
+/// assert!(is_square(144));
+/// assert!(!is_square(145));
+/// assert!(is_square(4_294_967_295 * 4_294_967_295));
+/// assert!(!is_square(4_294_967_295 * 4_294_967_295 - 1));
+/// ```
+pub fn is_square(n: u64) -> bool {
+    if (SQUARES_MOD_64 >> (n % 64)) & 1 == 0 || (SQUARES_MOD_63 >> (n % 63)) & 1 == 0 {
+        return false;
+    }
+    let root = isqrt(n);
+    root * root == n
+}
+
+/// The largest `r` with `r^k <= n`, for `k >= 1`
+pub(crate) fn iroot(n: u64, k: u32) -> u64 {
+    if k == 1 || n < 2 {
+        return n;
+    }
+    let fits = |r: u64| matches!(r.checked_pow(k), Some(rk) if rk <= n);
+    let mut root = (n as f64).powf(1.0 / k as f64) as u64;
+    while root > 0 && !fits(root) {
+        root -= 1;
+    }
+    while fits(root + 1) {
+        root += 1;
+    }
+    root
+}
+
+/// The largest prime representable as a `u64`, `2^64 - 59`
+pub const LARGEST_U64_PRIME: u64 = 18_446_744_073_709_551_557;
+
+/// Find the smallest prime strictly greater than `n`, or `None` if that prime doesn't fit in a
+/// `u64`.
+///
+/// This steps through candidates on a mod-30 wheel and tests each with `is_prime_u64`, so it is
+/// fast for any `n` and needs no `PrimeSet`.
+///
+/// ```
+/// use primes::next_prime;
+///
+/// assert_eq!(next_prime(0), Some(2));
+/// assert_eq!(next_prime(13), Some(17));
+/// assert_eq!(next_prime(1_000_000), Some(1_000_003));
+/// assert_eq!(next_prime(u64::MAX - 58), None);
+/// ```
+pub fn next_prime(n: u64) -> Option<u64> {
+    next_prime_by(n, is_prime_u64)
+}
+
+/// Find the smallest number greater than `n` that `tester` doesn't reject, or `None` if there is
+/// no such `u64`.
+///
+/// Candidates on the same mod-30 wheel as `next_prime` are tested, and a `Primality::Probable`
+/// verdict is accepted, so with a probabilistic tester the result may be a pseudoprime.
+///
+/// ```
+/// use primes::{next_prime_with, Bpsw, WitnessSet};
+///
+/// assert_eq!(next_prime_with(1_000_000, &Bpsw), Some(1_000_003));
+/// // 3_215_031_751 is a strong pseudoprime to bases 2, 3, 5 and 7
+/// assert_eq!(
+///     next_prime_with(3_215_031_750, &WitnessSet::FirstFourPrimes),
+///     Some(3_215_031_751)
+/// );
+/// ```
+pub fn next_prime_with<T: PrimalityTest>(n: u64, tester: &T) -> Option<u64> {
+    next_prime_by(n, |candidate| {
+        tester.test(candidate) != Primality::Composite
+    })
+}
+
+// The first candidate on the wheel after `n` that passes `is_prime`, which must be right for 2,
+// 3, 5 and 7 if `n` is small
+fn next_prime_by<F: FnMut(u64) -> bool>(n: u64, mut is_prime: F) -> Option<u64> {
+    match n {
+        0 | 1 => return Some(2),
+        2 => return Some(3),
+        3 | 4 => return Some(5),
+        5 | 6 => return Some(7),
+        _ if n >= LARGEST_U64_PRIME => return None,
+        _ => (),
+    }
+    let mut wheel = Wheel30::after(n);
+    loop {
+        let candidate = wheel.next();
+        if is_prime(candidate) {
+            return Some(candidate);
+        }
+    }
+}
+
+/// Find the largest prime strictly less than `n`, or `None` if `n <= 2`.
+///
+/// ```
+/// use primes::prev_prime;
+///
+/// assert_eq!(prev_prime(2), None);
+/// assert_eq!(prev_prime(3), Some(2));
+/// assert_eq!(prev_prime(1_000_000), Some(999_983));
+/// assert_eq!(prev_prime(u64::MAX), Some(18_446_744_073_709_551_557));
+/// ```
+pub fn prev_prime(n: u64) -> Option<u64> {
+    match n {
+        0..=2 => return None,
+        3 => return Some(2),
+        4 | 5 => return Some(3),
+        6 | 7 => return Some(5),
+        _ if n > LARGEST_U64_PRIME => return Some(LARGEST_U64_PRIME),
+        _ => (),
+    }
+    let mut wheel = Wheel30::after(n - 1);
+    loop {
+        let candidate = wheel.prev();
+        if is_prime_u64(candidate) {
+            return Some(candidate);
+        }
+    }
+}
+
+/// Prime bucket counts for hash tables, each roughly twice the one before.
+///
+/// Each sits about halfway between two powers of two, so that it is as far as possible from
+/// both, and keys that share low bits still spread over the buckets.
+pub const PRIME_CAPACITIES: [u64; 28] = [
+    11,
+    23,
+    53,
+    97,
+    193,
+    389,
+    769,
+    1_543,
+    3_079,
+    6_151,
+    12_289,
+    24_593,
+    49_157,
+    98_317,
+    196_613,
+    393_241,
+    786_433,
+    1_572_869,
+    3_145_739,
+    6_291_469,
+    12_582_917,
+    25_165_843,
+    50_331_653,
+    100_663_319,
+    201_326_611,
+    402_653_189,
+    805_306_457,
+    1_610_612_741,
+];
+
+/// A prime capacity for a hash table that needs at least `n` buckets, or `None` if no prime that
+/// large fits in a `u64`.
+///
+/// This is the smallest entry of `PRIME_CAPACITIES` that is at least `n`, so that a table grown
+/// one step at a time roughly doubles each time; past the end of the table, it is the smallest
+/// prime that is at least `n`.
+///
+/// ```
+/// use primes::next_prime_capacity;
+///
+/// assert_eq!(next_prime_capacity(0), Some(11));
+/// assert_eq!(next_prime_capacity(100), Some(193));
+/// assert_eq!(next_prime_capacity(193), Some(193));
+/// assert_eq!(next_prime_capacity(2_000_000_000), Some(2_000_000_011));
+/// ```
+pub fn next_prime_capacity(n: u64) -> Option<u64> {
+    match PRIME_CAPACITIES.iter().find(|&&c| c >= n) {
+        Some(&c) => Some(c),
+        None => next_prime(n - 1),
+    }
 }
@@ -0,0 +1,273 @@
+//! Modular arithmetic on `u64`, used by the primality and factoring code.
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::is_prime_u64;
+
+/// Compute `(a * b) % m` without overflow.
+///
+/// Products of residues below 2^32 fit in a `u64`, so small moduli never touch 128-bit
+/// arithmetic. Larger moduli use a single widening multiply and a `u128` remainder.
+///
+/// Panics if `m == 0`.
+///
+/// ```
+/// use primes::mul_mod;
+///
+/// assert_eq!(mul_mod(3, 4, 5), 2);
+/// assert_eq!(mul_mod(u64::MAX - 1, u64::MAX - 1, u64::MAX), 1);
+/// ```
+#[inline]
+pub fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    if m <= 1 << 32 {
+        (a % m) * (b % m) % m
+    } else {
+        ((a as u128 * b as u128) % m as u128) as u64
+    }
+}
+
+/// Compute `base.pow(exp) % m` by repeated squaring, using `mul_mod` at each step.
+///
+/// Panics if `m == 0`.
+///
+/// ```
+/// use primes::pow_mod;
+///
+/// assert_eq!(pow_mod(2, 10, 1000), 24);
+/// assert_eq!(pow_mod(7, 0, 1), 0);
+/// ```
+pub fn pow_mod(base: u64, exp: u64, m: u64) -> u64 {
+    if m == 1 {
+        return 0;
+    }
+    let mut result = 1;
+    let mut base = base % m;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+// The product of `lo..=hi` modulo `m`
+fn product_mod(lo: u64, hi: u64, m: u64) -> u64 {
+    (lo..=hi).fold(1 % m, |acc, k| mul_mod(acc, k, m))
+}
+
+/// Compute `n! % p` for a prime `p`.
+///
+/// This is 0 once `n >= p`. Otherwise Wilson's theorem, `(p - 1)! ≡ -1 (mod p)`, gives
+/// `n! ≡ (-1)^(p - n) / (p - 1 - n)! (mod p)`, so only `min(n, p - 1 - n)` multiplications are
+/// needed; `n` close to `p` is as quick as `n` close to 0.
+///
+/// Panics if `p` is not prime.
+///
+/// ```
+/// use primes::factorial_mod;
+///
+/// assert_eq!(factorial_mod(5, 7), 120 % 7);
+/// assert_eq!(factorial_mod(7, 7), 0);
+/// // Wilson's theorem itself
+/// assert_eq!(factorial_mod(1_000_000_006, 1_000_000_007), 1_000_000_006);
+/// ```
+pub fn factorial_mod(n: u64, p: u64) -> u64 {
+    assert!(is_prime_u64(p), "{} is not prime", p);
+    if n >= p {
+        return 0;
+    }
+    let rest = p - 1 - n;
+    if n <= rest {
+        return product_mod(2, n, p);
+    }
+    let inverse = inv_mod(product_mod(2, rest, p), p).unwrap();
+    if (p - n).is_multiple_of(2) {
+        inverse
+    } else {
+        p - inverse
+    }
+}
+
+/// The Wilson quotient `((p - 1)! + 1) / p` of a prime `p`, modulo `p`.
+///
+/// Wilson's theorem says the division is exact. The Wilson primes are those for which this is
+/// 0, i.e. `(p - 1)! ≡ -1 (mod p^2)`; the only ones known are 5, 13 and 563. This takes `p`
+/// multiplications modulo `p^2`.
+///
+/// Panics if `p` is not prime, or if `p^2` doesn't fit in a `u64`.
+///
+/// ```
+/// use primes::wilson_quotient;
+///
+/// assert_eq!(wilson_quotient(7), (720 + 1) / 7 % 7);
+/// assert_eq!(wilson_quotient(563), 0);
+/// ```
+pub fn wilson_quotient(p: u64) -> u64 {
+    assert!(is_prime_u64(p), "{} is not prime", p);
+    let square = p.checked_mul(p).expect("p^2 must fit in a u64");
+    (product_mod(2, p - 1, square) + 1) % square / p
+}
+
+/// Find the inverse of `a` modulo `m` with the extended Euclidean algorithm, if `gcd(a, m) = 1`
+pub(crate) fn inv_mod(a: u64, m: u64) -> Option<u64> {
+    let (mut old_r, mut r) = (a as i128 % m as i128, m as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+    while r != 0 {
+        let q = old_r / r;
+        let next_r = old_r - q * r;
+        old_r = r;
+        r = next_r;
+        let next_s = old_s - q * s;
+        old_s = s;
+        s = next_s;
+    }
+    if old_r != 1 {
+        return None;
+    }
+    Some(old_s.rem_euclid(m as i128) as u64)
+}
+
+/**
+An integer modulo `M`, with arithmetic that never overflows.
+
+```
+use primes::ModInt;
+
+type Mod7 = ModInt<7>;
+
+let a = Mod7::new(5);
+let b = Mod7::new(4);
+assert_eq!((a + b).value(), 2);
+assert_eq!((a * b).value(), 6);
+assert_eq!((a - b).value(), 1);
+assert_eq!(a.pow(6).value(), 1);
+assert_eq!(a.inv().unwrap() * a, Mod7::new(1));
+```
+
+`M` must be at least 1.
+**/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ModInt<const M: u64> {
+    value: u64,
+}
+
+impl<const M: u64> ModInt<M> {
+    /// The modulus
+    pub const MODULUS: u64 = M;
+
+    /// `n mod M`
+    pub fn new(n: u64) -> Self {
+        ModInt { value: n % M }
+    }
+
+    /// The representative in `0..M`
+    pub fn value(self) -> u64 {
+        self.value
+    }
+
+    /// Raise to the power `exp`
+    pub fn pow(self, exp: u64) -> Self {
+        ModInt {
+            value: pow_mod(self.value, exp, M),
+        }
+    }
+
+    /// The multiplicative inverse, or `None` if this shares a factor with `M`, found with the
+    /// extended Euclidean algorithm.
+    pub fn inv(self) -> Option<Self> {
+        if self.value == 0 {
+            return None;
+        }
+        inv_mod(self.value, M).map(|value| ModInt { value })
+    }
+}
+
+impl<const M: u64> From<u64> for ModInt<M> {
+    fn from(n: u64) -> Self {
+        ModInt::new(n)
+    }
+}
+
+impl<const M: u64> fmt::Display for ModInt<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl<const M: u64> Add for ModInt<M> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        let (sum, overflowed) = self.value.overflowing_add(rhs.value);
+        let value = if overflowed || sum >= M {
+            sum.wrapping_sub(M)
+        } else {
+            sum
+        };
+        ModInt { value }
+    }
+}
+
+impl<const M: u64> Sub for ModInt<M> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        let value = if self.value >= rhs.value {
+            self.value - rhs.value
+        } else {
+            M - (rhs.value - self.value)
+        };
+        ModInt { value }
+    }
+}
+
+impl<const M: u64> Neg for ModInt<M> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        ModInt::default() - self
+    }
+}
+
+impl<const M: u64> Mul for ModInt<M> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        ModInt {
+            value: mul_mod(self.value, rhs.value, M),
+        }
+    }
+}
+
+/// Panics if `rhs` has no inverse modulo `M`.
+impl<const M: u64> Div for ModInt<M> {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self {
+        self * rhs.inv().expect("division by a non-invertible ModInt")
+    }
+}
+
+impl<const M: u64> AddAssign for ModInt<M> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const M: u64> SubAssign for ModInt<M> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const M: u64> MulAssign for ModInt<M> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl<const M: u64> DivAssign for ModInt<M> {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
@@ -0,0 +1,279 @@
+//! Reading and writing lists of primes in bulk, for interchange with other tools.
+
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::is_prime_u64;
+use crate::segment::SegmentIter;
+
+/// How `write_primes` lays out the primes it writes, and how `read_primes` expects them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrimeFormat {
+    /// One decimal prime per line, each followed by `\n`
+    Lines,
+    /// Decimal primes separated by commas, on a single line ending in `\n`
+    Csv,
+    /// Each prime as 8 little-endian bytes, with no separators
+    BinaryLe,
+}
+
+/// Write every prime up to and including `limit` to `w` in the given format, returning how many
+/// primes were written.
+///
+/// Primes are generated and written one sieve segment at a time, so memory use only grows with
+/// `sqrt(limit)`, not with the number of primes written. Each segment is handed to `w` with a
+/// single `write_all`, so there is no need to wrap `w` in a `BufWriter`.
+///
+/// ```
+/// use primes::{write_primes, PrimeFormat};
+///
+/// let mut out = Vec::new();
+/// assert_eq!(write_primes(&mut out, 20, PrimeFormat::Csv).unwrap(), 8);
+/// assert_eq!(out, b"2,3,5,7,11,13,17,19\n");
+/// ```
+pub fn write_primes<W: Write>(mut w: W, limit: u64, format: PrimeFormat) -> io::Result<u64> {
+    let mut segments = SegmentIter::new(2, limit);
+    let mut buf = Vec::new();
+    let mut count = 0;
+    while let Some((lo, flags)) = segments.next_segment() {
+        buf.clear();
+        for (ix, _) in flags.iter().enumerate().filter(|&(_, &b)| b) {
+            let p = lo + ix as u64;
+            match format {
+                PrimeFormat::Lines => writeln!(buf, "{}", p)?,
+                PrimeFormat::Csv if count == 0 => write!(buf, "{}", p)?,
+                PrimeFormat::Csv => write!(buf, ",{}", p)?,
+                PrimeFormat::BinaryLe => buf.extend_from_slice(&p.to_le_bytes()),
+            }
+            count += 1;
+        }
+        w.write_all(&buf)?;
+    }
+    if format == PrimeFormat::Csv && count > 0 {
+        w.write_all(b"\n")?;
+    }
+    w.flush()?;
+    Ok(count)
+}
+
+/// Why `read_primes` rejected its input. Indices count values from 0, in the order they appear.
+#[derive(Debug)]
+pub enum ReadPrimesError {
+    /// The underlying reader failed
+    Io(io::Error),
+    /// A value wasn't a decimal `u64`
+    Parse { index: usize, text: String },
+    /// Binary input ended partway through a value
+    Truncated { trailing_bytes: usize },
+    /// A value wasn't greater than the one before it
+    NotIncreasing { index: usize, value: u64 },
+    /// A value wasn't prime (only checked when asked for)
+    NotPrime { index: usize, value: u64 },
+}
+
+impl fmt::Display for ReadPrimesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadPrimesError::Io(e) => write!(f, "error reading primes: {}", e),
+            ReadPrimesError::Parse { index, text } => {
+                write!(f, "value {} ({:?}) is not a u64", index, text)
+            }
+            ReadPrimesError::Truncated { trailing_bytes } => {
+                write!(
+                    f,
+                    "input ends with {} bytes of a partial value",
+                    trailing_bytes
+                )
+            }
+            ReadPrimesError::NotIncreasing { index, value } => {
+                write!(
+                    f,
+                    "value {} ({}) is not greater than the one before it",
+                    index, value
+                )
+            }
+            ReadPrimesError::NotPrime { index, value } => {
+                write!(f, "value {} ({}) is not prime", index, value)
+            }
+        }
+    }
+}
+
+impl error::Error for ReadPrimesError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ReadPrimesError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadPrimesError {
+    fn from(e: io::Error) -> Self {
+        ReadPrimesError::Io(e)
+    }
+}
+
+/// Read a list of primes in the given format, as written by `write_primes` or another tool.
+///
+/// For the text formats, surrounding whitespace and blank lines are ignored, and `Lines` input
+/// may also separate values with commas. The values must be strictly increasing; if
+/// `check_primality` is set, each one is also tested with `is_prime_u64`. A list of the
+/// consecutive primes from 2 can be handed straight to `Sieve::from_primes` or
+/// `TrialDivision::from_primes`.
+///
+/// This collects `read_primes_iter`, which reads the same input lazily.
+///
+/// ```
+/// use primes::{read_primes, PrimeFormat, PrimeSetBasics, Sieve};
+///
+/// let primes = read_primes(&b"2\n3\n5\n7\n"[..], PrimeFormat::Lines, true).unwrap();
+/// let sieve = Sieve::from_primes(primes);
+/// assert_eq!(sieve.list(), &[2, 3, 5, 7]);
+///
+/// assert!(read_primes(&b"2,3,9"[..], PrimeFormat::Csv, true).is_err());
+/// ```
+pub fn read_primes<R: BufRead>(
+    r: R,
+    format: PrimeFormat,
+    check_primality: bool,
+) -> Result<Vec<u64>, ReadPrimesError> {
+    read_primes_iter(r, format, check_primality).collect()
+}
+
+/// An iterator over the primes in a list being read, one value at a time. Created by
+/// `read_primes_iter`.
+pub struct PrimeFileIter<R> {
+    r: R,
+    format: PrimeFormat,
+    check_primality: bool,
+    // For the text formats, the current line and how far into it we've parsed
+    line: String,
+    pos: usize,
+    index: usize,
+    prev: Option<u64>,
+    // Set after the end of the input or an error, after which nothing more is read
+    done: bool,
+}
+
+/// Read a list of primes lazily, with the same formats and checks as `read_primes`.
+///
+/// Only one line (for the text formats) or one value (for `BinaryLe`) is held at a time, so
+/// files of billions of primes can be scanned in constant memory. Each item is a prime or the
+/// error that stopped the reading; nothing more is read after an error.
+///
+/// ```
+/// use primes::{read_primes_iter, PrimeFormat};
+///
+/// let mut primes = read_primes_iter(&b"2\n3\n5\n4\n"[..], PrimeFormat::Lines, true);
+/// assert_eq!(primes.next().unwrap().unwrap(), 2);
+/// let sum: u64 = primes.by_ref().take(2).map(Result::unwrap).sum();
+/// assert_eq!(sum, 8);
+/// assert!(primes.next().unwrap().is_err());
+/// assert!(primes.next().is_none());
+/// ```
+pub fn read_primes_iter<R: BufRead>(
+    r: R,
+    format: PrimeFormat,
+    check_primality: bool,
+) -> PrimeFileIter<R> {
+    PrimeFileIter {
+        r,
+        format,
+        check_primality,
+        line: String::new(),
+        pos: 0,
+        index: 0,
+        prev: None,
+        done: false,
+    }
+}
+
+impl<R: BufRead> PrimeFileIter<R> {
+    // The next value in a text format, not yet checked
+    fn next_text(&mut self) -> Result<Option<u64>, ReadPrimesError> {
+        loop {
+            let rest = &self.line[self.pos..];
+            let (field, consumed) = match rest.find(',') {
+                Some(comma) => (&rest[..comma], comma + 1),
+                None => (rest, rest.len()),
+            };
+            self.pos += consumed;
+            let text = field.trim();
+            if !text.is_empty() {
+                return text.parse().map(Some).map_err(|_| ReadPrimesError::Parse {
+                    index: self.index,
+                    text: text.to_string(),
+                });
+            }
+            if self.pos >= self.line.len() {
+                self.line.clear();
+                self.pos = 0;
+                if self.r.read_line(&mut self.line)? == 0 {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    // The next value in `BinaryLe` format, not yet checked
+    fn next_binary(&mut self) -> Result<Option<u64>, ReadPrimesError> {
+        let mut le = [0; 8];
+        let mut filled = 0;
+        while filled < le.len() {
+            match self.r.read(&mut le[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        match filled {
+            0 => Ok(None),
+            8 => Ok(Some(u64::from_le_bytes(le))),
+            trailing_bytes => Err(ReadPrimesError::Truncated { trailing_bytes }),
+        }
+    }
+
+    fn next_checked(&mut self) -> Result<Option<u64>, ReadPrimesError> {
+        let value = match self.format {
+            PrimeFormat::Lines | PrimeFormat::Csv => self.next_text()?,
+            PrimeFormat::BinaryLe => self.next_binary()?,
+        };
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let index = self.index;
+        if matches!(self.prev, Some(prev) if value <= prev) {
+            return Err(ReadPrimesError::NotIncreasing { index, value });
+        }
+        if self.check_primality && !is_prime_u64(value) {
+            return Err(ReadPrimesError::NotPrime { index, value });
+        }
+        self.prev = Some(value);
+        self.index += 1;
+        Ok(Some(value))
+    }
+}
+
+impl<R: BufRead> Iterator for PrimeFileIter<R> {
+    type Item = Result<u64, ReadPrimesError>;
+    fn next(&mut self) -> Option<Result<u64, ReadPrimesError>> {
+        if self.done {
+            return None;
+        }
+        match self.next_checked() {
+            Ok(Some(value)) => Some(Ok(value)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
@@ -0,0 +1,677 @@
+//! Primality tests that don't need a `PrimeSet`.
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
+
+use crate::modular::{mul_mod, pow_mod};
+use crate::{first_primes, firstfac, gcd, is_square};
+
+/// The verdict of a `PrimalityTest`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Primality {
+    /// Certainly prime
+    Prime,
+    /// Certainly composite (or less than 2)
+    Composite,
+    /// Passed a test that some composites also pass
+    Probable,
+}
+
+/**
+A way of testing numbers for primality, so that callers can choose how to trade certainty for
+speed.
+
+Implemented by `TrialDivisionTest`, by each `WitnessSet` (as a Miller-Rabin test), by
+`StagedMillerRabin`, and by `Bpsw`. Functions such as `next_prime_with` and `factors_with` accept
+any of these.
+
+```
+use primes::{Bpsw, Primality, PrimalityTest, WitnessSet};
+
+let n = 3_215_031_751; // 151 * 751 * 28351
+assert_eq!(WitnessSet::FirstFourPrimes.test(n), Primality::Probable);
+assert_eq!(WitnessSet::FirstSevenPrimes.test(n), Primality::Composite);
+assert_eq!(Bpsw.test(1_000_000_007), Primality::Prime);
+```
+**/
+pub trait PrimalityTest {
+    /// Test whether `n` is prime
+    fn test(&self, n: u64) -> Primality;
+}
+
+/// Trial division by every odd number up to `sqrt(n)`, as `factors` does. Always certain, but
+/// slow for large primes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrialDivisionTest;
+
+impl PrimalityTest for TrialDivisionTest {
+    fn test(&self, n: u64) -> Primality {
+        if n > 1 && firstfac(n) == n {
+            Primality::Prime
+        } else {
+            Primality::Composite
+        }
+    }
+}
+
+/**
+A set of Miller-Rabin bases, each known to give a deterministic answer below some bound.
+
+Larger sets cover more of the `u64` range at the cost of a few more modular exponentiations per
+test. Use `WitnessSet::limit` to see what a set guarantees, or `WitnessSet::smallest_for` to pick
+the cheapest set that is deterministic for a given number.
+
+Above its limit, a set still gives a correct "composite" answer, but a "prime" answer only means
+that `n` is a strong probable prime to every base in the set.
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WitnessSet {
+    /// Bases 2, 3, 5, 7. Deterministic for `n < 3_215_031_751`.
+    FirstFourPrimes,
+    /// Bases 2 through 17. Deterministic for `n < 341_550_071_728_321`.
+    FirstSevenPrimes,
+    /// Bases 2 through 23. Deterministic for `n < 3_825_123_056_546_413_051`.
+    FirstNinePrimes,
+    /// Jim Sinclair's seven bases. Deterministic for every `u64`.
+    Sinclair,
+}
+
+impl WitnessSet {
+    /// All witness sets, from cheapest to most thorough
+    pub const ALL: [WitnessSet; 4] = [
+        WitnessSet::FirstFourPrimes,
+        WitnessSet::FirstSevenPrimes,
+        WitnessSet::FirstNinePrimes,
+        WitnessSet::Sinclair,
+    ];
+
+    /// The bases tested by this set
+    pub fn bases(self) -> &'static [u64] {
+        match self {
+            WitnessSet::FirstFourPrimes => &[2, 3, 5, 7],
+            WitnessSet::FirstSevenPrimes => &[2, 3, 5, 7, 11, 13, 17],
+            WitnessSet::FirstNinePrimes => &[2, 3, 5, 7, 11, 13, 17, 19, 23],
+            WitnessSet::Sinclair => &[2, 325, 9375, 28178, 450_775, 9_780_504, 1_795_265_022],
+        }
+    }
+
+    /// The (exclusive) bound below which this set is deterministic, or `None` if it is
+    /// deterministic for every `u64`
+    pub fn limit(self) -> Option<u64> {
+        match self {
+            WitnessSet::FirstFourPrimes => Some(3_215_031_751),
+            WitnessSet::FirstSevenPrimes => Some(341_550_071_728_321),
+            WitnessSet::FirstNinePrimes => Some(3_825_123_056_546_413_051),
+            WitnessSet::Sinclair => None,
+        }
+    }
+
+    /// Whether this set gives a deterministic answer for `n`
+    pub fn covers(self, n: u64) -> bool {
+        match self.limit() {
+            Some(limit) => n < limit,
+            None => true,
+        }
+    }
+
+    /// The cheapest set that is deterministic for `n`
+    pub fn smallest_for(n: u64) -> WitnessSet {
+        WitnessSet::ALL
+            .iter()
+            .copied()
+            .find(|set| set.covers(n))
+            .unwrap_or(WitnessSet::Sinclair)
+    }
+}
+
+// Whether odd `n > 2`, with `n - 1 = d * 2^s` and `d` odd, is a strong probable prime to base `a`.
+fn is_strong_probable_prime(n: u64, d: u64, s: u32, a: u64) -> bool {
+    let a = a % n;
+    if a == 0 {
+        return true;
+    }
+    let mut x = pow_mod(a, d, n);
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = mul_mod(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Run the Miller-Rabin test on `n` with every base in `witnesses`.
+///
+/// This is deterministic whenever `witnesses.covers(n)`; otherwise `true` means `n` is a strong
+/// probable prime to those bases.
+///
+/// ```
+/// use primes::{miller_rabin, WitnessSet};
+///
+/// assert!(miller_rabin(1_000_000_007, WitnessSet::FirstFourPrimes));
+/// // A strong pseudoprime to bases 2, 3, 5 and 7, just past where that set is deterministic
+/// assert!(miller_rabin(3_215_031_751, WitnessSet::FirstFourPrimes));
+/// assert!(!miller_rabin(3_215_031_751, WitnessSet::FirstSevenPrimes));
+/// ```
+pub fn miller_rabin(n: u64, witnesses: WitnessSet) -> bool {
+    if n < 4 {
+        return n >= 2;
+    }
+    if n.is_multiple_of(2) {
+        return false;
+    }
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    witnesses
+        .bases()
+        .iter()
+        .all(|&a| is_strong_probable_prime(n, d, s, a))
+}
+
+/// A Miller-Rabin test with this set's bases, which is certain up to `limit()` and probable beyond
+impl PrimalityTest for WitnessSet {
+    fn test(&self, n: u64) -> Primality {
+        if !miller_rabin(n, *self) {
+            Primality::Composite
+        } else if self.covers(n) {
+            Primality::Prime
+        } else {
+            Primality::Probable
+        }
+    }
+}
+
+/// The primes below 1000, which `has_small_factor` screens with precomputed products
+const SCREEN_PRIMES: [u64; 168] = first_primes::<168>();
+
+// How many runs of consecutive `SCREEN_PRIMES` it takes for each run's product to fit in a u128
+const fn screen_chunk_count() -> usize {
+    let mut count = 0;
+    let mut product: u128 = 1;
+    let mut ix = 0;
+    while ix < SCREEN_PRIMES.len() {
+        match product.checked_mul(SCREEN_PRIMES[ix] as u128) {
+            Some(next) => product = next,
+            None => {
+                count += 1;
+                product = SCREEN_PRIMES[ix] as u128;
+            }
+        }
+        ix += 1;
+    }
+    count + 1
+}
+
+const SCREEN_CHUNK_COUNT: usize = screen_chunk_count();
+
+// Entry `(product, end)` is the product of `SCREEN_PRIMES[start..end]`, where `start` is the
+// previous entry's `end`
+const fn screen_chunks() -> [(u128, usize); SCREEN_CHUNK_COUNT] {
+    let mut chunks = [(1u128, 0usize); SCREEN_CHUNK_COUNT];
+    let mut chunk = 0;
+    let mut ix = 0;
+    while ix < SCREEN_PRIMES.len() {
+        match chunks[chunk].0.checked_mul(SCREEN_PRIMES[ix] as u128) {
+            Some(next) => chunks[chunk].0 = next,
+            None => {
+                chunk += 1;
+                chunks[chunk].0 = SCREEN_PRIMES[ix] as u128;
+            }
+        }
+        ix += 1;
+        chunks[chunk].1 = ix;
+    }
+    chunks
+}
+
+const SCREEN_CHUNKS: [(u128, usize); SCREEN_CHUNK_COUNT] = screen_chunks();
+
+/// Find the smallest prime factor of `n` that is at most `bound`, if there is one.
+///
+/// Rather than dividing by each small prime in turn, this takes the GCD of `n` with precomputed
+/// products of the primes below 1000, a dozen or more to each `u128`, and only looks for the
+/// individual prime within a product that shares a factor with `n`. Primes from 1000 up to
+/// `bound` are tried by trial division. This makes it a quick screen for candidate primes: most
+/// random composites have a small factor.
+///
+/// `n` itself counts, so a prime at most `bound` is its own small factor.
+///
+/// ```
+/// use primes::has_small_factor;
+///
+/// assert_eq!(has_small_factor(1_000_000_007 * 101, 200), Some(101));
+/// assert_eq!(has_small_factor(1_000_000_007 * 101, 100), None);
+/// assert_eq!(has_small_factor(1_000_000_007, 1_000), None);
+/// ```
+pub fn has_small_factor(n: u64, bound: u64) -> Option<u64> {
+    if n == 1 {
+        return None;
+    }
+    let mut start = 0;
+    for &(product, end) in SCREEN_CHUNKS.iter() {
+        if SCREEN_PRIMES[start] > bound {
+            return None;
+        }
+        let shared = if n == 0 {
+            0
+        } else {
+            gcd((product % n as u128) as u64, n)
+        };
+        if shared != 1 {
+            return SCREEN_PRIMES[start..end]
+                .iter()
+                .copied()
+                .take_while(|&p| p <= bound)
+                .find(|&p| shared % p == 0);
+        }
+        start = end;
+    }
+
+    let first = *SCREEN_PRIMES.last().unwrap() + 2;
+    let mut d = first;
+    while d <= bound {
+        if d > n / d {
+            // Nothing up to sqrt(n) divides n, so n is prime
+            return if n <= bound { Some(n) } else { None };
+        }
+        if n.is_multiple_of(d) {
+            return Some(d);
+        }
+        d += 2;
+    }
+    None
+}
+
+const SMALL_PRIMES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Test whether any `u64` is prime, using trial division by a few small primes followed by a
+/// deterministic Miller-Rabin test with the cheapest sufficient `WitnessSet`.
+///
+/// ```
+/// use primes::is_prime_u64;
+///
+/// assert!(is_prime_u64(18_446_744_073_709_551_557));
+/// assert!(!is_prime_u64(18_446_744_073_709_551_559));
+/// ```
+pub fn is_prime_u64(n: u64) -> bool {
+    for &p in SMALL_PRIMES.iter() {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    if n < 41 * 41 {
+        return n > 1;
+    }
+    miller_rabin(n, WitnessSet::smallest_for(n))
+}
+
+/**
+A Miller-Rabin test run in stages, each cheaper than the next and each rejecting most of what
+reaches it: trial division by the primes up to 37, a GCD screen against products of the primes
+from 41 to 127, a single strong probable prime test to base 2, and only then the rest of the
+cheapest `WitnessSet` that is deterministic for `n`.
+
+Every prime goes through all the stages, so this is no faster than `is_prime_u64` on primes. It
+is meant for searches over random candidates, where nearly everything is composite: about 77% of
+odd candidates stop before any modular exponentiation, and almost all of the rest fail the
+base-2 test after one.
+
+```
+use primes::{Primality, PrimalityTest, StagedMillerRabin};
+
+assert_eq!(StagedMillerRabin.test(1_000_000_007), Primality::Prime);
+assert_eq!(StagedMillerRabin.test(127 * 1_000_000_007), Primality::Composite);
+// A strong pseudoprime to base 2, caught by the full witness set
+assert_eq!(StagedMillerRabin.test(3_215_031_751), Primality::Composite);
+```
+**/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StagedMillerRabin;
+
+// The primes from 41 to 127, in two products that each fit in a u64
+const GCD_SCREEN: [u64; 2] = [
+    41 * 43 * 47 * 53 * 59 * 61 * 67 * 71 * 73 * 79,
+    83 * 89 * 97 * 101 * 103 * 107 * 109 * 113 * 127,
+];
+
+// Binary GCD, which is quicker than `gcd` for full-width operands. Both must be nonzero.
+fn binary_gcd(mut a: u64, mut b: u64) -> u64 {
+    let shift = (a | b).trailing_zeros();
+    a >>= a.trailing_zeros();
+    loop {
+        b >>= b.trailing_zeros();
+        if a > b {
+            std::mem::swap(&mut a, &mut b);
+        }
+        b -= a;
+        if b == 0 {
+            return a << shift;
+        }
+    }
+}
+
+impl PrimalityTest for StagedMillerRabin {
+    fn test(&self, n: u64) -> Primality {
+        for &p in SMALL_PRIMES.iter() {
+            if n.is_multiple_of(p) {
+                return if n == p {
+                    Primality::Prime
+                } else {
+                    Primality::Composite
+                };
+            }
+        }
+        if n < 41 * 41 {
+            return if n > 1 {
+                Primality::Prime
+            } else {
+                Primality::Composite
+            };
+        }
+        // n > 127 here, so any factor shared with the screen is a proper one
+        for &product in GCD_SCREEN.iter() {
+            let r = n % product;
+            if r == 0 || binary_gcd(product, r) != 1 {
+                return Primality::Composite;
+            }
+        }
+        if n < 131 * 131 {
+            return Primality::Prime;
+        }
+
+        let s = (n - 1).trailing_zeros();
+        let d = (n - 1) >> s;
+        if !is_strong_probable_prime(n, d, s, 2) {
+            return Primality::Composite;
+        }
+        // Every witness set starts with base 2, which has already passed
+        let bases = &WitnessSet::smallest_for(n).bases()[1..];
+        if bases.iter().all(|&a| is_strong_probable_prime(n, d, s, a)) {
+            Primality::Prime
+        } else {
+            Primality::Composite
+        }
+    }
+}
+
+/**
+The Baillie-PSW test: a strong probable prime test to base 2, followed by a strong Lucas probable
+prime test with Selfridge's parameters.
+
+No composite passing both is known, and none exists below 2^64, so for a `u64` the answer is
+always certain.
+**/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bpsw;
+
+impl PrimalityTest for Bpsw {
+    fn test(&self, n: u64) -> Primality {
+        if is_prime_bpsw(n) {
+            Primality::Prime
+        } else {
+            Primality::Composite
+        }
+    }
+}
+
+/// The Jacobi symbol `(a / n)`, for odd `n`
+pub(crate) fn jacobi(a: u64, n: u64) -> i8 {
+    let (mut a, mut n) = (a % n, n);
+    let mut result = 1;
+    while a != 0 {
+        while a % 2 == 0 {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        a %= n;
+    }
+    if n == 1 {
+        result
+    } else {
+        0
+    }
+}
+
+fn add_mod(a: u64, b: u64, n: u64) -> u64 {
+    let (sum, overflowed) = a.overflowing_add(b);
+    if overflowed || sum >= n {
+        sum.wrapping_sub(n)
+    } else {
+        sum
+    }
+}
+
+fn sub_mod(a: u64, b: u64, n: u64) -> u64 {
+    if a >= b {
+        a - b
+    } else {
+        n - (b - a)
+    }
+}
+
+// `a / 2` modulo odd `n`
+fn half_mod(a: u64, n: u64) -> u64 {
+    if a.is_multiple_of(2) {
+        a / 2
+    } else {
+        // (a + n) / 2, without overflowing
+        a / 2 + n / 2 + 1
+    }
+}
+
+// Whether odd `n > 37` with no factor up to 37 is a strong Lucas probable prime, with `P = 1` and
+// `D` the first of 5, -7, 9, -11, ... for which `(D / n) = -1`
+fn is_strong_lucas_probable_prime(n: u64) -> bool {
+    // A square never has (D / n) = -1, so the search for D would never end
+    if is_square(n) {
+        return false;
+    }
+
+    let mut d_abs = 5u64;
+    let mut negative = false;
+    let d = loop {
+        let d = if negative { n - d_abs % n } else { d_abs % n };
+        match jacobi(d, n) {
+            -1 => break d,
+            // D shares a factor with n, and n is too big to be that factor
+            0 if !d_abs.is_multiple_of(n) => return false,
+            _ => (),
+        }
+        d_abs += 2;
+        negative = !negative;
+    };
+    // Q = (1 - D) / 4
+    let q = if negative {
+        (1 + d_abs) / 4 % n
+    } else {
+        n - (d_abs - 1) / 4 % n
+    };
+
+    let s = (n + 1).trailing_zeros();
+    let k = (n + 1) >> s;
+
+    // Walk the bits of k from the top, tracking U_j, V_j and Q^j for the prefix j seen so far
+    let (mut u, mut v, mut qj) = (1, 1, q);
+    for bit in (0..63 - k.leading_zeros()).rev() {
+        // j -> 2j
+        u = mul_mod(u, v, n);
+        v = sub_mod(mul_mod(v, v, n), add_mod(qj, qj, n), n);
+        qj = mul_mod(qj, qj, n);
+        if (k >> bit) & 1 == 1 {
+            // j -> j + 1, with P = 1
+            let next_u = half_mod(add_mod(u, v, n), n);
+            v = half_mod(add_mod(mul_mod(d, u, n), v, n), n);
+            u = next_u;
+            qj = mul_mod(qj, q, n);
+        }
+    }
+
+    if u == 0 || v == 0 {
+        return true;
+    }
+    for _ in 1..s {
+        v = sub_mod(mul_mod(v, v, n), add_mod(qj, qj, n), n);
+        if v == 0 {
+            return true;
+        }
+        qj = mul_mod(qj, qj, n);
+    }
+    false
+}
+
+/// Run the Baillie-PSW test on `n`: a strong probable prime test to base 2, followed by a strong
+/// Lucas probable prime test with Selfridge's parameters. This is what `Bpsw` tests with.
+///
+/// No composite below 2^64 passes both, so for a `u64` the answer is certain.
+///
+/// ```
+/// use primes::is_prime_bpsw;
+///
+/// assert!(is_prime_bpsw(1_000_000_007));
+/// // A strong pseudoprime to base 2, caught by the Lucas test
+/// assert!(!is_prime_bpsw(2_047));
+/// assert!(is_prime_bpsw(18_446_744_073_709_551_557));
+/// ```
+pub fn is_prime_bpsw(n: u64) -> bool {
+    for &p in SMALL_PRIMES.iter() {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    if n < 41 * 41 {
+        return n > 1;
+    }
+    let s = (n - 1).trailing_zeros();
+    is_strong_probable_prime(n, (n - 1) >> s, s, 2) && is_strong_lucas_probable_prime(n)
+}
+
+/// Run the Baillie-PSW test on a `BigUint`, with the same two stages as `is_prime_bpsw`.
+///
+/// No composite of any size is known to pass, but past 2^64 the search has not been exhaustive,
+/// so `true` means `n` is a probable prime. Values that fit in a `u64` get `is_prime_bpsw`, and
+/// are certain.
+///
+/// ```
+/// use num_bigint::BigUint;
+/// use primes::is_prime_bpsw_big;
+///
+/// let mersenne = |e: u32| (BigUint::from(1u32) << e) - 1u32;
+/// assert!(is_prime_bpsw_big(&mersenne(127)));
+/// assert!(!is_prime_bpsw_big(&mersenne(67)));
+/// ```
+#[cfg(feature = "bigint")]
+pub fn is_prime_bpsw_big(n: &BigUint) -> bool {
+    use std::convert::TryFrom;
+
+    if let Ok(small) = u64::try_from(n) {
+        return is_prime_bpsw(small);
+    }
+    let zero = BigUint::from(0u32);
+    if SMALL_PRIMES.iter().any(|&p| n % p == zero) {
+        return false;
+    }
+
+    let one = BigUint::from(1u32);
+    let n_minus_1 = n - &one;
+    let s = n_minus_1.trailing_zeros().unwrap();
+    let mut x = BigUint::from(2u32).modpow(&(&n_minus_1 >> s), n);
+    let base_2 = x == one || x == n_minus_1 || {
+        (1..s).any(|_| {
+            x = &x * &x % n;
+            x == n_minus_1
+        })
+    };
+    base_2 && is_strong_lucas_probable_prime_big(n)
+}
+
+// `is_strong_lucas_probable_prime` for a `BigUint` with no factor up to 37, and larger than any
+// `u64`
+#[cfg(feature = "bigint")]
+fn is_strong_lucas_probable_prime_big(n: &BigUint) -> bool {
+    use std::convert::TryFrom;
+
+    let root = n.sqrt();
+    if &root * &root == *n {
+        return false;
+    }
+
+    // (D / n) by reciprocity, from (n / |D|), which only needs n modulo |D|
+    let n_is_3_mod_4 = n.bit(0) && n.bit(1);
+    let mut d_abs = 5u64;
+    let mut negative = false;
+    loop {
+        let mut symbol = jacobi(u64::try_from(n % d_abs).unwrap(), d_abs);
+        if d_abs % 4 == 3 && n_is_3_mod_4 {
+            symbol = -symbol;
+        }
+        // (-1 / n) = -1 exactly when n = 3 (mod 4)
+        if negative && n_is_3_mod_4 {
+            symbol = -symbol;
+        }
+        match symbol {
+            -1 => break,
+            // D shares a factor with n, and n is too big to be that factor
+            0 => return false,
+            _ => (),
+        }
+        d_abs += 2;
+        negative = !negative;
+    }
+    let d = if negative {
+        n - d_abs
+    } else {
+        BigUint::from(d_abs)
+    };
+    // Q = (1 - D) / 4
+    let q = if negative {
+        BigUint::from((1 + d_abs) / 4)
+    } else {
+        n - (d_abs - 1) / 4
+    };
+
+    let add = |a: &BigUint, b: &BigUint| (a + b) % n;
+    let sub = |a: &BigUint, b: &BigUint| (a + n - b) % n;
+    let half = |a: BigUint| if a.bit(0) { (a + n) >> 1 } else { a >> 1 };
+
+    let n_plus_1 = n + 1u32;
+    let s = n_plus_1.trailing_zeros().unwrap();
+    let k = &n_plus_1 >> s;
+
+    // Walk the bits of k from the top, tracking U_j, V_j and Q^j for the prefix j seen so far
+    let (mut u, mut v, mut qj) = (BigUint::from(1u32), BigUint::from(1u32), q.clone());
+    for bit in (0..k.bits() - 1).rev() {
+        // j -> 2j
+        u = &u * &v % n;
+        v = sub(&(&v * &v % n), &add(&qj, &qj));
+        qj = &qj * &qj % n;
+        if k.bit(bit) {
+            // j -> j + 1, with P = 1
+            let next_u = half(add(&u, &v));
+            v = half(add(&(&d * &u % n), &v));
+            u = next_u;
+            qj = &qj * &q % n;
+        }
+    }
+
+    let zero = BigUint::from(0u32);
+    if u == zero || v == zero {
+        return true;
+    }
+    for _ in 1..s {
+        v = sub(&(&v * &v % n), &add(&qj, &qj));
+        if v == zero {
+            return true;
+        }
+        qj = &qj * &qj % n;
+    }
+    false
+}
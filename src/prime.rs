@@ -0,0 +1,58 @@
+//! A `u64` known to be prime.
+
+use std::fmt;
+
+use crate::is_prime_u64;
+
+/**
+A `u64` that is known to be prime.
+
+A `Prime` can only be created by `Prime::try_new`, which checks primality, or by iterating over a
+`PrimeSet` with `PrimeSetIter::typed`, which checks any primes the set hasn't verified itself.
+Functions that need a prime argument take a `Prime`, so that precondition is checked once, up
+front.
+
+```
+use primes::Prime;
+
+let p = Prime::try_new(101).unwrap();
+assert_eq!(p.get(), 101);
+assert!(Prime::try_new(100).is_none());
+```
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Prime(u64);
+
+impl Prime {
+    /// `Some(Prime)` if `n` is prime, else `None`
+    pub fn try_new(n: u64) -> Option<Prime> {
+        if is_prime_u64(n) {
+            Some(Prime(n))
+        } else {
+            None
+        }
+    }
+
+    // Skip the check, for values already known to be prime
+    pub(crate) fn new_unchecked(n: u64) -> Prime {
+        debug_assert!(is_prime_u64(n));
+        Prime(n)
+    }
+
+    /// The prime as a `u64`
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<Prime> for u64 {
+    fn from(p: Prime) -> u64 {
+        p.0
+    }
+}
+
+impl fmt::Display for Prime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
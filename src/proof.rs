@@ -0,0 +1,277 @@
+//! Primality proofs for numbers with a well-factored neighbor, `n - 1` or `n + 1`.
+
+use crate::modular::inv_mod;
+use crate::primality::jacobi;
+use crate::{gcd, is_prime_u64, is_square, mul_mod, pow_mod};
+
+// How many bases or Lucas parameters to try for each prime factor before giving up. For a prime
+// `n`, at least half of them work for any factor, so running out means `n` is almost certainly
+// composite.
+const MAX_ATTEMPTS: u64 = 100;
+
+// The part of `m` made of the primes in `factors`, with full multiplicity, or `None` if any of
+// them is repeated or is not a prime dividing `m`
+fn factored_part(m: u64, factors: &[u64]) -> Option<u64> {
+    let mut part = 1u64;
+    for (ix, &q) in factors.iter().enumerate() {
+        if !is_prime_u64(q) || !m.is_multiple_of(q) || factors[..ix].contains(&q) {
+            return None;
+        }
+        let mut rest = m;
+        while rest.is_multiple_of(q) {
+            part = part.checked_mul(q)?;
+            rest /= q;
+        }
+    }
+    Some(part)
+}
+
+/**
+A proof that `n` is prime by Pocklington's theorem, made by `prove_prime_n_minus_1`.
+
+If `F` is the part of `n - 1` made up of the primes `q` in `witnesses`, with `F > sqrt(n)`, and
+for each `q` its base `a` satisfies `a^(n - 1) ≡ 1 (mod n)` and `gcd(a^((n - 1) / q) - 1, n) = 1`,
+then every prime factor of `n` is 1 modulo `F`, so larger than `sqrt(n)`, and `n` is prime.
+`verify` checks all of that, including that each `q` is prime.
+**/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PocklingtonCertificate {
+    /// The number proved prime
+    pub n: u64,
+    /// Pairs `(q, a)` of a prime factor `q` of `n - 1` and its base `a`
+    pub witnesses: Vec<(u64, u64)>,
+}
+
+impl PocklingtonCertificate {
+    /// Check the certificate, returning whether it proves `n` prime
+    pub fn verify(&self) -> bool {
+        let n = self.n;
+        if n < 3 || n.is_multiple_of(2) {
+            return false;
+        }
+        let factors: Vec<u64> = self.witnesses.iter().map(|&(q, _)| q).collect();
+        let part = match factored_part(n - 1, &factors) {
+            Some(part) => part,
+            None => return false,
+        };
+        (part as u128) * (part as u128) > n as u128
+            && self.witnesses.iter().all(|&(q, a)| {
+                pow_mod(a, n - 1, n) == 1 && gcd_minus_one(pow_mod(a, (n - 1) / q, n), n) == 1
+            })
+    }
+}
+
+/// Prove `n` prime with Pocklington's theorem, from the distinct prime factors of `n - 1`.
+///
+/// `factors` need not include every prime factor of `n - 1`, as long as the part of `n - 1` made
+/// of those it does include (with their full multiplicity) is larger than `sqrt(n)`. That makes
+/// this suited to numbers of special forms, such as `k * 2^m + 1`, where `n - 1` is easy to
+/// partly factor even when `n` is too large for anything else to prove prime.
+///
+/// Returns `None` if `n` is 2 or composite, if any of `factors` is not a prime factor of `n - 1`,
+/// or if they make up too little of `n - 1`.
+///
+/// ```
+/// use primes::prove_prime_n_minus_1;
+///
+/// // 3 * 2^30 + 1, a prime used for number-theoretic transforms
+/// let n = 3 * (1 << 30) + 1;
+/// let certificate = prove_prime_n_minus_1(n, &[2]).unwrap();
+/// assert!(certificate.verify());
+/// assert_eq!(prove_prime_n_minus_1(n + 2, &[2]), None);
+/// ```
+pub fn prove_prime_n_minus_1(n: u64, factors: &[u64]) -> Option<PocklingtonCertificate> {
+    if n < 3 || n.is_multiple_of(2) {
+        return None;
+    }
+    let part = factored_part(n - 1, factors)?;
+    if (part as u128) * (part as u128) <= n as u128 {
+        return None;
+    }
+    let mut witnesses = Vec::with_capacity(factors.len());
+    for &q in factors {
+        let mut found = None;
+        for a in (2..n).take(MAX_ATTEMPTS as usize) {
+            if pow_mod(a, n - 1, n) != 1 {
+                // Fermat's little theorem fails, so n is composite
+                return None;
+            }
+            match gcd_minus_one(pow_mod(a, (n - 1) / q, n), n) {
+                1 => {
+                    found = Some(a);
+                    break;
+                }
+                g if g != n => return None,
+                _ => (),
+            }
+        }
+        witnesses.push((q, found?));
+    }
+    Some(PocklingtonCertificate { n, witnesses })
+}
+
+// gcd(x - 1, n), for `x < n`
+fn gcd_minus_one(x: u64, n: u64) -> u64 {
+    gcd(if x == 0 { n - 1 } else { x - 1 }, n)
+}
+
+// The Lucas sequence term `U_k(P, Q)` modulo odd `n`, where `d = P^2 - 4Q (mod n)`
+fn lucas_u(k: u64, p: u64, q: u64, d: u64, n: u64) -> u64 {
+    if k == 0 {
+        return 0;
+    }
+    let add = |a: u64, b: u64| ((a as u128 + b as u128) % n as u128) as u64;
+    let sub = |a: u64, b: u64| if a >= b { a - b } else { n - (b - a) };
+    // (a + n) / 2 for odd a, without overflowing
+    let half = |a: u64| {
+        if a.is_multiple_of(2) {
+            a / 2
+        } else {
+            a / 2 + n / 2 + 1
+        }
+    };
+
+    // Walk the bits of k from the top, tracking U_j, V_j and Q^j for the prefix j seen so far
+    let (mut u, mut v, mut qj) = (1 % n, p % n, q % n);
+    for bit in (0..63 - k.leading_zeros()).rev() {
+        // j -> 2j
+        u = mul_mod(u, v, n);
+        v = sub(mul_mod(v, v, n), add(qj, qj));
+        qj = mul_mod(qj, qj, n);
+        if (k >> bit) & 1 == 1 {
+            // j -> j + 1
+            let next_u = half(add(mul_mod(p, u, n), v));
+            v = half(add(mul_mod(d, u, n), mul_mod(p, v, n)));
+            u = next_u;
+            qj = mul_mod(qj, q, n);
+        }
+    }
+    u
+}
+
+// Q = (P^2 - D) / 4 modulo odd `n`
+fn lucas_q(p: u64, d: u64, n: u64) -> u64 {
+    let numerator = mul_mod(p, p, n) as u128 + n as u128 - d as u128;
+    mul_mod((numerator % n as u128) as u64, inv_mod(4, n).unwrap(), n)
+}
+
+/**
+A proof that `n` is prime by Morrison's theorem, made by `prove_prime_n_plus_1`.
+
+Each witness `(q, P)` picks the Lucas sequence `U_k(P, Q)` with discriminant
+`P^2 - 4Q ≡ discriminant (mod n)`, where `(discriminant / n) = -1`. If `F` is the part of `n + 1`
+made up of the primes `q`, with `F > sqrt(n) + 1`, and for each `q`, `gcd(Q, n) = 1`,
+`U_(n + 1) ≡ 0 (mod n)` and `gcd(U_((n + 1) / q), n) = 1`, then every prime factor of `n` is
+`±1` modulo `F`, so at least `F - 1 > sqrt(n)`, and `n` is prime. `verify` checks all of that,
+including that each `q` is prime.
+**/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LucasCertificate {
+    /// The number proved prime
+    pub n: u64,
+    /// The discriminant shared by every witness's sequence, modulo `n`
+    pub discriminant: u64,
+    /// Pairs `(q, P)` of a prime factor `q` of `n + 1` and its Lucas parameter `P`
+    pub witnesses: Vec<(u64, u64)>,
+}
+
+impl LucasCertificate {
+    /// Check the certificate, returning whether it proves `n` prime
+    pub fn verify(&self) -> bool {
+        let n = self.n;
+        if n < 3 || n.is_multiple_of(2) || n == u64::MAX {
+            return false;
+        }
+        let d = self.discriminant % n;
+        if jacobi(d, n) != -1 {
+            return false;
+        }
+        let factors: Vec<u64> = self.witnesses.iter().map(|&(q, _)| q).collect();
+        let part = match factored_part(n + 1, &factors) {
+            Some(part) => part,
+            None => return false,
+        };
+        (part as u128 - 1) * (part as u128 - 1) > n as u128
+            && self.witnesses.iter().all(|&(q, p)| {
+                let big_q = lucas_q(p, d, n);
+                gcd(big_q, n) == 1
+                    && lucas_u(n + 1, p, big_q, d, n) == 0
+                    && gcd(lucas_u((n + 1) / q, p, big_q, d, n), n) == 1
+            })
+    }
+}
+
+/// Prove `n` prime with Morrison's theorem, the `n + 1` counterpart of Pocklington's, from the
+/// distinct prime factors of `n + 1`.
+///
+/// As with `prove_prime_n_minus_1`, `factors` need only cover enough of `n + 1`: the part of it
+/// they make up (with full multiplicity) must be larger than `sqrt(n) + 1`. This suits numbers
+/// such as `k * 2^m - 1`.
+///
+/// Returns `None` if `n` is 2 or composite, if any of `factors` is not a prime factor of `n + 1`,
+/// or if they make up too little of `n + 1`.
+///
+/// ```
+/// use primes::prove_prime_n_plus_1;
+///
+/// // The Mersenne prime 2^61 - 1
+/// let n = (1 << 61) - 1;
+/// let certificate = prove_prime_n_plus_1(n, &[2]).unwrap();
+/// assert!(certificate.verify());
+/// assert_eq!(prove_prime_n_plus_1((1 << 59) - 1, &[2]), None);
+/// ```
+pub fn prove_prime_n_plus_1(n: u64, factors: &[u64]) -> Option<LucasCertificate> {
+    if n < 3 || n.is_multiple_of(2) || n == u64::MAX || is_square(n) {
+        return None;
+    }
+    let part = factored_part(n + 1, factors)?;
+    if (part as u128 - 1) * (part as u128 - 1) <= n as u128 {
+        return None;
+    }
+
+    // Selfridge's choice: the first of 5, -7, 9, -11, ... with (D / n) = -1
+    let mut d_abs = 5u64;
+    let mut negative = false;
+    let d = loop {
+        let d = if negative { n - d_abs % n } else { d_abs % n };
+        match jacobi(d, n) {
+            -1 => break d,
+            0 if !d_abs.is_multiple_of(n) => return None,
+            _ => (),
+        }
+        d_abs += 2;
+        negative = !negative;
+    };
+
+    let mut witnesses = Vec::with_capacity(factors.len());
+    for &q in factors {
+        let mut found = None;
+        for p in 1..=MAX_ATTEMPTS {
+            let big_q = lucas_q(p, d, n);
+            match gcd(big_q, n) {
+                1 => (),
+                g if g != n => return None,
+                _ => continue,
+            }
+            if lucas_u(n + 1, p, big_q, d, n) != 0 {
+                return None;
+            }
+            match gcd(lucas_u((n + 1) / q, p, big_q, d, n), n) {
+                1 => {
+                    found = Some(p);
+                    break;
+                }
+                g if g != n => return None,
+                _ => (),
+            }
+        }
+        witnesses.push((q, found?));
+    }
+    Some(LucasCertificate {
+        n,
+        discriminant: d,
+        witnesses,
+    })
+}
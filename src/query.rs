@@ -0,0 +1,91 @@
+//! A builder for common shapes of prime iteration.
+
+use crate::PrimeSet;
+
+/**
+An iterator over a selection of primes, built up with chained methods. Created by
+`PrimeSet::primes`.
+
+```
+use primes::{PrimeSet, Sieve};
+
+let mut pset = Sieve::new();
+let selected: Vec<u64> = pset
+    .primes()
+    .from(100)
+    .residues_mod(10, &[3, 7])
+    .below(200)
+    .collect();
+assert_eq!(selected, vec![103, 107, 113, 127, 137, 157, 163, 167, 173, 193, 197]);
+```
+
+The primes needed are generated and cached in the `PrimeSet` as iteration goes, the same as
+`PrimeSet::iter`.
+**/
+pub struct PrimeQuery<'a, P: PrimeSet> {
+    p: &'a mut P,
+    from: u64,
+    below: Option<u64>,
+    residues: Option<(u64, Vec<u64>)>,
+    // Index of the next prime to consider, once iteration has started
+    ix: Option<usize>,
+}
+
+impl<'a, P: PrimeSet> PrimeQuery<'a, P> {
+    pub(crate) fn new(p: &'a mut P) -> PrimeQuery<'a, P> {
+        PrimeQuery {
+            p,
+            from: 0,
+            below: None,
+            residues: None,
+            ix: None,
+        }
+    }
+
+    /// Start at the first prime greater than or equal to `n`
+    pub fn from(mut self, n: u64) -> Self {
+        self.from = n;
+        self
+    }
+
+    /// Stop before the first prime greater than or equal to `limit`
+    pub fn below(mut self, limit: u64) -> Self {
+        self.below = Some(limit);
+        self
+    }
+
+    /// Only yield primes `p` where `p % modulus` is one of `residues`
+    ///
+    /// Panics if `modulus == 0`.
+    pub fn residues_mod(mut self, modulus: u64, residues: &[u64]) -> Self {
+        assert!(modulus != 0, "modulus must be nonzero");
+        self.residues = Some((modulus, residues.iter().map(|r| r % modulus).collect()));
+        self
+    }
+}
+
+impl<'a, P: PrimeSet> Iterator for PrimeQuery<'a, P> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let mut ix = match self.ix {
+            Some(ix) => ix,
+            None => self.p.find(self.from).0,
+        };
+        loop {
+            let p = self.p.get(ix);
+            ix += 1;
+            self.ix = Some(ix);
+            if let Some(limit) = self.below {
+                if p >= limit {
+                    // Don't look any further next time either
+                    self.ix = Some(ix - 1);
+                    return None;
+                }
+            }
+            match self.residues {
+                Some((modulus, ref residues)) if !residues.contains(&(p % modulus)) => continue,
+                _ => return Some(p),
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Random primes of a given size, for exercises in public-key cryptography.
+//!
+//! With the `rand` feature, randomness comes from any `rand::RngCore`, such as
+//! `rand::thread_rng()` or a seeded `StdRng`. None of this is suitable for real keys: a `u64` is
+//! far too small, and the `BigUint` versions (which also need the `bigint` feature) make no attempt
+//! to be constant-time.
+
+#[cfg(all(feature = "rand", feature = "bigint"))]
+use num_bigint::BigUint;
+#[cfg(feature = "rand")]
+use rand::RngCore;
+
+#[cfg(all(feature = "rand", feature = "bigint"))]
+use crate::is_prime_bpsw_big;
+use crate::is_prime_u64;
+#[cfg(feature = "rand")]
+use crate::{for_each_prime_in, pow_mod, Primality, PrimalityTest};
+
+// A random odd number with exactly `bits` bits
+#[cfg(feature = "rand")]
+fn random_odd<R: RngCore + ?Sized>(bits: u32, rng: &mut R) -> u64 {
+    let top = 1 << (bits - 1);
+    (rng.next_u64() & (top - 1 + top)) | top | 1
+}
+
+// A random number with exactly `bits` bits that passes `is_prime`, for `bits >= 2`
+#[cfg(feature = "rand")]
+fn random_prime_by<R, F>(bits: u32, rng: &mut R, is_prime: &mut F) -> u64
+where
+    R: RngCore + ?Sized,
+    F: FnMut(u64) -> bool,
+{
+    loop {
+        let n = random_odd(bits, rng);
+        if is_prime(n) {
+            return n;
+        }
+    }
+}
+
+/// A random strong prime with exactly `bits` bits, made with Gordon's algorithm, drawing
+/// randomness from `rng`.
+///
+/// A strong prime `p` has a large prime factor `r` of `p - 1`, a large prime factor `s` of
+/// `p + 1`, and a large prime factor of `r - 1`, which defeats Pollard's `p - 1` and Williams'
+/// `p + 1` factoring methods. Here `r` and `s` each have at least `(bits - 10) / 2` bits, and the
+/// factor of `r - 1` one fewer.
+///
+/// Panics if `bits` is less than 20 or more than 64.
+///
+/// ```
+/// use primes::{is_prime, random_strong_prime};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(1);
+/// let p = random_strong_prime(64, &mut rng);
+/// assert!(p >> 63 == 1 && is_prime(p));
+/// ```
+#[cfg(feature = "rand")]
+pub fn random_strong_prime<R: RngCore + ?Sized>(bits: u32, rng: &mut R) -> u64 {
+    strong_prime_by(bits, rng, is_prime_u64)
+}
+
+/// A random strong prime with exactly `bits` bits, as `random_strong_prime` makes, with every
+/// candidate checked by `tester`.
+///
+/// A `Primality::Probable` verdict is accepted, so with a probabilistic tester the result, or one
+/// of the large factors it was built from, may be a pseudoprime.
+///
+/// Panics if `bits` is less than 20 or more than 64.
+///
+/// ```
+/// use primes::{is_prime, random_strong_prime_with, StagedMillerRabin};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(1);
+/// let p = random_strong_prime_with(64, &mut rng, &StagedMillerRabin);
+/// assert!(p >> 63 == 1 && is_prime(p));
+/// ```
+#[cfg(feature = "rand")]
+pub fn random_strong_prime_with<R, T>(bits: u32, rng: &mut R, tester: &T) -> u64
+where
+    R: RngCore + ?Sized,
+    T: PrimalityTest,
+{
+    strong_prime_by(bits, rng, |n| tester.test(n) != Primality::Composite)
+}
+
+// Gordon's algorithm, checking candidates with `is_prime`
+#[cfg(feature = "rand")]
+fn strong_prime_by<R, F>(bits: u32, rng: &mut R, mut is_prime: F) -> u64
+where
+    R: RngCore + ?Sized,
+    F: FnMut(u64) -> bool,
+{
+    assert!(
+        (20..=64).contains(&bits),
+        "strong primes need between 20 and 64 bits"
+    );
+    let large = (bits - 10) / 2;
+    let lo = 1u128 << (bits - 1);
+    let hi = 1u128 << bits;
+    loop {
+        let s = random_prime_by(large, rng, &mut is_prime);
+        let t = random_prime_by(large - 1, rng, &mut is_prime);
+        // The first prime r = 2it + 1, starting from a random i in [1, 3)
+        let mut i = 1 + (rng.next_u64() & 1);
+        let r = loop {
+            let r = 2 * i * t + 1;
+            if is_prime(r) {
+                break r;
+            }
+            i += 1;
+        };
+        // Start again in the rare case that r is too large to leave room for p
+        let step = 2 * r as u128 * s as u128;
+        if r == s || step > hi - lo {
+            continue;
+        }
+
+        // p0 = 1 (mod r) and p0 = -1 (mod s), and so is p0 + 2jrs for any j
+        let p0 = 2 * pow_mod(s % r, r - 2, r) as u128 * s as u128 - 1;
+        let first = (lo - p0).div_ceil(step);
+        let last = (hi - 1 - p0) / step;
+        let mut j = first + rng.next_u64() as u128 % ((last - first) / 2 + 1);
+        while j <= last {
+            let p = (p0 + j * step) as u64;
+            if is_prime(p) {
+                return p;
+            }
+            j += 1;
+        }
+    }
+}
+
+// A random number with exactly `bits` bits, odd if `odd` is set
+#[cfg(all(feature = "rand", feature = "bigint"))]
+fn random_bits_big<R: RngCore + ?Sized>(bits: u64, odd: bool, rng: &mut R) -> BigUint {
+    let mut bytes = vec![0u8; bits.div_ceil(8) as usize];
+    rng.fill_bytes(&mut bytes);
+    let mut n = BigUint::from_bytes_le(&bytes) >> (8 * bytes.len() as u64 - bits);
+    n.set_bit(bits - 1, true);
+    if odd {
+        n.set_bit(0, true);
+    }
+    n
+}
+
+// A random number below `bound`, nearly uniform: 64 spare bits keep the bias below 2^-64
+#[cfg(all(feature = "rand", feature = "bigint"))]
+fn random_below_big<R: RngCore + ?Sized>(bound: &BigUint, rng: &mut R) -> BigUint {
+    random_bits_big(bound.bits() + 64, false, rng) % bound
+}
+
+// A random probable prime with exactly `bits` bits, for `bits >= 2`
+#[cfg(all(feature = "rand", feature = "bigint"))]
+fn random_prime_big<R: RngCore + ?Sized>(bits: u64, rng: &mut R) -> BigUint {
+    loop {
+        let n = random_bits_big(bits, true, rng);
+        if is_prime_bpsw_big(&n) {
+            return n;
+        }
+    }
+}
+
+/// A random strong prime with exactly `bits` bits, as `random_strong_prime` makes, but of any
+/// size.
+///
+/// Primality is checked with `is_prime_bpsw_big`, so past 2^64 the result is a probable prime,
+/// though no composite is known to pass that test.
+///
+/// Panics if `bits` is less than 20.
+///
+/// ```
+/// use num_bigint::BigUint;
+/// use primes::{is_prime_bpsw_big, random_strong_prime_big};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(1);
+/// let p = random_strong_prime_big(256, &mut rng);
+/// assert_eq!(p.bits(), 256);
+/// assert!(is_prime_bpsw_big(&p));
+/// ```
+#[cfg(all(feature = "rand", feature = "bigint"))]
+pub fn random_strong_prime_big<R: RngCore + ?Sized>(bits: u64, rng: &mut R) -> BigUint {
+    assert!(bits >= 20, "strong primes need at least 20 bits");
+    let large = (bits - 10) / 2;
+    let lo = BigUint::from(1u32) << (bits - 1);
+    let hi = BigUint::from(1u32) << bits;
+    loop {
+        let s = random_prime_big(large, rng);
+        let t = random_prime_big(large - 1, rng);
+        // The first prime r = 2it + 1, starting from a random i in [1, 3)
+        let mut r = &t * (2 + 2 * (rng.next_u64() & 1)) + 1u32;
+        while !is_prime_bpsw_big(&r) {
+            r += &t * 2u32;
+        }
+        // Start again in the rare case that r is too large to leave room for p
+        let step = &r * &s * 2u32;
+        if r == s || step > &hi - &lo {
+            continue;
+        }
+
+        // p0 = 1 (mod r) and p0 = -1 (mod s), and so is p0 + 2jrs for any j
+        let p0 = (&s % &r).modpow(&(&r - 2u32), &r) * &s * 2u32 - 1u32;
+        let first = (&lo - &p0 + &step - 1u32) / &step;
+        let last = (&hi - 1u32 - &p0) / &step;
+        let mut p =
+            (&first + random_below_big(&((&last - &first) / 2u32 + 1u32), rng)) * &step + p0;
+        while p < hi {
+            if is_prime_bpsw_big(&p) {
+                return p;
+            }
+            p += &step;
+        }
+    }
+}
+
+/// Choose `k` distinct primes uniformly at random from the inclusive range `[lo, hi]`, drawing
+/// randomness from `rng`, and return them in increasing order. Returns `None` if the range holds
+/// fewer than `k` primes.
+///
+/// This sieves the whole range once and then samples from the primes it found, which for dense
+/// ranges is much faster than testing random numbers until enough of them are prime. It keeps
+/// every prime in the range in memory while it samples, though.
+///
+/// ```
+/// use primes::sample_primes_in;
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(1);
+/// let sample = sample_primes_in(1_000, 2_000, 10, &mut rng).unwrap();
+/// assert_eq!(sample.len(), 10);
+/// assert!(sample.windows(2).all(|w| w[0] < w[1]));
+/// assert!(sample.iter().all(|&p| p >= 1_000 && p <= 2_000 && primes::is_prime(p)));
+/// assert_eq!(sample_primes_in(24, 28, 1, &mut rng), None);
+/// ```
+#[cfg(feature = "rand")]
+pub fn sample_primes_in<R: RngCore + ?Sized>(
+    lo: u64,
+    hi: u64,
+    k: usize,
+    rng: &mut R,
+) -> Option<Vec<u64>> {
+    let mut found = Vec::new();
+    for_each_prime_in(lo, hi, |p| found.push(p));
+    if found.len() < k {
+        return None;
+    }
+    // A partial Fisher-Yates shuffle, leaving the sample at the front
+    for i in 0..k {
+        let j = i + (rng.next_u64() % (found.len() - i) as u64) as usize;
+        found.swap(i, j);
+    }
+    found.truncate(k);
+    found.sort_unstable();
+    Some(found)
+}
+
+/// Whether `p` is a Blum prime: a prime with `p ≡ 3 (mod 4)`.
+///
+/// ```
+/// use primes::is_blum_prime;
+///
+/// let blum: Vec<u64> = (0..50).filter(|&p| is_blum_prime(p)).collect();
+/// assert_eq!(blum, vec![3, 7, 11, 19, 23, 31, 43, 47]);
+/// ```
+pub fn is_blum_prime(p: u64) -> bool {
+    p % 4 == 3 && is_prime_u64(p)
+}
+
+/// A random Blum integer `n = p * q` with exactly `bits` bits, drawing randomness from `rng`.
+/// Returns `(n, p, q)`, where `p < q` are Blum primes (see `is_blum_prime`).
+///
+/// `p` has `(bits - 1) / 2` bits, and `q` is whatever is left, so the two are about the same size.
+/// These are the moduli the Blum-Blum-Shub generator uses.
+///
+/// Panics if `bits` is less than 5 or more than 64.
+///
+/// ```
+/// use primes::{is_blum_prime, random_blum_integer};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(1);
+/// let (n, p, q) = random_blum_integer(64, &mut rng);
+/// assert_eq!(n >> 63, 1);
+/// assert_eq!(n, p * q);
+/// assert!(is_blum_prime(p) && is_blum_prime(q));
+/// ```
+#[cfg(feature = "rand")]
+pub fn random_blum_integer<R: RngCore + ?Sized>(bits: u32, rng: &mut R) -> (u64, u64, u64) {
+    assert!(
+        (5..=64).contains(&bits),
+        "Blum integers need between 5 and 64 bits"
+    );
+    let p = loop {
+        let p = random_odd((bits - 1) / 2, rng) | 2;
+        if is_prime_u64(p) {
+            break p;
+        }
+    };
+    // q is in [2^(bits - 1) / p, 2^bits / p), which makes it larger than p
+    let lo = ((1u128 << (bits - 1)) as u64).div_ceil(p);
+    let hi = ((1u128 << bits) - 1) as u64 / p;
+    loop {
+        let q = (lo + rng.next_u64() % (hi - lo + 1)) | 3;
+        if q <= hi && is_blum_prime(q) {
+            return (p * q, p, q);
+        }
+    }
+}
@@ -0,0 +1,1167 @@
+//! Segmented sieving, for working with primes in a window without caching everything below it.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use crate::{is_prime, isqrt, next_prime, DefaultPrimeSet, PrimeSet, PrimeSetBasics};
+
+/// The number of integers sieved at once
+pub(crate) const SEGMENT_LEN: u64 = 1 << 16;
+
+/// Expand `base` until it holds every prime up to `root`
+fn grow_base_primes(base: &mut DefaultPrimeSet, root: u64) {
+    let mut largest = *base.list().last().unwrap();
+    while largest < root {
+        largest = base.expand_next();
+    }
+}
+
+/// The first multiple of `m` in `[lo, hi]`, or `None` if there isn't one
+fn first_multiple(lo: u64, hi: u64, m: u64) -> Option<u64> {
+    let first = match lo % m {
+        0 => lo,
+        r => lo.checked_add(m - r)?,
+    };
+    Some(first).filter(|&first| first <= hi)
+}
+
+/// The first multiple of `p` in `[lo, hi]` that is at least `p * p`, which is where sieving by `p`
+/// starts, or `None` if there isn't one
+fn first_sieved_multiple(lo: u64, hi: u64, p: u64) -> Option<u64> {
+    let first = first_multiple(lo, hi, p)?;
+    Some(first.max(p * p)).filter(|&start| start <= hi)
+}
+
+/// The primes in `base` up to `sqrt(hi)`, which are all that's needed to sieve up to `hi`
+fn sieving_primes(base: &DefaultPrimeSet, hi: u64) -> &[u64] {
+    let root = isqrt(hi);
+    let list = base.list();
+    &list[..list.partition_point(|&p| p <= root)]
+}
+
+/// Clear the flags of the composites in the segment `[lo, lo + flags.len())`, and of 0 and 1,
+/// given every prime up to the square root of its last value
+fn strike_composites<I: IntoIterator<Item = u64>>(flags: &mut [bool], lo: u64, primes: I) {
+    let hi = lo + (flags.len() as u64 - 1);
+    for n in lo..=hi.min(1) {
+        flags[(n - lo) as usize] = false;
+    }
+    for p in primes {
+        let start = match first_sieved_multiple(lo, hi, p) {
+            Some(start) => start,
+            None => continue,
+        };
+        let mut ix = (start - lo) as usize;
+        while ix < flags.len() {
+            flags[ix] = false;
+            ix += p as usize;
+        }
+    }
+}
+
+/**
+Sieves an inclusive range `[lo, hi]` one segment at a time, using a `DefaultPrimeSet` for the base
+primes up to `sqrt(hi)`, and lends out each segment's flags in turn.
+
+Segments start at `lo`, `lo + 2^16`, `lo + 2 * 2^16`, ..., and only the final segment may be
+shorter than 2^16. This is what the other segmented functions are built on, for analyses they
+don't cover: each segment can be inspected in place without collecting its primes into a `Vec`.
+Since the flags are reused from one segment to the next, this is not an `Iterator`; call
+`next_segment` in a `while let` loop instead.
+
+```
+use primes::SegmentIter;
+
+// Count the primes up to 10^6 in each residue class modulo 4
+let mut counts = [0; 4];
+let mut segments = SegmentIter::new(0, 1_000_000);
+while let Some((start, flags)) = segments.next_segment() {
+    for (ix, _) in flags.iter().enumerate().filter(|&(_, &is_prime)| is_prime) {
+        counts[((start + ix as u64) % 4) as usize] += 1;
+    }
+}
+assert_eq!(counts, [0, 39_175, 1, 39_322]);
+```
+**/
+pub struct SegmentIter {
+    base: DefaultPrimeSet,
+    next_lo: Option<u64>,
+    hi: u64,
+    flags: Vec<bool>,
+}
+
+impl SegmentIter {
+    /// Sieve the inclusive range `[lo, hi]`; the range is empty if `lo > hi`
+    pub fn new(lo: u64, hi: u64) -> SegmentIter {
+        SegmentIter {
+            base: DefaultPrimeSet::new(),
+            next_lo: if lo <= hi { Some(lo) } else { None },
+            hi,
+            flags: Vec::new(),
+        }
+    }
+
+    /// Sieve the next segment, returning its first value and a flag for each value in it that is
+    /// `true` for primes, or `None` once the whole range has been sieved
+    pub fn next_segment(&mut self) -> Option<(u64, &[bool])> {
+        let (lo, hi) = self.next_bounds()?;
+        self.flags.clear();
+        self.flags.resize((hi - lo + 1) as usize, true);
+        strike_composites(
+            &mut self.flags,
+            lo,
+            sieving_primes(&self.base, hi).iter().cloned(),
+        );
+        Some((lo, &self.flags))
+    }
+
+    /// Move on to the next segment without sieving it, returning its bounds once the base primes
+    /// reach its square root; for sieves that track something other than primality
+    pub(crate) fn next_bounds(&mut self) -> Option<(u64, u64)> {
+        let lo = self.next_lo?;
+        let hi = lo.saturating_add(SEGMENT_LEN - 1).min(self.hi);
+        self.next_lo = if hi < self.hi { Some(hi + 1) } else { None };
+        grow_base_primes(&mut self.base, isqrt(hi));
+        Some((lo, hi))
+    }
+
+    /// The base primes needed to sieve a segment ending at `hi`, once `next_bounds` has reached it
+    pub(crate) fn base_primes(&self, hi: u64) -> &[u64] {
+        sieving_primes(&self.base, hi)
+    }
+}
+
+/// An iterator over the primes in an inclusive range, found by segmented sieving
+pub(crate) struct SegmentedPrimes {
+    segments: SegmentIter,
+    lo: u64,
+    ix: usize,
+}
+
+impl SegmentedPrimes {
+    pub(crate) fn new(lo: u64, hi: u64) -> SegmentedPrimes {
+        SegmentedPrimes {
+            segments: SegmentIter::new(lo, hi),
+            lo,
+            ix: 0,
+        }
+    }
+}
+
+impl Iterator for SegmentedPrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            while self.ix < self.segments.flags.len() {
+                self.ix += 1;
+                if self.segments.flags[self.ix - 1] {
+                    return Some(self.lo + (self.ix - 1) as u64);
+                }
+            }
+            self.lo = self.segments.next_segment()?.0;
+            self.ix = 0;
+        }
+    }
+}
+
+/**
+An iterator over `(n, spf)` for each `n` in an inclusive range, where `spf` is the smallest prime
+factor of `n`. Created by `spf_range`.
+**/
+pub struct SpfRange {
+    segments: SegmentIter,
+    lo: u64,
+    spf: Vec<u64>,
+    ix: usize,
+}
+
+impl SpfRange {
+    // Fill `spf` for the next segment, or return false if there isn't one
+    fn next_segment(&mut self) -> bool {
+        let (lo, hi) = match self.segments.next_bounds() {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+        self.lo = lo;
+        self.ix = 0;
+
+        self.spf.clear();
+        self.spf.resize((hi - lo + 1) as usize, 0);
+        for &p in self.segments.base_primes(hi) {
+            let start = match first_sieved_multiple(lo, hi, p) {
+                Some(start) => start,
+                None => continue,
+            };
+            let mut ix = (start - lo) as usize;
+            while ix < self.spf.len() {
+                if self.spf[ix] == 0 {
+                    self.spf[ix] = p;
+                }
+                ix += p as usize;
+            }
+        }
+        true
+    }
+}
+
+impl Iterator for SpfRange {
+    type Item = (u64, u64);
+    fn next(&mut self) -> Option<(u64, u64)> {
+        while self.ix >= self.spf.len() {
+            if !self.next_segment() {
+                return None;
+            }
+        }
+        let n = self.lo + self.ix as u64;
+        let spf = match self.spf[self.ix] {
+            // Nothing up to sqrt(n) divides n, so n is prime
+            0 => n,
+            p => p,
+        };
+        self.ix += 1;
+        Some((n, spf))
+    }
+}
+
+/// Iterate over each `n` in the inclusive range `[lo, hi]` together with its smallest prime
+/// factor, skipping 0 and 1, which have none.
+///
+/// The range is sieved one segment at a time with the primes up to `sqrt(hi)`, so windows far
+/// from zero cost no more than windows near it. This is the building block for factoring many
+/// consecutive numbers at once: divide `n` by its smallest prime factor and look up the rest.
+///
+/// ```
+/// use primes::spf_range;
+///
+/// let spfs: Vec<(u64, u64)> = spf_range(0, 10).collect();
+/// assert_eq!(
+///     spfs,
+///     vec![(2, 2), (3, 3), (4, 2), (5, 5), (6, 2), (7, 7), (8, 2), (9, 3), (10, 2)]
+/// );
+/// ```
+pub fn spf_range(lo: u64, hi: u64) -> SpfRange {
+    let lo = lo.max(2);
+    SpfRange {
+        segments: SegmentIter::new(lo, hi),
+        lo,
+        spf: Vec::new(),
+        ix: 0,
+    }
+}
+
+/**
+An iterator over `(n, Λ(n))` for each `n` in an inclusive range, where `Λ` is the von Mangoldt
+function. Created by `mangoldt_range`.
+**/
+pub struct MangoldtRange {
+    spf: SpfRange,
+}
+
+impl Iterator for MangoldtRange {
+    type Item = (u64, f64);
+    fn next(&mut self) -> Option<(u64, f64)> {
+        let (n, p) = self.spf.next()?;
+        let mut rest = n;
+        while rest % p == 0 {
+            rest /= p;
+        }
+        let lambda = if rest == 1 { (p as f64).ln() } else { 0.0 };
+        Some((n, lambda))
+    }
+}
+
+/// Iterate over each `n` in the inclusive range `[lo, hi]` together with the von Mangoldt function
+/// `Λ(n)`, skipping 0 and 1 (`Λ(1) = 0`).
+///
+/// This runs on `spf_range`: `n` is a prime power exactly when dividing out its smallest prime
+/// factor leaves 1, so no per-number primality test is needed.
+///
+/// ```
+/// use primes::mangoldt_range;
+///
+/// // Chebyshev's ψ(10) = ln(2^3 * 3^2 * 5 * 7) = ln(2520)
+/// let psi: f64 = mangoldt_range(0, 10).map(|(_, lambda)| lambda).sum();
+/// assert!((psi - 2520f64.ln()).abs() < 1e-12);
+/// ```
+pub fn mangoldt_range(lo: u64, hi: u64) -> MangoldtRange {
+    MangoldtRange {
+        spf: spf_range(lo, hi),
+    }
+}
+
+/**
+An iterator over `(n, φ(n))` for each `n` in an inclusive range, where `φ` is Euler's totient
+function. Created by `totient_range`.
+**/
+pub struct TotientRange {
+    segments: SegmentIter,
+    lo: u64,
+    // The unfactored part of each value, and the totient so far
+    rem: Vec<u64>,
+    phi: Vec<u64>,
+    ix: usize,
+}
+
+impl TotientRange {
+    // Fill `phi` for the next segment, or return false if there isn't one
+    fn next_segment(&mut self) -> bool {
+        let (lo, hi) = match self.segments.next_bounds() {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+        self.lo = lo;
+        self.ix = 0;
+
+        self.rem.clear();
+        self.rem.extend(lo..=hi);
+        self.phi.clear();
+        self.phi.extend(lo..=hi);
+        for &p in self.segments.base_primes(hi) {
+            let first = match first_multiple(lo, hi, p) {
+                Some(first) => first,
+                None => continue,
+            };
+            let mut ix = (first - lo) as usize;
+            while ix < self.rem.len() {
+                self.phi[ix] -= self.phi[ix] / p;
+                while self.rem[ix].is_multiple_of(p) {
+                    self.rem[ix] /= p;
+                }
+                ix += p as usize;
+            }
+        }
+        // Whatever is left has no factor up to sqrt(hi), so it is 1 or a prime
+        for (phi, &rem) in self.phi.iter_mut().zip(self.rem.iter()) {
+            if rem > 1 {
+                *phi -= *phi / rem;
+            }
+        }
+        true
+    }
+}
+
+impl Iterator for TotientRange {
+    type Item = (u64, u64);
+    fn next(&mut self) -> Option<(u64, u64)> {
+        while self.ix >= self.phi.len() {
+            if !self.next_segment() {
+                return None;
+            }
+        }
+        let n = self.lo + self.ix as u64;
+        let phi = self.phi[self.ix];
+        self.ix += 1;
+        Some((n, phi))
+    }
+}
+
+/// Iterate over each `n` in the inclusive range `[lo, hi]` together with Euler's totient `φ(n)`,
+/// the count of integers in `1..=n` coprime to `n`. 0 is skipped.
+///
+/// Like `spf_range`, this sieves a segment at a time with the primes up to `sqrt(hi)`, so a
+/// window such as `[10^12, 10^12 + 10^6]` only needs the primes below `10^6`.
+///
+/// ```
+/// use primes::totient_range;
+///
+/// let phis: Vec<u64> = totient_range(0, 10).map(|(_, phi)| phi).collect();
+/// assert_eq!(phis, vec![1, 1, 2, 2, 4, 2, 6, 4, 6, 4]);
+/// ```
+pub fn totient_range(lo: u64, hi: u64) -> TotientRange {
+    let lo = lo.max(1);
+    TotientRange {
+        segments: SegmentIter::new(lo, hi),
+        lo,
+        rem: Vec::new(),
+        phi: Vec::new(),
+        ix: 0,
+    }
+}
+
+/// Find which numbers in the inclusive range `[lo, hi]` are squarefree: entry `i` of the result
+/// is `true` when no square greater than 1 divides `lo + i`.
+///
+/// Instead of factoring each number, this strikes out the multiples of `p^2` for every prime `p`
+/// up to `sqrt(hi)`, so windows far from zero (say near 10^14) are as cheap as any other.
+///
+/// ```
+/// use primes::squarefree_in_range;
+///
+/// assert_eq!(
+///     squarefree_in_range(8, 13),
+///     vec![false, false, true, true, false, true]
+/// );
+/// ```
+pub fn squarefree_in_range(lo: u64, hi: u64) -> Vec<bool> {
+    if lo > hi {
+        return Vec::new();
+    }
+    let mut squarefree = vec![true; (hi - lo + 1) as usize];
+    let root = isqrt(hi);
+    let mut base = DefaultPrimeSet::new();
+    grow_base_primes(&mut base, root);
+    for &p in base.list() {
+        if p > root {
+            break;
+        }
+        let square = p * p;
+        let first = match first_multiple(lo, hi, square) {
+            Some(first) => first,
+            None => continue,
+        };
+        let mut ix = (first - lo) as usize;
+        while ix < squarefree.len() {
+            squarefree[ix] = false;
+            ix += square as usize;
+        }
+    }
+    squarefree
+}
+
+/// Call `f` with the count of distinct prime factors ω(n) and of prime factors with multiplicity
+/// Ω(n) for each `n` in the inclusive range `[lo, hi]` in turn, with 0 counted as having none
+fn prime_factor_counts<F: FnMut(u8, u8)>(lo: u64, hi: u64, mut f: F) {
+    let mut segments = SegmentIter::new(lo, hi);
+    let mut counts = Vec::new();
+    // The unfactored part of each value
+    let mut rem = Vec::new();
+    while let Some((lo, hi)) = segments.next_bounds() {
+        counts.clear();
+        counts.resize((hi - lo + 1) as usize, (0u8, 0u8));
+        rem.clear();
+        rem.extend(lo..=hi);
+        for &p in segments.base_primes(hi) {
+            let first = match first_multiple(lo, hi, p) {
+                Some(first) => first,
+                None => continue,
+            };
+            let mut ix = (first - lo) as usize;
+            while ix < rem.len() {
+                if rem[ix] != 0 {
+                    counts[ix].0 += 1;
+                    while rem[ix].is_multiple_of(p) {
+                        rem[ix] /= p;
+                        counts[ix].1 += 1;
+                    }
+                }
+                ix += p as usize;
+            }
+        }
+        // Whatever is left has no factor up to sqrt(hi), so it is 0, 1 or a prime
+        for (&(omega, big_omega), &rem) in counts.iter().zip(rem.iter()) {
+            let extra = (rem > 1) as u8;
+            f(omega + extra, big_omega + extra);
+        }
+    }
+}
+
+/// The Möbius function μ(n) for each `n` in the inclusive range `[lo, hi]`: entry `i` of the
+/// result is μ(lo + i), which is 0 if a square divides `lo + i`, and otherwise 1 or -1 as it has
+/// an even or odd number of prime factors. μ(0) is taken to be 0.
+///
+/// Like `totient_range`, this sieves by the primes up to `sqrt(hi)`, so it costs the same far
+/// from zero as near it; see `mobius` for single values.
+///
+/// ```
+/// use primes::mobius_range;
+///
+/// assert_eq!(mobius_range(1, 10), vec![1, -1, -1, 0, -1, 1, -1, 0, 0, 1]);
+/// ```
+pub fn mobius_range(lo: u64, hi: u64) -> Vec<i8> {
+    let mut mu = Vec::with_capacity(hi.saturating_sub(lo).saturating_add(1) as usize);
+    prime_factor_counts(lo, hi, |omega, big_omega| {
+        mu.push(match (omega, big_omega) {
+            (w, k) if w != k => 0,
+            (w, _) if w % 2 == 0 => 1,
+            _ => -1,
+        })
+    });
+    if lo == 0 && !mu.is_empty() {
+        mu[0] = 0;
+    }
+    mu
+}
+
+/// The Liouville function λ(n) for each `n` in the inclusive range `[lo, hi]`: entry `i` of the
+/// result is 1 or -1 as `lo + i` has an even or odd number of prime factors, counted with
+/// multiplicity. λ(0) is taken to be 0.
+///
+/// This is sieved in the same way as `mobius_range`; see `liouville` for single values.
+///
+/// ```
+/// use primes::liouville_range;
+///
+/// assert_eq!(liouville_range(1, 10), vec![1, -1, -1, 1, -1, 1, -1, -1, 1, 1]);
+/// ```
+pub fn liouville_range(lo: u64, hi: u64) -> Vec<i8> {
+    let mut lambda = Vec::with_capacity(hi.saturating_sub(lo).saturating_add(1) as usize);
+    prime_factor_counts(lo, hi, |_, big_omega| {
+        lambda.push(if big_omega % 2 == 0 { 1 } else { -1 })
+    });
+    if lo == 0 && !lambda.is_empty() {
+        lambda[0] = 0;
+    }
+    lambda
+}
+
+/// Count the primes in the inclusive range `[lo, hi]`
+pub(crate) fn count_primes_in(lo: u64, hi: u64) -> u64 {
+    let mut segments = SegmentIter::new(lo, hi);
+    let mut count = 0;
+    while let Some((_, flags)) = segments.next_segment() {
+        count += flags.iter().filter(|&&b| b).count() as u64;
+    }
+    count
+}
+
+/// Call `f` on every prime in the inclusive range `[lo, hi]`, in increasing order.
+///
+/// This sieves one segment at a time and calls `f` straight from the segment's flags, so it
+/// allocates nothing beyond a single segment buffer and the base primes up to `sqrt(hi)`. For
+/// "do something with every prime below 10^10" workloads, it's the fastest option here.
+///
+/// ```
+/// use primes::for_each_prime_in;
+///
+/// let mut sum = 0;
+/// for_each_prime_in(10, 30, |p| sum += p);
+/// assert_eq!(sum, 11 + 13 + 17 + 19 + 23 + 29);
+/// ```
+pub fn for_each_prime_in<F: FnMut(u64)>(lo: u64, hi: u64, mut f: F) {
+    let mut segments = SegmentIter::new(lo, hi);
+    while let Some((seg_lo, flags)) = segments.next_segment() {
+        for (ix, &is_prime) in flags.iter().enumerate() {
+            if is_prime {
+                f(seg_lo + ix as u64);
+            }
+        }
+    }
+}
+
+/// Distance between the checkpoints cached by `prime_pi`. A multiple of `SEGMENT_LEN`.
+const PI_CHECKPOINT_STEP: u64 = 16 * SEGMENT_LEN;
+
+thread_local! {
+    // Entry `k` is the number of primes less than `k * PI_CHECKPOINT_STEP`
+    static PI_CHECKPOINTS: RefCell<Vec<u64>> = RefCell::new(vec![0]);
+}
+
+/// Count the primes less than or equal to `x`, often written π(x).
+///
+/// Counts at regularly spaced checkpoints are cached per thread, so repeated calls only need to
+/// sieve from the nearest checkpoint below `x`, plus whatever new ground the cache hasn't covered
+/// yet.
+///
+/// ```
+/// use primes::prime_pi;
+///
+/// assert_eq!(prime_pi(10), 4);
+/// assert_eq!(prime_pi(1_000_000), 78_498);
+/// ```
+pub fn prime_pi(x: u64) -> u64 {
+    PI_CHECKPOINTS.with(|checkpoints| {
+        let mut checkpoints = checkpoints.borrow_mut();
+        let target = (x / PI_CHECKPOINT_STEP) as usize;
+
+        if checkpoints.len() <= target {
+            let start = (checkpoints.len() - 1) as u64 * PI_CHECKPOINT_STEP;
+            let end = target as u64 * PI_CHECKPOINT_STEP - 1;
+            let mut count = *checkpoints.last().unwrap();
+            let mut segments = SegmentIter::new(start, end);
+            while let Some((lo, flags)) = segments.next_segment() {
+                count += flags.iter().filter(|&&b| b).count() as u64;
+                let next = lo + flags.len() as u64;
+                if next.is_multiple_of(PI_CHECKPOINT_STEP) {
+                    checkpoints.push(count);
+                }
+            }
+        }
+
+        checkpoints[target] + count_primes_in(target as u64 * PI_CHECKPOINT_STEP, x)
+    })
+}
+
+/**
+An index of prime counts at regularly spaced checkpoints up to a limit, for answering many
+counting queries below that limit. Created by `PiCheckpoints::build`.
+
+Each query starts from the nearest checkpoint and sieves at most `step` values from there, so
+with a `step` of a few segments, queries cost about the same wherever they land.
+
+```
+use primes::PiCheckpoints;
+
+let index = PiCheckpoints::build(10_000_000, 1 << 16);
+assert_eq!(index.prime_pi(1_000_000), Some(78_498));
+assert_eq!(index.nth_prime(78_498), Some(999_983));
+assert_eq!(index.rank(999_983), Some(78_498));
+assert_eq!(index.rank(999_981), None);
+assert_eq!(index.prime_pi(10_000_001), None);
+```
+**/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiCheckpoints {
+    limit: u64,
+    step: u64,
+    // Entry `k` is the number of primes less than `k * step`
+    counts: Vec<u64>,
+}
+
+impl PiCheckpoints {
+    /// Sieve every number up to and including `limit` once, recording the prime count every
+    /// `step` numbers.
+    ///
+    /// Panics if `step == 0`.
+    pub fn build(limit: u64, step: u64) -> PiCheckpoints {
+        assert!(step > 0, "checkpoint step must be positive");
+        let mut counts = vec![0; (limit / step) as usize + 1];
+        let mut segments = SegmentIter::new(0, limit);
+        while let Some((lo, flags)) = segments.next_segment() {
+            for (ix, _) in flags.iter().enumerate().filter(|&(_, &b)| b) {
+                let p = lo + ix as u64;
+                if let Some(count) = counts.get_mut((p / step) as usize + 1) {
+                    *count += 1;
+                }
+            }
+        }
+        for k in 1..counts.len() {
+            counts[k] += counts[k - 1];
+        }
+        PiCheckpoints {
+            limit,
+            step,
+            counts,
+        }
+    }
+
+    /// The largest number the index covers
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// The distance between checkpoints
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    /// The number of primes less than or equal to `n`, or `None` if `n` is past `limit()`
+    pub fn prime_pi(&self, n: u64) -> Option<u64> {
+        if n > self.limit {
+            return None;
+        }
+        let k = n / self.step;
+        Some(self.counts[k as usize] + count_primes_in(k * self.step, n))
+    }
+
+    /// The `n`th prime, counting 2 as the first, or `None` if `n == 0` or the `n`th prime is past
+    /// `limit()`
+    pub fn nth_prime(&self, n: u64) -> Option<u64> {
+        if n == 0 {
+            return None;
+        }
+        // The last checkpoint with fewer than `n` primes before it
+        let k = self.counts.partition_point(|&count| count < n) - 1;
+        let lo = k as u64 * self.step;
+        let hi = lo.saturating_add(self.step - 1).min(self.limit);
+        SegmentedPrimes::new(lo, hi).nth((n - self.counts[k] - 1) as usize)
+    }
+
+    /// The position of `p` among the primes, counting 2 as the first, so that
+    /// `nth_prime(rank(p)) == Some(p)`; or `None` if `p` isn't prime or is past `limit()`
+    pub fn rank(&self, p: u64) -> Option<u64> {
+        if p > self.limit || !is_prime(p) {
+            return None;
+        }
+        self.prime_pi(p)
+    }
+}
+
+/// The distance from `n` to the smallest prime greater than `n`, or `None` if there is no such
+/// `u64`. When `n` is prime, this is the gap between `n` and the next prime.
+///
+/// ```
+/// use primes::gap_after;
+///
+/// assert_eq!(gap_after(23), Some(6));
+/// assert_eq!(gap_after(24), Some(5));
+/// ```
+pub fn gap_after(n: u64) -> Option<u64> {
+    next_prime(n).map(|p| p - n)
+}
+
+/**
+An iterator over the record gaps between consecutive primes, as `(gap, p)` where `p` is the
+first prime followed by a gap that large. Created by `maximal_gaps` or `maximal_gaps_below`.
+**/
+pub struct MaximalGaps {
+    primes: SegmentedPrimes,
+    prev: Option<u64>,
+    best: u64,
+}
+
+impl MaximalGaps {
+    fn new(x: u64) -> MaximalGaps {
+        let mut primes = SegmentedPrimes::new(2, x);
+        let prev = primes.next();
+        MaximalGaps {
+            primes,
+            prev,
+            best: 0,
+        }
+    }
+}
+
+impl Iterator for MaximalGaps {
+    type Item = (u64, u64);
+    fn next(&mut self) -> Option<(u64, u64)> {
+        let mut prev = self.prev?;
+        for p in &mut self.primes {
+            let gap = p - prev;
+            let start = prev;
+            prev = p;
+            if gap > self.best {
+                self.best = gap;
+                self.prev = Some(p);
+                return Some((gap, start));
+            }
+        }
+        self.prev = None;
+        None
+    }
+}
+
+/// Iterate over the record gaps between consecutive primes: each `(gap, p)` is the first gap
+/// larger than every gap before it, starting at the prime `p`.
+///
+/// Primes are sieved a segment at a time and then dropped, so memory use only grows with the
+/// square root of how far the search has reached.
+///
+/// ```
+/// use primes::maximal_gaps;
+///
+/// let records: Vec<(u64, u64)> = maximal_gaps().take(6).collect();
+/// assert_eq!(records, vec![(1, 2), (2, 3), (4, 7), (6, 23), (8, 89), (14, 113)]);
+/// ```
+pub fn maximal_gaps() -> MaximalGaps {
+    MaximalGaps::new(u64::MAX)
+}
+
+/// Iterate over the record gaps between consecutive primes no greater than `x`, as
+/// `maximal_gaps` does.
+///
+/// ```
+/// use primes::maximal_gaps_below;
+///
+/// assert_eq!(maximal_gaps_below(100).last(), Some((8, 89)));
+/// assert_eq!(maximal_gaps_below(2).count(), 0);
+/// ```
+pub fn maximal_gaps_below(x: u64) -> MaximalGaps {
+    MaximalGaps::new(x)
+}
+
+/// Find the largest gap between consecutive primes no greater than `x`.
+///
+/// Returns `(gap, p)` where `p` is the first prime followed by a gap that large, or `None` if
+/// there are fewer than two primes up to `x`. This is the last of the `maximal_gaps_below(x)`.
+///
+/// ```
+/// use primes::max_gap_below;
+///
+/// assert_eq!(max_gap_below(100), Some((8, 89)));
+/// assert_eq!(max_gap_below(2), None);
+/// ```
+pub fn max_gap_below(x: u64) -> Option<(u64, u64)> {
+    maximal_gaps_below(x).last()
+}
+
+/// Count how often each gap between consecutive primes no greater than `x` occurs, as a map from
+/// gap to count.
+///
+/// Like `maximal_gaps_below`, this goes a segment at a time without keeping the primes, so memory
+/// use only grows with `sqrt(x)`, and how far it can go is limited by time rather than memory.
+///
+/// ```
+/// use primes::gap_histogram_below;
+///
+/// // 2, 3, 5, 7, 11, 13, 17, 19, 23, 29
+/// let histogram = gap_histogram_below(30);
+/// let expected = vec![(1, 1), (2, 4), (4, 3), (6, 1)];
+/// assert_eq!(histogram.into_iter().collect::<Vec<_>>(), expected);
+/// ```
+pub fn gap_histogram_below(x: u64) -> BTreeMap<u64, u64> {
+    // Entry `k` counts the gaps of length `k`; all but the first gap are even
+    let mut counts: Vec<u64> = Vec::new();
+    let mut prev = None;
+    for_each_prime_in(2, x, |p| {
+        if let Some(q) = prev {
+            let gap = (p - q) as usize;
+            if gap >= counts.len() {
+                counts.resize(gap + 1, 0);
+            }
+            counts[gap] += 1;
+        }
+        prev = Some(p);
+    });
+    counts
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, count)| count > 0)
+        .map(|(gap, count)| (gap as u64, count))
+        .collect()
+}
+
+// Call `f` with the smaller of each pair of twin primes `(p, p + 2)` with `p + 2 <= x`
+fn for_each_twin_below<F: FnMut(u64)>(x: u64, mut f: F) {
+    let mut prev = None;
+    for_each_prime_in(2, x, |p| {
+        if prev == Some(p - 2) {
+            f(p - 2);
+        }
+        prev = Some(p);
+    });
+}
+
+/// Count the pairs of twin primes `(p, p + 2)` with `p + 2 <= x`.
+///
+/// The primes are sieved a segment at a time and dropped, so memory use only grows with
+/// `sqrt(x)`.
+///
+/// ```
+/// use primes::twin_prime_count;
+///
+/// // (3, 5), (5, 7), (11, 13), (17, 19), (29, 31), (41, 43), (59, 61), (71, 73)
+/// assert_eq!(twin_prime_count(100), 8);
+/// assert_eq!(twin_prime_count(1_000_000), 8_169);
+/// ```
+pub fn twin_prime_count(x: u64) -> u64 {
+    let mut count = 0;
+    for_each_twin_below(x, |_| count += 1);
+    count
+}
+
+/// The sum of `1 / p + 1 / (p + 2)` over the pairs of twin primes `(p, p + 2)` with
+/// `p + 2 <= x`, sieved as `twin_prime_count` does.
+///
+/// As `x` grows this converges to Brun's constant, about 1.902, but very slowly: the sum up to
+/// 10^16 is still only about 1.8318.
+///
+/// ```
+/// use primes::brun_constant_partial;
+///
+/// let expected = 1.0 / 3.0 + 2.0 / 5.0 + 1.0 / 7.0;
+/// assert!((brun_constant_partial(10) - expected).abs() < 1e-15);
+/// assert!((brun_constant_partial(1_000_000) - 1.710_776_930_804).abs() < 1e-12);
+/// ```
+pub fn brun_constant_partial(x: u64) -> f64 {
+    let mut sum = 0.0;
+    for_each_twin_below(x, |p| sum += 1.0 / p as f64 + 1.0 / (p + 2) as f64);
+    sum
+}
+
+/// How far below each even number `verify_goldbach` looks for the prime `n - p`. The smallest `p`
+/// that works is nearly always tiny: up to 4 * 10^18 it is never more than 9,781.
+const GOLDBACH_WINDOW: u64 = 1 << 12;
+
+/// Check Goldbach's conjecture for every even number greater than 2 in `range`, returning the
+/// first that is not the sum of two primes, or `None` if there is no such number (as there
+/// certainly won't be).
+///
+/// The range is sieved a segment at a time, keeping the last 4,096 flags of the previous segment
+/// as well, so that for each even `n` the primes `p` up to 4,096 are tried in turn until `n - p`
+/// is flagged prime. Nearly every `n` is done within its first few `p`; any that need a larger
+/// one are checked with `is_prime` instead.
+///
+/// ```
+/// use primes::verify_goldbach;
+///
+/// assert_eq!(verify_goldbach(0..=1_000_000), None);
+/// assert_eq!(verify_goldbach(1_000_000_000_000..=1_000_000_100_000), None);
+/// ```
+pub fn verify_goldbach(range: RangeInclusive<u64>) -> Option<u64> {
+    let (start, end) = range.into_inner();
+    let start = start.max(4);
+    let lo = start.checked_add(start % 2)?;
+    if lo > end {
+        return None;
+    }
+    let mut pset = DefaultPrimeSet::new();
+    let small: Vec<u64> = pset.iter().take_while(|&p| p <= GOLDBACH_WINDOW).collect();
+
+    // Flags for the values from `window_lo` to the end of the current segment
+    let mut window: Vec<bool> = Vec::new();
+    let mut segments = SegmentIter::new(lo.saturating_sub(GOLDBACH_WINDOW), end);
+    while let Some((seg_lo, flags)) = segments.next_segment() {
+        let keep = window.len().min(GOLDBACH_WINDOW as usize);
+        window.drain(..window.len() - keep);
+        window.extend_from_slice(flags);
+        let window_lo = seg_lo - keep as u64;
+        let seg_hi = seg_lo + (flags.len() - 1) as u64;
+
+        let first = lo.max(seg_lo + seg_lo % 2);
+        for n in (first..=seg_hi).step_by(2) {
+            let found = small
+                .iter()
+                .take_while(|&&p| p < n && n - p >= window_lo)
+                .any(|&p| window[(n - p - window_lo) as usize]);
+            let found =
+                found || (GOLDBACH_WINDOW + 1..=n / 2).any(|p| is_prime(p) && is_prime(n - p));
+            if !found {
+                return Some(n);
+            }
+        }
+    }
+    None
+}
+
+/// Count the primes less than or equal to `x` in roughly `O(x^(3/4))` time and `O(sqrt(x))`
+/// memory, without sieving all the way to `x`.
+///
+/// This is the Legendre-style recurrence popularized by Lucy Hedgehog: it tracks the count of
+/// numbers surviving sieving by each prime up to `sqrt(x)`, but only at the `O(sqrt(x))` values of
+/// `x / k`.
+///
+/// ```
+/// use primes::prime_pi_sublinear;
+///
+/// assert_eq!(prime_pi_sublinear(1_000_000_000), 50_847_534);
+/// ```
+pub fn prime_pi_sublinear(x: u64) -> u64 {
+    if x < 2 {
+        return 0;
+    }
+    let root = isqrt(x);
+    // small[v] is the count for v <= root; large[k] is the count for x / k, with k <= root
+    let mut small: Vec<u64> = (0..=root).map(|v| v.saturating_sub(1)).collect();
+    let mut large: Vec<u64> = Some(0)
+        .into_iter()
+        .chain((1..=root).map(|k| x / k - 1))
+        .collect();
+
+    for p in 2..=root {
+        if small[p as usize] == small[p as usize - 1] {
+            // Not prime
+            continue;
+        }
+        let below_p = small[p as usize - 1];
+        let p2 = p * p;
+
+        for k in 1..=root.min(x / p2) {
+            let v = x / k;
+            let vp = v / p;
+            let count = if vp <= root {
+                small[vp as usize]
+            } else {
+                large[(k * p) as usize]
+            };
+            large[k as usize] -= count - below_p;
+        }
+        for v in (p2..=root).rev() {
+            small[v as usize] -= small[(v / p) as usize] - below_p;
+        }
+    }
+    large[1]
+}
+
+/// Count the primes in the inclusive range `[lo, hi]`.
+///
+/// Narrow ranges are counted with a single segmented sieve pass over the range. Wide ranges
+/// are counted as the difference of two `prime_pi_sublinear` values, which is cheaper once the
+/// range is a sizeable fraction of `hi`.
+///
+/// ```
+/// use primes::prime_count_in_range;
+///
+/// assert_eq!(prime_count_in_range(10, 20), 4);
+/// assert_eq!(prime_count_in_range(1_000_000_000, 1_000_001_000), 49);
+/// ```
+pub fn prime_count_in_range(lo: u64, hi: u64) -> u64 {
+    if lo > hi {
+        return 0;
+    }
+    let width = (hi - lo) as f64;
+    if width < (hi as f64).powf(0.75) {
+        count_primes_in(lo, hi)
+    } else {
+        prime_pi_sublinear(hi) - prime_pi_sublinear(lo.saturating_sub(1))
+    }
+}
+
+/// The longest segment `LargePrimes` sieves at once
+const LARGE_SEGMENT_MAX: u64 = 1 << 24;
+
+/**
+An iterator over the primes from some starting point up to the largest `u64` prime. Created by
+`large_primes_from`.
+**/
+pub struct LargePrimes {
+    // The base primes found so far, all below 2^32, and the stream they come from
+    base: Vec<u32>,
+    base_stream: SegmentedPrimes,
+    next_base: Option<u64>,
+    next_lo: Option<u64>,
+    lo: u64,
+    flags: Vec<bool>,
+    ix: usize,
+}
+
+impl LargePrimes {
+    // Pull base primes from the stream until every prime up to `root` is in `base`
+    fn grow_base(&mut self, root: u64) {
+        while let Some(p) = self.next_base {
+            if p > root {
+                break;
+            }
+            self.base.push(p as u32);
+            self.next_base = self.base_stream.next();
+        }
+    }
+
+    // Sieve the next segment into `flags`, or return false if there isn't one
+    fn next_segment(&mut self) -> bool {
+        let lo = match self.next_lo {
+            Some(lo) => lo,
+            None => return false,
+        };
+        // Each segment walks the whole list of base primes, so segments grow with sqrt(lo) to
+        // keep that cost in proportion to the values sieved
+        let len = isqrt(lo).clamp(SEGMENT_LEN, LARGE_SEGMENT_MAX);
+        let hi = lo.saturating_add(len - 1);
+        self.next_lo = hi.checked_add(1);
+        self.lo = lo;
+        self.ix = 0;
+
+        let root = isqrt(hi);
+        self.grow_base(root);
+
+        self.flags.clear();
+        self.flags.resize((hi - lo + 1) as usize, true);
+        strike_composites(&mut self.flags, lo, self.base.iter().map(|&p| p as u64));
+        true
+    }
+}
+
+impl Iterator for LargePrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            while self.ix < self.flags.len() {
+                self.ix += 1;
+                if self.flags[self.ix - 1] {
+                    return Some(self.lo + (self.ix - 1) as u64);
+                }
+            }
+            if !self.next_segment() {
+                return None;
+            }
+        }
+    }
+}
+
+/// Iterate over the primes greater than or equal to `start`, in increasing order, up to the
+/// largest prime that fits in a `u64`.
+///
+/// Unlike a `PrimeSet`, this keeps nothing but the current segment and the base primes up to
+/// the square root of the current position, which are stored as `u32` since they are all below
+/// 2^32. That makes it practical to walk the primes anywhere in the `u64` range, although
+/// starting near `u64::MAX` first means finding the ~200 million primes below 2^32, which takes
+/// several seconds and around 800 MB.
+///
+/// ```
+/// use primes::large_primes_from;
+///
+/// let primes: Vec<u64> = large_primes_from(1 << 40).take(3).collect();
+/// assert_eq!(primes, vec![1_099_511_627_791, 1_099_511_627_803, 1_099_511_627_831]);
+/// ```
+pub fn large_primes_from(start: u64) -> LargePrimes {
+    let mut base_stream = SegmentedPrimes::new(2, u32::MAX as u64);
+    let next_base = base_stream.next();
+    LargePrimes {
+        base: Vec::new(),
+        base_stream,
+        next_base,
+        next_lo: Some(start),
+        lo: start,
+        flags: Vec::new(),
+        ix: 0,
+    }
+}
+
+/**
+An iterator over the primes whose index has been taken through the primes `depth` times: for a
+depth of 2, the primes `p(p(n))` whose index is itself prime. Created by `iterate_index` or
+`prime_indexed_primes`.
+
+Each level is a stream of primes (see `large_primes_from`) that skips ahead to the indices the
+level below it yields, so no list of primes is kept however far the sequence goes.
+**/
+pub struct IteratedPrimes {
+    // The primes at this level, or `None` at depth 0, which counts 1, 2, 3, ...
+    primes: Option<LargePrimes>,
+    // The sequence one level down, giving the 1-based indices to pick out of `primes`
+    indices: Option<Box<IteratedPrimes>>,
+    // The index of the last prime taken from `primes`, or at depth 0 the last value yielded
+    count: u64,
+    last: u64,
+}
+
+/// Iterate over the numbers `p(p(...p(n)...))`, with `p` applied `depth` times and `p(1) = 2`,
+/// for `n = 1, 2, 3, ...`.
+///
+/// A depth of 0 gives the positive integers, 1 the primes, 2 the prime-indexed primes (see
+/// `prime_indexed_primes`), and so on.
+///
+/// ```
+/// use primes::iterate_index;
+///
+/// let depth3: Vec<u64> = iterate_index(3).take(6).collect();
+/// assert_eq!(depth3, vec![5, 11, 31, 59, 127, 179]);
+/// ```
+pub fn iterate_index(depth: u32) -> IteratedPrimes {
+    if depth == 0 {
+        return IteratedPrimes {
+            primes: None,
+            indices: None,
+            count: 0,
+            last: 0,
+        };
+    }
+    IteratedPrimes {
+        primes: Some(large_primes_from(2)),
+        indices: Some(Box::new(iterate_index(depth - 1))),
+        count: 0,
+        last: 0,
+    }
+}
+
+/// Iterate over the prime-indexed primes `p(p(n))`: the primes whose position among the
+/// primes, counting 2 as the first, is itself prime. The same as `iterate_index(2)`.
+///
+/// ```
+/// use primes::prime_indexed_primes;
+///
+/// let first: Vec<u64> = prime_indexed_primes().take(8).collect();
+/// assert_eq!(first, vec![3, 5, 11, 17, 31, 41, 59, 67]);
+/// ```
+pub fn prime_indexed_primes() -> IteratedPrimes {
+    iterate_index(2)
+}
+
+impl Iterator for IteratedPrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let (primes, indices) = match (&mut self.primes, &mut self.indices) {
+            (Some(primes), Some(indices)) => (primes, indices),
+            _ => {
+                self.count = self.count.checked_add(1)?;
+                return Some(self.count);
+            }
+        };
+        let target = indices.next()?;
+        while self.count < target {
+            self.last = primes.next()?;
+            self.count += 1;
+        }
+        Some(self.last)
+    }
+}
@@ -0,0 +1,160 @@
+//! A prime cache shared between threads, with readers working from published snapshots.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+
+use crate::{is_prime_u64, FrozenPrimes, PrimeSet, Sieve};
+
+/**
+A cache of primes that many threads can query and grow at once.
+
+Growing is done by one thread at a time on an internal `Sieve`, which then publishes a new
+`FrozenPrimes` snapshot and bumps an epoch counter. Readers (see `SharedPrimes::reader`) keep
+their own snapshot and only compare the epoch with a single atomic load before each query, so
+queries within what has been found take no lock at all; a reader only touches a lock when the
+epoch has moved on, or when it needs primes nobody has found yet. Primality checks past the
+cache are answered with `is_prime_u64` rather than by growing it, as `PrimeSet::is_prime` does.
+
+```
+use primes::SharedPrimes;
+use std::thread;
+
+let shared = SharedPrimes::new();
+thread::scope(|s| {
+    for k in 1..=4 {
+        let shared = &shared;
+        s.spawn(move || {
+            let mut reader = shared.reader();
+            let (_, p) = reader.find(1_000 * k);
+            assert!(reader.is_prime(p) && !reader.is_prime(p + 1));
+        });
+    }
+});
+assert!(shared.snapshot().last().unwrap() >= 4_001);
+```
+**/
+pub struct SharedPrimes {
+    sieve: Mutex<Sieve>,
+    published: RwLock<FrozenPrimes>,
+    // Bumped after each new snapshot is published
+    epoch: AtomicU64,
+}
+
+impl SharedPrimes {
+    /// A new, nearly empty cache
+    pub fn new() -> SharedPrimes {
+        let sieve = Sieve::new();
+        let published = RwLock::new(sieve.freeze());
+        SharedPrimes {
+            sieve: Mutex::new(sieve),
+            published,
+            epoch: AtomicU64::new(0),
+        }
+    }
+
+    /// The most recently published snapshot
+    pub fn snapshot(&self) -> FrozenPrimes {
+        self.published.read().unwrap().clone()
+    }
+
+    /// How many snapshots have been published since the cache was created
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::Acquire)
+    }
+
+    /// Make sure every prime up to `limit` has been found, and return a snapshot that includes
+    /// them.
+    ///
+    /// Publishing copies the whole list, so the cache grows to at least twice its previous
+    /// largest prime each time, which keeps the copying to a constant factor of the sieving.
+    pub fn ensure(&self, limit: u64) -> FrozenPrimes {
+        let current = self.snapshot();
+        if current.last().unwrap_or(0) >= limit {
+            return current;
+        }
+
+        let mut sieve = self.sieve.lock().unwrap();
+        // Another thread may have grown it while we waited for the lock
+        let largest = sieve.last_found().unwrap_or(0);
+        if largest < limit {
+            sieve.find(limit.max(largest.saturating_mul(2)));
+            let frozen = sieve.freeze();
+            *self.published.write().unwrap() = frozen.clone();
+            self.epoch.fetch_add(1, Ordering::AcqRel);
+            return frozen;
+        }
+        drop(sieve);
+        self.snapshot()
+    }
+
+    /// A handle for querying the cache from one thread
+    pub fn reader(&self) -> SharedPrimesReader<'_> {
+        let epoch = self.epoch();
+        SharedPrimesReader {
+            shared: self,
+            epoch,
+            snapshot: self.snapshot(),
+        }
+    }
+}
+
+impl Default for SharedPrimes {
+    fn default() -> Self {
+        SharedPrimes::new()
+    }
+}
+
+/// A reader of a `SharedPrimes`, holding its own snapshot. Created by `SharedPrimes::reader`.
+pub struct SharedPrimesReader<'a> {
+    shared: &'a SharedPrimes,
+    epoch: u64,
+    snapshot: FrozenPrimes,
+}
+
+impl<'a> SharedPrimesReader<'a> {
+    /// The latest snapshot, refreshed first if a newer one has been published
+    pub fn snapshot(&mut self) -> &FrozenPrimes {
+        let epoch = self.shared.epoch();
+        if epoch != self.epoch {
+            self.epoch = epoch;
+            self.snapshot = self.shared.snapshot();
+        }
+        &self.snapshot
+    }
+
+    /// Make sure the snapshot covers every prime up to `limit`, growing the shared cache if no
+    /// thread has yet
+    fn cover(&mut self, limit: u64) -> &FrozenPrimes {
+        if self.snapshot().last().unwrap_or(0) < limit {
+            self.epoch = self.shared.epoch();
+            self.snapshot = self.shared.ensure(limit);
+        }
+        &self.snapshot
+    }
+
+    /// Check whether `n` is prime, looking it up in the snapshot if it reaches that far, and
+    /// otherwise testing it with `is_prime_u64` without growing the shared cache
+    pub fn is_prime(&mut self, n: u64) -> bool {
+        self.snapshot()
+            .is_prime_cached(n)
+            .unwrap_or_else(|| is_prime_u64(n))
+    }
+
+    /// Find the next largest prime from `n`, growing the shared cache if needed
+    ///
+    /// Returns `(idx, prime)`, the same as `PrimeSet::find`.
+    pub fn find(&mut self, n: u64) -> (usize, u64) {
+        self.cover(n).find(n).unwrap()
+    }
+
+    /// Get the prime at `index`, growing the shared cache if needed
+    pub fn get(&mut self, index: usize) -> u64 {
+        loop {
+            if let Some(p) = self.snapshot().get(index) {
+                return p;
+            }
+            let largest = self.snapshot.last().unwrap_or(0);
+            self.cover(largest + 1);
+        }
+    }
+}
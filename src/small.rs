@@ -0,0 +1,142 @@
+//! Primality and factoring for `u32` values, using `u32` arithmetic and a table of smallest prime
+//! factors below 2^16.
+//!
+//! These give the same answers as `is_prime_u64` and `factors` for values that fit in a `u32`,
+//! but skip the wider multiplications and larger witness sets the `u64` versions need.
+
+/// The smallest prime factor of each `n` below 2^16, with 0 for 0 and 1
+static SMALLEST_FACTOR: [u16; 1 << 16] = smallest_factor_table();
+
+/// The 6,542 primes below 2^16, which are enough to trial divide any `u32`
+static SMALL_PRIMES: [u16; 6_542] = small_primes();
+
+const fn smallest_factor_table() -> [u16; 1 << 16] {
+    let mut table = [0u16; 1 << 16];
+    let mut p = 2;
+    while p < 1 << 16 {
+        if table[p] == 0 {
+            let mut m = p;
+            while m < 1 << 16 {
+                if table[m] == 0 {
+                    table[m] = p as u16;
+                }
+                m += p;
+            }
+        }
+        p += 1;
+    }
+    table
+}
+
+const fn small_primes() -> [u16; 6_542] {
+    let table = smallest_factor_table();
+    let mut primes = [0u16; 6_542];
+    let mut count = 0;
+    let mut n = 2;
+    while n < 1 << 16 {
+        if table[n] as usize == n {
+            primes[count] = n as u16;
+            count += 1;
+        }
+        n += 1;
+    }
+    primes
+}
+
+fn mul_mod_u32(a: u32, b: u32, m: u32) -> u32 {
+    (a as u64 * b as u64 % m as u64) as u32
+}
+
+fn pow_mod_u32(mut base: u32, mut exp: u32, m: u32) -> u32 {
+    let mut result = 1;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod_u32(result, base, m);
+        }
+        base = mul_mod_u32(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+// Whether odd `n > 61` is a strong probable prime to base `a`
+fn is_strong_probable_prime(n: u32, a: u32) -> bool {
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    let mut x = pow_mod_u32(a, d, n);
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = mul_mod_u32(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Check whether a `u32` is prime.
+///
+/// Values below 2^16 are looked up in a table; larger ones get a deterministic Miller-Rabin test
+/// to bases 2, 7 and 61, which has no false positives below 4,759,123,141.
+///
+/// ```
+/// use primes::small::is_prime_u32;
+///
+/// assert!(is_prime_u32(65_521));
+/// assert!(!is_prime_u32(65_535));
+/// assert!(is_prime_u32(4_294_967_291));
+/// assert!(!is_prime_u32(u32::MAX));
+/// ```
+pub fn is_prime_u32(n: u32) -> bool {
+    if n < 1 << 16 {
+        return n >= 2 && SMALLEST_FACTOR[n as usize] as u32 == n;
+    }
+    for &p in &SMALL_PRIMES[..12] {
+        if n.is_multiple_of(p as u32) {
+            return false;
+        }
+    }
+    [2, 7, 61].iter().all(|&a| is_strong_probable_prime(n, a))
+}
+
+/// Find all prime factors of a `u32`, in increasing order with repeats. Returns an empty list for
+/// 0 and 1.
+///
+/// Trial division by the primes below 2^16 stops as soon as what is left is prime or below 2^16,
+/// where the rest of the factors are read off the table.
+///
+/// ```
+/// use primes::small::factors_u32;
+///
+/// assert_eq!(factors_u32(360), vec![2, 2, 2, 3, 3, 5]);
+/// assert_eq!(factors_u32(4_294_967_291), vec![4_294_967_291]);
+/// assert_eq!(factors_u32(65_521 * 65_519), vec![65_519, 65_521]);
+/// ```
+pub fn factors_u32(mut n: u32) -> Vec<u32> {
+    let mut factors = Vec::new();
+    if n < 2 {
+        return factors;
+    }
+    let mut primes = SMALL_PRIMES.iter().map(|&p| p as u32);
+    while n >= 1 << 16 {
+        if is_prime_u32(n) {
+            factors.push(n);
+            return factors;
+        }
+        // `n` is composite, so it has a factor below 2^16
+        let p = primes.find(|&p| n.is_multiple_of(p)).unwrap();
+        while n.is_multiple_of(p) {
+            factors.push(p);
+            n /= p;
+        }
+    }
+    while n > 1 {
+        let p = SMALLEST_FACTOR[n as usize] as u32;
+        factors.push(p);
+        n /= p;
+    }
+    factors
+}
@@ -0,0 +1,465 @@
+//! Tests for, and iterators over, special classes of primes.
+
+use std::ops::RangeInclusive;
+
+use crate::primality::jacobi;
+use crate::segment::SegmentedPrimes;
+use crate::{first_primes, is_prime_u64, is_square, mul_mod, pow_mod};
+
+/// Test whether `n` is a semiprime: the product of exactly two primes, not necessarily distinct.
+///
+/// Only trial division up to the cube root of `n` is needed: past that, whatever remains of `n`
+/// has at most two prime factors, so one primality test settles it.
+///
+/// ```
+/// use primes::special::is_semiprime;
+///
+/// assert!(is_semiprime(49));
+/// assert!(is_semiprime(1_000_000_007 * 3));
+/// assert!(!is_semiprime(30));
+/// assert!(!is_semiprime(13));
+/// ```
+pub fn is_semiprime(n: u64) -> bool {
+    if n < 4 {
+        return false;
+    }
+    let mut d = 2;
+    while d <= n / d / d {
+        if n.is_multiple_of(d) {
+            return is_prime_u64(n / d);
+        }
+        d += if d == 2 { 1 } else { 2 };
+    }
+    !is_prime_u64(n)
+}
+
+/// Test whether `p` is a Chen prime: `p` is prime, and `p + 2` is either prime or a semiprime.
+///
+/// ```
+/// use primes::special::is_chen_prime;
+///
+/// assert!(is_chen_prime(7)); // 9 = 3 * 3
+/// assert!(!is_chen_prime(43)); // 45 = 3 * 3 * 5
+/// ```
+pub fn is_chen_prime(p: u64) -> bool {
+    match p.checked_add(2) {
+        Some(q) => is_prime_u64(p) && (is_prime_u64(q) || is_semiprime(q)),
+        None => false,
+    }
+}
+
+/// An iterator over the Chen primes in increasing order. Created by `chen_primes`.
+pub struct ChenPrimes {
+    primes: SegmentedPrimes,
+}
+
+/// Iterate over the Chen primes, in increasing order.
+///
+/// Candidates come from a segmented sieve, so only `p + 2` needs to be tested.
+///
+/// ```
+/// use primes::special::chen_primes;
+///
+/// let chen: Vec<u64> = chen_primes().take(10).collect();
+/// assert_eq!(chen, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+/// ```
+pub fn chen_primes() -> ChenPrimes {
+    ChenPrimes {
+        primes: SegmentedPrimes::new(2, u64::MAX - 2),
+    }
+}
+
+impl Iterator for ChenPrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        self.primes
+            .find(|&p| is_prime_u64(p + 2) || is_semiprime(p + 2))
+    }
+}
+
+/// All the lucky numbers up to and including `limit`, in increasing order.
+///
+/// Lucky numbers are left by a sieve much like Eratosthenes': start with the odd numbers, and
+/// then repeatedly take the next surviving number `k` after 1 and strike out every `k`-th
+/// survivor, counting by position rather than value.
+///
+/// ```
+/// use primes::special::lucky_numbers;
+///
+/// assert_eq!(lucky_numbers(50), vec![1, 3, 7, 9, 13, 15, 21, 25, 31, 33, 37, 43, 49]);
+/// ```
+pub fn lucky_numbers(limit: u64) -> Vec<u64> {
+    let mut lucky: Vec<u64> = (1..=limit).step_by(2).collect();
+    let mut ix = 1;
+    while ix < lucky.len() && lucky[ix] as usize <= lucky.len() {
+        let k = lucky[ix] as usize;
+        let mut position = 0;
+        lucky.retain(|_| {
+            position += 1;
+            position % k != 0
+        });
+        ix += 1;
+    }
+    lucky
+}
+
+/// Test whether `n` is a happy number: repeatedly replacing it with the sum of the squares of
+/// its digits eventually reaches 1. Every unhappy number instead falls into a cycle through 4.
+///
+/// ```
+/// use primes::special::is_happy;
+///
+/// assert!(is_happy(7)); // 49, 97, 130, 10, 1
+/// assert!(!is_happy(4));
+/// ```
+pub fn is_happy(n: u64) -> bool {
+    let mut n = n;
+    while n != 1 && n != 4 && n != 0 {
+        let mut sum = 0;
+        while n > 0 {
+            sum += (n % 10) * (n % 10);
+            n /= 10;
+        }
+        n = sum;
+    }
+    n == 1
+}
+
+/// An iterator over the primes that are also happy numbers. Created by `happy_primes`.
+pub struct HappyPrimes {
+    primes: SegmentedPrimes,
+}
+
+/// Iterate over the happy primes, in increasing order.
+///
+/// ```
+/// use primes::special::happy_primes;
+///
+/// let happy: Vec<u64> = happy_primes().take(6).collect();
+/// assert_eq!(happy, vec![7, 13, 19, 23, 31, 79]);
+/// ```
+pub fn happy_primes() -> HappyPrimes {
+    HappyPrimes {
+        primes: SegmentedPrimes::new(2, u64::MAX),
+    }
+}
+
+impl Iterator for HappyPrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        self.primes.find(|&p| is_happy(p))
+    }
+}
+
+/// An iterator over the primes that are also lucky numbers. Created by `lucky_primes`.
+pub struct LuckyPrimes {
+    lucky: std::vec::IntoIter<u64>,
+}
+
+/// Iterate over the lucky primes up to and including `limit`, in increasing order. The lucky
+/// numbers come from `lucky_numbers(limit)`, since each depends on every one before it.
+///
+/// ```
+/// use primes::special::lucky_primes;
+///
+/// let lucky: Vec<u64> = lucky_primes(100).collect();
+/// assert_eq!(lucky, vec![3, 7, 13, 31, 37, 43, 67, 73, 79]);
+/// ```
+pub fn lucky_primes(limit: u64) -> LuckyPrimes {
+    LuckyPrimes {
+        lucky: lucky_numbers(limit).into_iter(),
+    }
+}
+
+impl Iterator for LuckyPrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        self.lucky.find(|&n| is_prime_u64(n))
+    }
+}
+
+/// The primes `polynomial_prime_count` sieves polynomial values by before testing them
+const POLYNOMIAL_SIEVE_PRIMES: [u64; 168] = first_primes::<168>();
+
+/// How many values of `n` `polynomial_prime_count` sieves at once
+const POLYNOMIAL_BLOCK_LEN: usize = 1 << 16;
+
+/// Count the `n` in `range` for which `a n² + b n + c` is a (positive) prime.
+///
+/// Rather than testing every value, each block of `n` is first sieved by the primes below 1000:
+/// `p` divides `a n² + b n + c` exactly when `n` is one of the polynomial's roots modulo `p`, so
+/// finding those roots among `n, n + 1, ..., n + p - 1` strikes out every multiple of `p` in the
+/// block at once. Only the survivors get a full `is_prime_u64` test.
+///
+/// Panics if a value of the polynomial over `range` is larger than `u64::MAX`, or too large in
+/// magnitude to compute in an `i128`.
+///
+/// ```
+/// use primes::special::polynomial_prime_count;
+///
+/// // Euler's polynomial n² + n + 41 is prime for n = 0, 1, ..., 39
+/// assert_eq!(polynomial_prime_count(1, 1, 41, 0..=39), 40);
+/// assert_eq!(polynomial_prime_count(1, 1, 41, 0..=40), 40);
+/// // It is also prime for n = -40, ..., -1, as n² + n is symmetric about -1/2
+/// assert_eq!(polynomial_prime_count(1, 1, 41, -40..=39), 80);
+/// ```
+pub fn polynomial_prime_count(a: i64, b: i64, c: i64, range: RangeInclusive<i64>) -> u64 {
+    let (a, b, c) = (a as i128, b as i128, c as i128);
+    let value = |n: i128| -> i128 {
+        a.checked_mul(n)
+            .and_then(|an| an.checked_add(b))
+            .and_then(|anb| anb.checked_mul(n))
+            .and_then(|anbn| anbn.checked_add(c))
+            .expect("polynomial value overflows an i128")
+    };
+    let largest_sieve_prime = POLYNOMIAL_SIEVE_PRIMES[POLYNOMIAL_SIEVE_PRIMES.len() - 1] as i128;
+
+    let (start, end) = (*range.start() as i128, *range.end() as i128);
+    let mut count = 0;
+    let mut coprime = Vec::with_capacity(POLYNOMIAL_BLOCK_LEN);
+    let mut block_start = start;
+    while block_start <= end {
+        let len = (end - block_start + 1).min(POLYNOMIAL_BLOCK_LEN as i128) as usize;
+        coprime.clear();
+        coprime.resize(len, true);
+        for &p in &POLYNOMIAL_SIEVE_PRIMES {
+            let p = p as i128;
+            let (am, bm, cm) = (a.rem_euclid(p), b.rem_euclid(p), c.rem_euclid(p));
+            for offset in 0..(p as usize).min(len) {
+                let nm = (block_start + offset as i128).rem_euclid(p);
+                if ((am * nm + bm) * nm + cm) % p == 0 {
+                    for flag in coprime[offset..].iter_mut().step_by(p as usize) {
+                        *flag = false;
+                    }
+                }
+            }
+        }
+
+        for (offset, &flag) in coprime.iter().enumerate() {
+            let v = value(block_start + offset as i128);
+            if v < 2 {
+                continue;
+            }
+            assert!(
+                v <= u64::MAX as i128,
+                "polynomial value doesn't fit in a u64"
+            );
+            // Values up to the largest sieving prime may be one of those primes themselves
+            if (flag || v <= largest_sieve_prime) && is_prime_u64(v as u64) {
+                count += 1;
+            }
+        }
+        block_start += len as i128;
+    }
+    count
+}
+
+/// Proth's test: whether the Proth number `N = k * 2^n + 1`, with odd `k < 2^n`, is prime.
+///
+/// By Proth's theorem, `N` is prime exactly when `a^((N - 1) / 2) ≡ -1 (mod N)` for some `a`, and
+/// any quadratic non-residue `a` will do, so this is a single modular exponentiation and is
+/// certain.
+///
+/// Panics if `k` is even or at least `2^n`, or if `N` doesn't fit in a `u64`.
+///
+/// ```
+/// use primes::special::proth_test;
+///
+/// // 3 * 2^30 + 1 is prime, and 5 * 2^30 + 1 is divisible by 3
+/// assert!(proth_test(3, 30));
+/// assert!(!proth_test(5, 30));
+/// ```
+pub fn proth_test(k: u64, n: u32) -> bool {
+    assert!(
+        k % 2 == 1 && n < 64 && k < 1 << n,
+        "k must be odd and less than 2^n"
+    );
+    let big_n = (k as u128) << n | 1;
+    assert!(
+        big_n <= u64::MAX as u128,
+        "k * 2^n + 1 doesn't fit in a u64"
+    );
+    let big_n = big_n as u64;
+    // A square has no quadratic non-residues to test with
+    if is_square(big_n) {
+        return false;
+    }
+    let mut a = 3;
+    loop {
+        match jacobi(a, big_n) {
+            -1 => return pow_mod(a, (big_n - 1) / 2, big_n) == big_n - 1,
+            0 if a % big_n != 0 => return false,
+            _ => a += 2,
+        }
+    }
+}
+
+/// Pépin's test: whether the Fermat number `F_m = 2^(2^m) + 1` is prime.
+///
+/// This is Proth's test with `k = 1`, where 3 is always a quadratic non-residue: `F_m` is prime
+/// exactly when `3^((F_m - 1) / 2) ≡ -1 (mod F_m)`. Only `F_0` through `F_5` fit in a `u64`.
+///
+/// Panics if `m > 5`.
+///
+/// ```
+/// use primes::special::pepin_test;
+///
+/// let prime: Vec<u32> = (0..=5).filter(|&m| pepin_test(m)).collect();
+/// assert_eq!(prime, vec![0, 1, 2, 3, 4]);
+/// ```
+pub fn pepin_test(m: u32) -> bool {
+    assert!(m <= 5, "F_m only fits in a u64 for m <= 5");
+    let f = (1u64 << (1 << m)) + 1;
+    if f == 3 {
+        return true;
+    }
+    pow_mod(3, (f - 1) / 2, f) == f - 1
+}
+
+/// The Lucas-Lehmer test: whether the Mersenne number `M_p = 2^p - 1` is prime.
+///
+/// `M_p` can only be prime when `p` is, and then it is prime exactly when `s_(p - 2) ≡ 0
+/// (mod M_p)`, where `s_0 = 4` and `s_(i + 1) = s_i^2 - 2`. That takes `p - 2` squarings.
+///
+/// Panics if `p > 64`.
+///
+/// ```
+/// use primes::special::lucas_lehmer_test;
+///
+/// let exponents: Vec<u32> = (0..=64).filter(|&p| lucas_lehmer_test(p)).collect();
+/// assert_eq!(exponents, vec![2, 3, 5, 7, 13, 17, 19, 31, 61]);
+/// ```
+pub fn lucas_lehmer_test(p: u32) -> bool {
+    assert!(p <= 64, "M_p only fits in a u64 for p <= 64");
+    if p == 2 {
+        return true;
+    }
+    if !is_prime_u64(p as u64) {
+        return false;
+    }
+    let m = u64::MAX >> (64 - p);
+    let mut s = 4;
+    for _ in 0..p - 2 {
+        let square = mul_mod(s, s, m);
+        s = if square >= 2 {
+            square - 2
+        } else {
+            square + m - 2
+        };
+    }
+    s == 0
+}
+
+/// A form of number with a dedicated primality test, as recognized by `SpecialForm::of`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpecialForm {
+    /// `2^(2^m) + 1`, tested with `pepin_test(m)`
+    Fermat(u32),
+    /// `2^p - 1`, tested with `lucas_lehmer_test(p)`
+    Mersenne(u32),
+    /// `k * 2^n + 1` with odd `k < 2^n`, tested with `proth_test(k, n)`
+    Proth {
+        /// The odd multiplier
+        k: u64,
+        /// The power of 2
+        n: u32,
+    },
+    /// None of the above, tested with `is_prime_u64`
+    General,
+}
+
+impl SpecialForm {
+    /// Recognize the form of `n`, preferring the narrower forms: every Fermat number past 3 is
+    /// also a Proth number, and 3, which is both `F_0` and `M_2`, counts as a Mersenne number.
+    ///
+    /// ```
+    /// use primes::special::SpecialForm;
+    ///
+    /// assert_eq!(SpecialForm::of(65_537), SpecialForm::Fermat(4));
+    /// assert_eq!(SpecialForm::of(8_191), SpecialForm::Mersenne(13));
+    /// assert_eq!(SpecialForm::of(97), SpecialForm::Proth { k: 3, n: 5 });
+    /// assert_eq!(SpecialForm::of(101), SpecialForm::General);
+    /// ```
+    pub fn of(n: u64) -> SpecialForm {
+        if n < 3 {
+            return SpecialForm::General;
+        }
+        if n & n.wrapping_add(1) == 0 {
+            return SpecialForm::Mersenne(n.count_ones());
+        }
+        let e = (n - 1).trailing_zeros();
+        let k = (n - 1) >> e;
+        if k == 1 && e.is_power_of_two() {
+            SpecialForm::Fermat(e.trailing_zeros())
+        } else if k >> e == 0 {
+            SpecialForm::Proth { k, n: e }
+        } else {
+            SpecialForm::General
+        }
+    }
+}
+
+/// Test whether `n` is prime, with the dedicated test for its `SpecialForm` if it has one, or
+/// `is_prime_u64` otherwise.
+///
+/// ```
+/// use primes::special::is_prime_special;
+///
+/// assert!(is_prime_special((1 << 61) - 1));
+/// assert!(is_prime_special(7 * (1 << 26) + 1));
+/// assert!(!is_prime_special((1 << 32) + 1));
+/// ```
+pub fn is_prime_special(n: u64) -> bool {
+    match SpecialForm::of(n) {
+        SpecialForm::Fermat(m) => pepin_test(m),
+        SpecialForm::Mersenne(p) => lucas_lehmer_test(p),
+        SpecialForm::Proth { k, n } => proth_test(k, n),
+        SpecialForm::General => is_prime_u64(n),
+    }
+}
+
+/// Landau's function g(n): the largest order of a permutation of `n` elements, which is the
+/// largest least common multiple of a partition of `n`.
+///
+/// The best partitions use only prime powers for distinct primes (padded out with 1s), so this
+/// is a knapsack over the primes up to `n`: `best[s]` holds the largest product of prime powers
+/// with distinct primes summing to at most `s`, updated one prime at a time. That takes roughly
+/// `π(n) · n · log(n)` big-number steps. g(n) grows like `e^sqrt(n ln n)`, so it is returned as a
+/// `BigUint`.
+///
+/// ```
+/// use num_bigint::BigUint;
+/// use primes::special::landau;
+///
+/// let small: Vec<BigUint> = (0..=10).map(landau).collect();
+/// let expected: Vec<BigUint> = [1u32, 1, 2, 3, 4, 6, 6, 12, 15, 20, 30]
+///     .iter()
+///     .map(|&g| BigUint::from(g))
+///     .collect();
+/// assert_eq!(small, expected);
+/// ```
+#[cfg(feature = "bigint")]
+pub fn landau(n: u64) -> num_bigint::BigUint {
+    use num_bigint::BigUint;
+
+    let n = n as usize;
+    let mut best = vec![BigUint::from(1u32); n + 1];
+    for p in SegmentedPrimes::new(2, n as u64) {
+        let p = p as usize;
+        // Going down, so each `best[s - q]` is still from before `p` was considered
+        for s in (p..=n).rev() {
+            let mut q = p;
+            while q <= s {
+                let candidate = &best[s - q] * q;
+                if candidate > best[s] {
+                    best[s] = candidate;
+                }
+                q = match q.checked_mul(p) {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        }
+    }
+    best.swap_remove(n)
+}
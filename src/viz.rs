@@ -0,0 +1,132 @@
+//! Coordinates for drawing the primes, for plotting front-ends to consume directly.
+
+use std::f64::consts::PI;
+
+use crate::segment::SegmentedPrimes;
+
+// Walks 1, 2, 3, ... alongside a stream of the primes, to flag which are prime
+struct PrimeFlags {
+    n: u64,
+    limit: u64,
+    primes: SegmentedPrimes,
+    next_prime: Option<u64>,
+}
+
+impl PrimeFlags {
+    fn new(limit: u64) -> PrimeFlags {
+        let mut primes = SegmentedPrimes::new(2, limit);
+        let next_prime = primes.next();
+        PrimeFlags {
+            n: 1,
+            limit,
+            primes,
+            next_prime,
+        }
+    }
+}
+
+impl Iterator for PrimeFlags {
+    type Item = (u64, bool);
+    fn next(&mut self) -> Option<(u64, bool)> {
+        if self.n > self.limit || self.n == 0 {
+            return None;
+        }
+        let n = self.n;
+        // Wraps to 0 after u64::MAX, which ends the iteration
+        self.n = self.n.wrapping_add(1);
+        let is_prime = self.next_prime == Some(n);
+        if is_prime {
+            self.next_prime = self.primes.next();
+        }
+        Some((n, is_prime))
+    }
+}
+
+/// An iterator over the points of an Ulam spiral. Created by `ulam_spiral`.
+pub struct UlamSpiral {
+    flags: PrimeFlags,
+    position: (i64, i64),
+    direction: (i64, i64),
+    // How long the current side is, and how far along it the walk has gone
+    side: i64,
+    steps: i64,
+    // Each side length is used twice before growing
+    turns: u8,
+}
+
+/// Iterate over `(x, y, is_prime)` for `n` from 1 to `limit` laid out in an Ulam spiral: 1 at the
+/// origin, 2 to its right, then winding counterclockwise outwards.
+///
+/// ```
+/// use primes::viz::ulam_spiral;
+///
+/// let points: Vec<(i64, i64, bool)> = ulam_spiral(5).collect();
+/// assert_eq!(
+///     points,
+///     vec![(0, 0, false), (1, 0, true), (1, 1, true), (0, 1, false), (-1, 1, true)]
+/// );
+/// ```
+pub fn ulam_spiral(limit: u64) -> UlamSpiral {
+    UlamSpiral {
+        flags: PrimeFlags::new(limit),
+        position: (0, 0),
+        direction: (1, 0),
+        side: 1,
+        steps: 0,
+        turns: 0,
+    }
+}
+
+impl Iterator for UlamSpiral {
+    type Item = (i64, i64, bool);
+    fn next(&mut self) -> Option<(i64, i64, bool)> {
+        let (_, is_prime) = self.flags.next()?;
+        let (x, y) = self.position;
+
+        self.position = (x + self.direction.0, y + self.direction.1);
+        self.steps += 1;
+        if self.steps == self.side {
+            self.steps = 0;
+            // Turn left
+            self.direction = (-self.direction.1, self.direction.0);
+            self.turns += 1;
+            if self.turns == 2 {
+                self.turns = 0;
+                self.side += 1;
+            }
+        }
+        Some((x, y, is_prime))
+    }
+}
+
+/// An iterator over the points of a Sacks spiral. Created by `sacks_spiral`.
+pub struct SacksSpiral {
+    flags: PrimeFlags,
+}
+
+/// Iterate over `(x, y, is_prime)` for `n` from 1 to `limit` laid out in a Sacks spiral: `n` sits
+/// at radius `sqrt(n)` and angle `2π sqrt(n)`, so the perfect squares line up along the positive
+/// x-axis.
+///
+/// ```
+/// use primes::viz::sacks_spiral;
+///
+/// let (x, y, is_prime) = sacks_spiral(4).last().unwrap();
+/// assert!((x - 2.0).abs() < 1e-9 && y.abs() < 1e-9);
+/// assert!(!is_prime);
+/// ```
+pub fn sacks_spiral(limit: u64) -> SacksSpiral {
+    SacksSpiral {
+        flags: PrimeFlags::new(limit),
+    }
+}
+
+impl Iterator for SacksSpiral {
+    type Item = (f64, f64, bool);
+    fn next(&mut self) -> Option<(f64, f64, bool)> {
+        let (n, is_prime) = self.flags.next()?;
+        let r = (n as f64).sqrt();
+        let theta = 2.0 * PI * r;
+        Some((r * theta.cos(), r * theta.sin(), is_prime))
+    }
+}
@@ -0,0 +1,202 @@
+//! Residue wheels that screen out multiples of a few small primes.
+
+use crate::is_prime;
+
+/**
+The residue table for a set of small primes: the residues modulo their product that none of them
+divide. Stepping through those residues, one turn of the wheel after another, visits exactly the
+numbers that have no factor in the set.
+
+The sieves use a hard-coded wheel for `[2, 3, 5]`; this builds one for any set of primes at
+runtime, for experimenting with how much a larger wheel saves against how much memory it costs.
+
+```
+use primes::Wheel;
+
+let wheel = Wheel::for_basis(&[2, 3, 5, 7]);
+assert_eq!(wheel.modulus(), 210);
+assert_eq!(wheel.spokes(), 48);
+let candidates: Vec<u64> = wheel.candidates_from(100).take(5).collect();
+assert_eq!(candidates, vec![101, 103, 107, 109, 113]);
+```
+**/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wheel {
+    primes: Vec<u64>,
+    modulus: u64,
+    // The residues modulo `modulus` coprime to it, in increasing order
+    residues: Vec<u64>,
+}
+
+impl Wheel {
+    /// The largest modulus `for_basis` accepts, which is the product of the primes up to 19.
+    /// Its table has 1,658,880 residues.
+    pub const MAX_MODULUS: u64 = 9_699_690;
+
+    /// Build the wheel for `primes`; repeats are ignored, and an empty slice gives a wheel that
+    /// skips nothing.
+    ///
+    /// Panics if any of `primes` is not prime, or if their product is larger than
+    /// `Wheel::MAX_MODULUS`.
+    pub fn for_basis(primes: &[u64]) -> Wheel {
+        let mut primes = primes.to_vec();
+        primes.sort_unstable();
+        primes.dedup();
+        let mut modulus: u64 = 1;
+        for &p in &primes {
+            assert!(is_prime(p), "{} is not prime", p);
+            modulus = modulus
+                .checked_mul(p)
+                .filter(|&m| m <= Wheel::MAX_MODULUS)
+                .expect("wheel modulus is larger than Wheel::MAX_MODULUS");
+        }
+        let residues = (0..modulus)
+            .filter(|&r| primes.iter().all(|&p| r % p != 0))
+            .collect();
+        Wheel {
+            primes,
+            modulus,
+            residues,
+        }
+    }
+
+    /// The primes the wheel is built from, in increasing order
+    pub fn primes(&self) -> &[u64] {
+        &self.primes
+    }
+
+    /// The product of the wheel's primes
+    pub fn modulus(&self) -> u64 {
+        self.modulus
+    }
+
+    /// The residues modulo `modulus()` that none of the wheel's primes divide, in increasing order
+    pub fn residues(&self) -> &[u64] {
+        &self.residues
+    }
+
+    /// The number of residues per turn of the wheel, which is φ(modulus)
+    pub fn spokes(&self) -> usize {
+        self.residues.len()
+    }
+
+    /// The fraction of all numbers the wheel visits, `spokes() / modulus()`
+    pub fn density(&self) -> f64 {
+        self.residues.len() as f64 / self.modulus as f64
+    }
+
+    /// Whether none of the wheel's primes divide `n`
+    pub fn admissible(&self, n: u64) -> bool {
+        self.residues.binary_search(&(n % self.modulus)).is_ok()
+    }
+
+    /// Iterate over the numbers the wheel visits that are at least `n`, in increasing order, up
+    /// to the largest that fits in a `u64`.
+    pub fn candidates_from(&self, n: u64) -> WheelCandidates<'_> {
+        let r = n % self.modulus;
+        WheelCandidates {
+            wheel: self,
+            base: Some(n - r),
+            ix: self.residues.partition_point(|&s| s < r),
+        }
+    }
+}
+
+/// An iterator over the numbers a `Wheel` visits. Created by `Wheel::candidates_from`.
+#[derive(Debug, Clone)]
+pub struct WheelCandidates<'a> {
+    wheel: &'a Wheel,
+    // The multiple of the modulus the wheel is currently on, or `None` past `u64::MAX`
+    base: Option<u64>,
+    ix: usize,
+}
+
+impl<'a> Iterator for WheelCandidates<'a> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        if self.ix == self.wheel.residues.len() {
+            self.ix = 0;
+            self.base = self.base?.checked_add(self.wheel.modulus);
+        }
+        let n = self.base?.checked_add(self.wheel.residues[self.ix]);
+        if n.is_none() {
+            self.base = None;
+        }
+        self.ix += 1;
+        n
+    }
+}
+
+/**
+A filter built from a set of small primes, for skipping candidates that one of them divides.
+
+This wraps a `Wheel` with the tests user code needs when it generates its own candidates (e.g.
+numbers of a special form), so it can discard the obvious composites cheaply before running a
+primality test.
+
+```
+use primes::WheelFilter;
+
+let wheel = WheelFilter::new(&[2, 3, 5, 7]);
+assert_eq!(wheel.modulus(), 210);
+assert!(wheel.admissible(121));
+assert!(!wheel.admissible(91));
+assert_eq!(wheel.next_admissible(1), 11);
+assert_eq!(wheel.next_admissible(11), 13);
+```
+
+Note that the primes the wheel is built from are themselves not admissible.
+**/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WheelFilter {
+    wheel: Wheel,
+}
+
+impl WheelFilter {
+    /// Build a filter from `primes`; repeats are ignored, and an empty slice gives a filter that
+    /// admits everything.
+    ///
+    /// Panics if any of `primes` is not prime, or if their product is larger than
+    /// `Wheel::MAX_MODULUS`.
+    pub fn new(primes: &[u64]) -> WheelFilter {
+        WheelFilter {
+            wheel: Wheel::for_basis(primes),
+        }
+    }
+
+    /// The underlying wheel
+    pub fn wheel(&self) -> &Wheel {
+        &self.wheel
+    }
+
+    /// The primes the wheel is built from, in increasing order
+    pub fn primes(&self) -> &[u64] {
+        self.wheel.primes()
+    }
+
+    /// The product of the wheel's primes
+    pub fn modulus(&self) -> u64 {
+        self.wheel.modulus()
+    }
+
+    /// The residues modulo `modulus()` that are admissible, in increasing order
+    pub fn residues(&self) -> &[u64] {
+        self.wheel.residues()
+    }
+
+    /// Whether none of the wheel's primes divide `n`
+    pub fn admissible(&self, n: u64) -> bool {
+        self.wheel.admissible(n)
+    }
+
+    /// The smallest admissible number greater than `n`.
+    ///
+    /// Panics if there is no admissible number greater than `n` that fits in a `u64`.
+    pub fn next_admissible(&self, n: u64) -> u64 {
+        let after = n.checked_add(1).expect("no admissible number after n");
+        self.wheel
+            .candidates_from(after)
+            .next()
+            .expect("no admissible number after n")
+    }
+}
@@ -0,0 +1,299 @@
+use primes::{
+    achilles_numbers, classify_aliquot, divisor_summatory, factors_uniq, highly_composite_numbers,
+    is_achilles, is_powerful, is_practical, is_prime_power, log_product_of_primes_below, mangoldt,
+    mangoldt_range, max_below_with_factors, omega_sieve, powerful_numbers, prime_powers,
+    prime_zeta, prod_one_minus_inv_p_below, product_of_primes_mod, sum_reciprocal_primes_below,
+    totient_range, totient_summatory, Aliquot, Factorization,
+};
+
+#[test]
+fn test_omega_sieve() {
+    assert_eq!(omega_sieve(0), vec![0]);
+    assert_eq!(omega_sieve(1), vec![0, 0]);
+
+    let omega = omega_sieve(100_000);
+    assert_eq!(omega.len(), 100_001);
+    for (k, &w) in omega.iter().enumerate() {
+        assert_eq!(w as usize, factors_uniq(k as u64).len(), "omega({})", k);
+    }
+    assert_eq!(omega[30_030], 6);
+}
+
+#[test]
+fn test_highly_composite_numbers() {
+    // Check records against a brute-force divisor count
+    let mut record = 0;
+    let mut expected = Vec::new();
+    for n in 1..10_000u64 {
+        let d = Factorization::of(n).num_divisors();
+        if d > record {
+            record = d;
+            expected.push(n);
+        }
+    }
+    let hcn: Vec<u64> = highly_composite_numbers()
+        .take_while(|&n| n < 10_000)
+        .collect();
+    assert_eq!(hcn, expected);
+
+    // The last highly composite number that fits in a u64
+    let last = highly_composite_numbers().last();
+    assert_eq!(last, Some(18_401_055_938_125_660_800));
+}
+
+#[test]
+fn test_is_practical() {
+    // Brute force: every k <= n is a sum of distinct divisors of n
+    fn brute(n: u64) -> bool {
+        let divisors = Factorization::of(n).divisors();
+        let mut reachable = vec![false; n as usize + 1];
+        reachable[0] = true;
+        for d in divisors {
+            for k in (d as usize..=n as usize).rev() {
+                reachable[k] |= reachable[k - d as usize];
+            }
+        }
+        reachable.iter().all(|&r| r)
+    }
+    assert!(!is_practical(0));
+    for n in 1..500 {
+        assert_eq!(is_practical(n), brute(n), "{}", n);
+    }
+    assert!(is_practical(720_720));
+    assert!(!is_practical(2 * 1_000_003));
+}
+
+#[test]
+fn test_powerful_and_achilles() {
+    assert!(is_powerful(1));
+    assert!(!is_powerful(0));
+    assert!(!is_achilles(1));
+
+    let powerful: Vec<u64> = powerful_numbers().take_while(|&n| n <= 100_000).collect();
+    let expected: Vec<u64> = (1..=100_000).filter(|&n| is_powerful(n)).collect();
+    assert_eq!(powerful, expected);
+
+    let achilles: Vec<u64> = achilles_numbers().take_while(|&n| n <= 100_000).collect();
+    let expected: Vec<u64> = (1..=100_000).filter(|&n| is_achilles(n)).collect();
+    assert_eq!(achilles, expected);
+
+    assert!(is_powerful(1 << 63));
+    assert!(!is_achilles(1 << 63));
+    assert!(!is_achilles((1 << 60) * 3 * 3));
+    assert!(is_achilles((1 << 59) * 3 * 3));
+}
+
+#[test]
+fn test_summatory_functions() {
+    let mut phi_sum = 0u128;
+    let mut d_sum = 0u128;
+    assert_eq!(totient_summatory(0), 0);
+    assert_eq!(divisor_summatory(0), 0);
+    for (n, phi) in totient_range(1, 3_000) {
+        phi_sum += phi as u128;
+        d_sum += Factorization::of(n).num_divisors() as u128;
+        assert_eq!(totient_summatory(n), phi_sum, "Phi({})", n);
+        assert_eq!(divisor_summatory(n), d_sum, "D({})", n);
+    }
+
+    assert_eq!(totient_summatory(1_000_000_000), 303_963_551_173_008_414);
+    // Past the point where the sum fits in a u64
+    assert_eq!(
+        totient_summatory(10_000_000_000),
+        30_396_355_092_886_216_366
+    );
+    assert_eq!(divisor_summatory(10_000_000_000), 231_802_823_220);
+}
+
+#[test]
+fn test_prime_powers() {
+    let mut expected = Vec::new();
+    for n in 0..200_000 {
+        let pairs = if n == 0 {
+            vec![]
+        } else {
+            Factorization::of(n).pairs().to_vec()
+        };
+        let answer = match pairs[..] {
+            [(p, k)] => Some((p, k)),
+            _ => None,
+        };
+        assert_eq!(is_prime_power(n), answer, "{}", n);
+        if answer.is_some() {
+            expected.push(n);
+        }
+    }
+    let powers: Vec<u64> = prime_powers().take(expected.len()).collect();
+    assert_eq!(powers, expected);
+
+    assert_eq!(is_prime_power(1 << 63), Some((2, 63)));
+    assert_eq!(is_prime_power(3u64.pow(40)), Some((3, 40)));
+    assert_eq!(
+        is_prime_power(4_294_967_291 * 4_294_967_291),
+        Some((4_294_967_291, 2))
+    );
+    assert_eq!(is_prime_power(4_294_967_291 * 4_294_967_279), None);
+    assert_eq!(is_prime_power(u64::MAX), None);
+    assert_eq!(
+        is_prime_power(18_446_744_073_709_551_557),
+        Some((18_446_744_073_709_551_557, 1))
+    );
+}
+
+#[test]
+fn test_mangoldt() {
+    for &(lo, hi) in &[(0, 70_000), (1 << 40, (1 << 40) + 2_000)] {
+        let mut count = 0;
+        for (n, lambda) in mangoldt_range(lo, hi) {
+            assert_eq!(lambda, mangoldt(n), "{}", n);
+            count += 1;
+        }
+        assert_eq!(count, hi - lo.max(2) + 1);
+    }
+    assert_eq!(mangoldt(1 << 40), 2f64.ln());
+    assert_eq!(mangoldt(0), 0.0);
+
+    // Chebyshev's ψ(x) is close to x, by the prime number theorem
+    let psi: f64 = mangoldt_range(0, 100_000).map(|(_, l)| l).sum();
+    assert!((psi - 100_000.0).abs() < 500.0);
+}
+
+#[test]
+fn test_log_product_of_primes_below() {
+    assert_eq!(log_product_of_primes_below(0), 0.0);
+    assert!((log_product_of_primes_below(2) - 2f64.ln()).abs() < 1e-12);
+    let theta = log_product_of_primes_below(1_000_000);
+    assert!((theta - 998_484.175_025_634_2).abs() < 1e-6, "{}", theta);
+}
+
+#[test]
+fn test_mertens_sums() {
+    assert_eq!(sum_reciprocal_primes_below(1), 0.0);
+    assert_eq!(prod_one_minus_inv_p_below(1), 1.0);
+    assert_eq!(prod_one_minus_inv_p_below(2), 0.5);
+    let sum = sum_reciprocal_primes_below(1_000_000);
+    assert!((sum - 2.887_328_099_567_694).abs() < 1e-12, "{}", sum);
+    let product = prod_one_minus_inv_p_below(1_000_000);
+    assert!(
+        (product - 0.040_638_210_171_648_59).abs() < 1e-14,
+        "{}",
+        product
+    );
+}
+
+#[test]
+fn test_prime_zeta() {
+    for &(s, expected) in &[
+        (1.01, 4.302651485932175),
+        (1.1, 2.1088436903320917),
+        (1.5, 0.8495626836215664),
+        (2.0, 0.4522474200410655),
+        (3.0, 0.17476263929944352),
+        (4.0, 0.07699313976424685),
+        (10.0, 0.0009936035744369802),
+    ] {
+        let p = prime_zeta(s);
+        assert!((p - expected).abs() < 1e-14 * expected, "P({}) = {}", s, p);
+    }
+    // Agrees with a direct sum where the tail is negligible
+    let mut direct = 0.0;
+    primes::for_each_prime_in(2, 100_000, |p| direct += (p as f64).powi(-5));
+    assert!((prime_zeta(5.0) - direct).abs() < 1e-15);
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn test_product_of_primes_below() {
+    use num_bigint::BigUint;
+    use primes::product_of_primes_below;
+
+    let expected: BigUint = "2305567963945518424753102147331756070".parse().unwrap();
+    assert_eq!(product_of_primes_below(100), expected);
+    assert_eq!(product_of_primes_below(0), BigUint::from(1u32));
+    assert_eq!(product_of_primes_below(10_000).to_string().len(), 4_298);
+
+    let product = product_of_primes_below(10_000);
+    let log = product.bits() as f64 * 2f64.ln();
+    assert!((log - log_product_of_primes_below(10_000)).abs() < 1.0);
+}
+
+#[test]
+fn test_max_below_with_factors() {
+    let sets: [&[u64]; 5] = [&[2], &[2, 3], &[2, 3, 5, 7], &[5, 11, 13], &[7]];
+    for primes in sets.iter() {
+        let mut smooth = Vec::new();
+        for n in 1..5_000u64 {
+            let rest = primes.iter().fold(n, |mut m, &p| {
+                while m % p == 0 {
+                    m /= p;
+                }
+                m
+            });
+            if rest == 1 {
+                smooth.push(n);
+            }
+            assert_eq!(max_below_with_factors(n, primes), smooth.last().copied());
+        }
+    }
+    assert_eq!(max_below_with_factors(0, &[2]), None);
+    assert_eq!(max_below_with_factors(u64::MAX, &[2]), Some(1 << 63));
+    assert_eq!(
+        max_below_with_factors(u64::MAX, &[3, 2, 3]),
+        Some(17_991_041_643_939_889_152)
+    );
+    assert_eq!(
+        max_below_with_factors(u64::MAX, &[2, 3, 5, 7, 11, 13]),
+        Some(18_446_613_971_412_049_920)
+    );
+}
+
+#[test]
+fn test_classify_aliquot() {
+    let mut counts = std::collections::HashMap::new();
+    for n in 1..1_000 {
+        *counts.entry(classify_aliquot(n, 30)).or_insert(0) += 1;
+    }
+    assert_eq!(counts.len(), 6);
+    assert_eq!(counts[&Aliquot::Terminates], 921);
+    assert_eq!(counts[&Aliquot::Unknown], 56);
+    assert_eq!(counts[&Aliquot::Perfect(6)], 13);
+    assert_eq!(counts[&Aliquot::Perfect(28)], 1);
+    assert_eq!(counts[&Aliquot::Perfect(496)], 5);
+    assert_eq!(counts[&Aliquot::Amicable(220, 284)], 3);
+
+    assert_eq!(classify_aliquot(1, 0), Aliquot::Unknown);
+    assert_eq!(classify_aliquot(1, 1), Aliquot::Terminates);
+    assert_eq!(classify_aliquot(6, 1), Aliquot::Perfect(6));
+    assert_eq!(classify_aliquot(220, 1), Aliquot::Unknown);
+    let cycle = vec![1_264_460, 1_547_860, 1_727_636, 1_305_184];
+    assert_eq!(classify_aliquot(1_305_184, 4), Aliquot::Sociable(cycle));
+    match classify_aliquot(14_316, 28) {
+        Aliquot::Sociable(cycle) => {
+            assert_eq!(cycle.len(), 28);
+            assert_eq!(cycle[0], 14_316);
+        }
+        other => panic!("{:?}", other),
+    }
+    // σ(3 · 2^62) overflows
+    assert_eq!(classify_aliquot(3 << 62, 10), Aliquot::Unknown);
+}
+
+#[test]
+fn test_product_of_primes_mod() {
+    let primes: Vec<u64> = (0..500).filter(|&n| primes::is_prime(n)).collect();
+    for &m in &[1, 2, 7, 30, 1_000_000_007, u64::MAX] {
+        for lo in (0..500).step_by(37) {
+            for hi in (lo..500).step_by(53) {
+                let expected = primes
+                    .iter()
+                    .filter(|&&p| p >= lo && p <= hi)
+                    .fold(1 % m as u128, |acc, &p| acc * p as u128 % m as u128);
+                assert_eq!(product_of_primes_mod(lo, hi, m) as u128, expected);
+            }
+        }
+    }
+
+    let p = 1_000_003;
+    assert_eq!(product_of_primes_mod(p, p, p), 0);
+    assert_eq!(product_of_primes_mod(2, 1_000_000, p), 803_659);
+}
@@ -1,4 +1,9 @@
-use primes::{factors, factors_uniq, is_prime, PrimeSet, PrimeSetBasics, Sieve, TrialDivision};
+use primes::{
+    collect_into_primeset, factors, factors_uniq, factors_with_limit, greatest_prime_factor,
+    is_prime, is_square, smallest_prime_factor, sopf, sopfr, static_primes, DefaultPrimeSet,
+    ExternalPrimes, IterPrimes, LimitExceeded, PrimeSet, PrimeSetBasics, SharedPrimes, Sieve,
+    TrialDivision, Wheel, WheelFilter, LARGEST_U64_PRIME,
+};
 
 #[test]
 fn test_primesetbasics() {
@@ -26,6 +31,50 @@ fn test_iter() {
     }
 }
 
+#[test]
+fn test_expand_next() {
+    let mut sieve = Sieve::new();
+    let mut td = TrialDivision::new();
+    for _ in 0..1_000 {
+        let p = sieve.expand_next();
+        assert_eq!(Some(&p), sieve.list().last());
+        let q = td.expand_next();
+        assert_eq!(Some(&q), td.list().last());
+    }
+}
+
+#[test]
+fn test_last_found_and_checked() {
+    let mut td = TrialDivision::new();
+    assert_eq!(td.last_found(), Some(3));
+    assert_eq!(td.largest_guaranteed_checked(), 3);
+    td.find(100);
+    assert_eq!(td.last_found(), Some(101));
+    assert_eq!(td.largest_guaranteed_checked(), 101);
+
+    let mut sieve = Sieve::new();
+    sieve.find(100);
+    assert_eq!(sieve.last_found(), Some(101));
+    assert_eq!(sieve.largest_guaranteed_checked(), sieve.largest_checked());
+    assert!(sieve.largest_guaranteed_checked() >= 101);
+
+    // Answered from the list, and by trial division past it
+    for n in 0..2_000 {
+        assert_eq!(sieve.is_prime(n), is_prime(n), "{}", n);
+    }
+
+    // And by Miller-Rabin for large numbers, without generating anything
+    for n in (1 << 20) - 100..(1 << 20) + 100 {
+        assert_eq!(sieve.is_prime(n), is_prime(n), "{}", n);
+    }
+    let len = sieve.len();
+    for n in (LARGEST_U64_PRIME - 100)..=LARGEST_U64_PRIME {
+        assert_eq!(sieve.is_prime(n), is_prime(n), "{}", n);
+    }
+    assert!(!sieve.is_prime(4_294_967_291 * 4_294_967_279));
+    assert_eq!(sieve.len(), len);
+}
+
 #[test]
 fn test_find() {
     let mut pset = TrialDivision::new();
@@ -50,6 +99,24 @@ fn test_find() {
     assert_eq!(pset.find_vec(n_exp), Some((ix_exp, n_exp)));
 }
 
+#[test]
+fn test_find_exact_and_neighbors() {
+    let mut pset = TrialDivision::new();
+    pset.find(1_000);
+    let list = pset.list().to_vec();
+    for n in 0..1_009 {
+        let exact = list.iter().position(|&p| p == n);
+        assert_eq!(pset.find_exact(n), exact, "{}", n);
+
+        let prev = list.iter().copied().rfind(|&p| p < n);
+        let next = list.iter().copied().find(|&p| p > n).unwrap();
+        assert_eq!(pset.find_neighbors(n), Some((prev, next)), "{}", n);
+    }
+    // 1009 is the largest prime found, so nothing is known past it
+    assert_eq!(pset.find_exact(1_009), Some(168));
+    assert_eq!(pset.find_neighbors(1_009), None);
+}
+
 #[test]
 fn test_primes() {
     let mut pset = TrialDivision::new();
@@ -92,7 +159,9 @@ fn test_primes() {
     assert!(pset.is_prime(63061489));
     assert!(!is_prime(63061489 * 2147483647));
     assert!(!is_prime(63061489 * 63061489));
-    // assert!(!is_prime(2147483647 * 2147483647)); // Runs very long
+    assert!(!is_prime(2147483647 * 2147483647));
+    assert!(!pset.is_prime(2147483647 * 2147483647));
+    assert!(pset.is_prime(LARGEST_U64_PRIME));
 }
 
 #[test]
@@ -124,6 +193,7 @@ fn test_factors() {
         let unique_factors = factors_uniq(n);
 
         // Get unique factors from the lists we made above
+        #[allow(clippy::map_clone)]
         let mut unique_factors_exp: Vec<u64> = v.iter().map(|&x| x).collect();
         unique_factors_exp.dedup();
 
@@ -145,3 +215,670 @@ fn test_sieve() {
 
     assert_eq!(sieved, trialled);
 }
+
+#[test]
+fn test_factors_with_limit() {
+    for n in 0..2_000 {
+        for &limit in [0, 1, 2, 3, 5, 10, 31, 50, 2_000].iter() {
+            let (small, cofactor) = factors_with_limit(n, limit);
+            let expected: Vec<u64> = factors(n).into_iter().filter(|&p| p <= limit).collect();
+            assert_eq!(small, expected, "factors_with_limit({}, {})", n, limit);
+            if n > 0 {
+                assert_eq!(small.iter().product::<u64>() * cofactor, n);
+            }
+            assert!(factors(cofactor).iter().all(|&p| p > limit));
+        }
+    }
+}
+
+#[test]
+fn test_composites() {
+    let mut pset = TrialDivision::new();
+    let composites: Vec<u64> = pset.composites().take_while(|&n| n < 1_000).collect();
+    let expected: Vec<u64> = (4..1_000).filter(|&n| !is_prime(n)).collect();
+    assert_eq!(composites, expected);
+
+    let mut sieve = Sieve::new();
+    for (n, spf) in sieve.composites_with_spf().take(5_000) {
+        assert_eq!(spf, factors(n)[0], "{}", n);
+    }
+}
+
+#[test]
+fn test_windows() {
+    let mut pset = Sieve::new();
+    let primes: Vec<u64> = pset.iter().take(1_000).collect();
+
+    let mut td = TrialDivision::new();
+    let pairs: Vec<[u64; 2]> = td.windows::<2>().take(999).collect();
+    let expected: Vec<[u64; 2]> = primes.windows(2).map(|w| [w[0], w[1]]).collect();
+    assert_eq!(pairs, expected);
+
+    let singles: Vec<[u64; 1]> = pset.windows::<1>().take(1_000).collect();
+    assert_eq!(singles.concat(), primes);
+
+    let fives: Vec<[u64; 5]> = pset.windows::<5>().take(2).collect();
+    assert_eq!(fives, vec![[2, 3, 5, 7, 11], [3, 5, 7, 11, 13]]);
+}
+
+#[test]
+#[should_panic]
+fn test_windows_empty() {
+    let mut pset = Sieve::new();
+    pset.windows::<0>();
+}
+
+#[test]
+fn test_iter_indexed() {
+    let mut pset = Sieve::new();
+    let expected: Vec<(usize, u64)> = Sieve::new().iter().enumerate().take(500).collect();
+    let indexed: Vec<(usize, u64)> = pset.iter_indexed().take(500).collect();
+    assert_eq!(indexed, expected);
+
+    let skipped: Vec<(usize, u64)> = pset.iter_indexed().skip(1_000).take(3).collect();
+    assert_eq!(
+        skipped,
+        vec![(1_000, 7_927), (1_001, 7_933), (1_002, 7_937)]
+    );
+
+    let mut td = TrialDivision::new();
+    assert_eq!(td.iter_indexed().nth(168), Some((168, 1_009)));
+}
+
+#[test]
+fn test_find_from_hint() {
+    let mut pset = Sieve::new();
+    pset.find(5_000);
+    for n in 0..5_000 {
+        let expected = pset.find(n);
+        for &hint in [0, 1, 2, 10, 100, 668, 669, 10_000].iter() {
+            assert_eq!(
+                pset.find_from_hint(n, hint),
+                expected,
+                "{} from {}",
+                n,
+                hint
+            );
+        }
+    }
+
+    let mut td = TrialDivision::new();
+    assert_eq!(td.find_from_hint(1_000, 0), (168, 1_009));
+}
+
+#[test]
+fn test_as_ref() {
+    fn sum_of<T: AsRef<[u64]>>(primes: T) -> u64 {
+        primes.as_ref().iter().sum()
+    }
+
+    let mut sieve = Sieve::new();
+    let mut td = TrialDivision::new();
+    sieve.find(30);
+    td.find(30);
+    assert_eq!(
+        sum_of(&sieve),
+        2 + 3 + 5 + 7 + 11 + 13 + 17 + 19 + 23 + 29 + 31
+    );
+    assert_eq!(sieve.as_ref(), td.as_ref());
+    assert_eq!(sieve[4], 11);
+    assert_eq!(td[4], 11);
+}
+
+#[test]
+fn test_freeze() {
+    let mut pset = Sieve::new();
+    pset.find(1_000);
+    let frozen = pset.freeze();
+    let copy = frozen.clone();
+    assert_eq!(copy.as_slice(), pset.list());
+    assert_eq!(frozen.len(), 169);
+    assert_eq!(frozen.last(), Some(1_009));
+    assert_eq!(frozen.get(168), Some(1_009));
+    assert_eq!(frozen.get(169), None);
+
+    for n in 0..=1_009 {
+        assert_eq!(frozen.is_prime_cached(n), Some(is_prime(n)), "{}", n);
+        assert_eq!(frozen.find(n), pset.find_vec(n), "{}", n);
+        let rank = (0..=n).filter(|&k| is_prime(k)).count();
+        assert_eq!(frozen.rank(n), Some(rank), "{}", n);
+    }
+    assert_eq!(frozen.is_prime_cached(1_010), None);
+    assert_eq!(frozen.rank(1_010), None);
+    assert_eq!(frozen.find(1_010), None);
+}
+
+#[test]
+fn test_sieve_from_primes() {
+    let expected: Vec<u64> = Sieve::new().iter().take(3_000).collect();
+    for &len in [0, 1, 2, 3, 4, 10, 11, 100, 1_000, 2_999].iter() {
+        let mut sieve = Sieve::from_primes(expected[..len].to_vec());
+        assert!(sieve.len() >= len);
+        let resumed: Vec<u64> = sieve.iter().take(3_000).collect();
+        assert_eq!(resumed, expected, "resuming from {} primes", len);
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_sieve_from_primes_bad_start() {
+    let _ = Sieve::from_primes(vec![3, 5, 7]);
+}
+
+#[test]
+fn test_sieve_merge() {
+    let mut short = Sieve::new();
+    let mut long = Sieve::new();
+    short.find(100);
+    long.find(10_000);
+
+    let mut merged = short.clone();
+    merged.merge(&long);
+    assert_eq!(merged.list(), long.list());
+
+    let mut merged = long.clone();
+    merged.merge(&short);
+    assert_eq!(merged.list(), long.list());
+    assert_eq!(merged.get(2_000), TrialDivision::new().get(2_000));
+}
+
+#[test]
+#[should_panic]
+fn test_sieve_merge_mismatched() {
+    let mut sieve = Sieve::new();
+    sieve.find(100);
+    sieve.merge(&Sieve::from_primes(vec![2, 3, 5, 7, 11, 17]));
+}
+
+#[test]
+fn test_sieve_checkpoint() {
+    let mut sieve = Sieve::new();
+    sieve.find(50_000);
+    let state = sieve.checkpoint();
+    assert_eq!(state.primes, sieve.list());
+    assert_eq!(state.pending.len(), sieve.len() - 3);
+
+    let mut restored = Sieve::restore(state.clone());
+    assert_eq!(restored.checkpoint(), state);
+    let expected: Vec<u64> = sieve.iter().take(8_000).collect();
+    let resumed: Vec<u64> = restored.iter().take(8_000).collect();
+    assert_eq!(resumed, expected);
+
+    let mut fresh = Sieve::restore(Sieve::new().checkpoint());
+    assert_eq!(fresh.get(100), 547);
+}
+
+#[test]
+fn test_prime_factor_sums() {
+    assert_eq!(sopfr(0), 0);
+    assert_eq!(sopfr(1), 0);
+    assert_eq!(sopf(1), 0);
+    assert_eq!(smallest_prime_factor(1), None);
+    assert_eq!(greatest_prime_factor(0), None);
+
+    assert_eq!(sopfr(720), 2 * 4 + 3 * 2 + 5);
+    assert_eq!(sopf(720), 2 + 3 + 5);
+    assert_eq!(smallest_prime_factor(720), Some(2));
+    assert_eq!(greatest_prime_factor(720), Some(5));
+
+    assert_eq!(sopfr(954_377 * 954_379), 954_377 + 954_379);
+    assert_eq!(smallest_prime_factor(954_377 * 954_379), Some(954_377));
+    assert_eq!(greatest_prime_factor(954_377 * 954_379), Some(954_379));
+    assert_eq!(greatest_prime_factor(13), Some(13));
+}
+
+#[test]
+fn test_prime_query() {
+    let mut pset = TrialDivision::new();
+    let all: Vec<u64> = pset.primes().take(10).collect();
+    assert_eq!(all, pset.iter().take(10).collect::<Vec<u64>>());
+
+    let from: Vec<u64> = pset.primes().from(7).below(20).collect();
+    assert_eq!(from, vec![7, 11, 13, 17, 19]);
+    assert_eq!(pset.primes().from(8).below(11).count(), 0);
+    assert_eq!(pset.primes().below(2).count(), 0);
+
+    let mut sieve = Sieve::new();
+    let quarter: Vec<u64> = sieve
+        .primes()
+        .from(10_000)
+        .below(1_000_000)
+        .residues_mod(4, &[1])
+        .collect();
+    let expected: Vec<u64> = (10_000..1_000_000)
+        .filter(|&n| n % 4 == 1 && is_prime(n))
+        .collect();
+    assert_eq!(quarter, expected);
+
+    let mut query = sieve.primes().below(3);
+    assert_eq!(query.next(), Some(2));
+    assert_eq!(query.next(), None);
+    assert_eq!(query.next(), None);
+}
+
+#[test]
+fn test_iter_to() {
+    let mut pset = TrialDivision::new();
+    assert_eq!(pset.iter_to(0).len(), 0);
+    assert_eq!(pset.iter_to(2).collect::<Vec<u64>>(), vec![2]);
+    for limit in 0..500 {
+        let expected: Vec<u64> = (0..=limit).filter(|&n| is_prime(n)).collect();
+        let mut sieve = Sieve::new();
+        assert_eq!(sieve.iter_to(limit).len(), expected.len());
+        assert_eq!(sieve.iter_to(limit).collect::<Vec<u64>>(), expected);
+        let reversed: Vec<u64> = pset.iter_to(limit).rev().collect();
+        assert_eq!(
+            reversed,
+            expected.iter().rev().copied().collect::<Vec<u64>>()
+        );
+    }
+}
+
+#[test]
+fn test_static_primes() {
+    const PRIMES: [u64; 1_000] = static_primes!(1_000);
+    let empty: [u64; 0] = static_primes!(0);
+    assert!(empty.is_empty());
+
+    let expected: Vec<u64> = Sieve::new().iter().take(1_000).collect();
+    assert_eq!(PRIMES.to_vec(), expected);
+
+    let mut td = TrialDivision::with_seed_primes(&PRIMES);
+    let mut sieve = Sieve::with_seed_primes(&PRIMES[..500]);
+    assert_eq!(td.len(), 1_000);
+    assert_eq!(td.get(2_000), sieve.get(2_000));
+    assert_eq!(TrialDivision::with_seed_primes(&[2]).list(), &[2, 3]);
+    assert_eq!(
+        TrialDivision::from_primes(sieve.list().to_vec()).list(),
+        sieve.list()
+    );
+}
+
+#[test]
+fn test_default_prime_set() {
+    let mut pset = DefaultPrimeSet::new();
+    assert_eq!(pset.find(1_000), (168, 1_009));
+}
+
+#[test]
+fn test_external_primes() {
+    let mut source = TrialDivision::new();
+    let expected: Vec<u64> = source.iter().take(1_000).collect();
+
+    let mut it = expected.clone().into_iter();
+    let mut pset = ExternalPrimes::new(move || it.next().unwrap());
+    assert_eq!(pset.list(), &[2]);
+    assert_eq!(pset.find(1_000), (168, 1_009));
+    assert!(pset.is_prime(7_919));
+    assert_eq!(pset.list(), &expected[..pset.list().len()]);
+
+    let mut pset = ExternalPrimes::from_slice(&expected[..100]);
+    assert_eq!(pset.get(99), 541);
+    assert_eq!(pset.prime_factors(541 * 2 * 2), vec![2, 2, 541]);
+
+    let mut rest = expected[100..].iter().copied();
+    let mut pset =
+        ExternalPrimes::with_primes(expected[..100].to_vec(), move || rest.next().unwrap());
+    assert_eq!(pset.get(999), 7_919);
+}
+
+#[test]
+#[should_panic]
+fn test_external_primes_exhausted() {
+    let mut pset = ExternalPrimes::from_slice(&[2, 3, 5]);
+    pset.get(3);
+}
+
+#[test]
+#[should_panic]
+fn test_external_primes_composite() {
+    let mut odd = vec![2, 3, 5, 7, 9, 11].into_iter();
+    let mut pset = ExternalPrimes::new(move || odd.next().unwrap());
+    pset.get(4);
+}
+
+#[test]
+#[should_panic]
+fn test_external_primes_composite_list() {
+    let _ = ExternalPrimes::from_slice(&[2, 3, 5, 7, 9, 11]);
+}
+
+#[test]
+fn test_iter_primes() {
+    let mut source = TrialDivision::new();
+    let expected: Vec<u64> = source.iter().take(1_000).collect();
+
+    let mut pset = IterPrimes::new(expected.clone().into_iter());
+    assert_eq!(pset.list(), &[2]);
+    assert_eq!(pset.find(1_000), (168, 1_009));
+    assert_eq!(pset.expandable_limit(), LARGEST_U64_PRIME);
+    assert_eq!(pset.get(999), 7_919);
+    assert_eq!(pset.expandable_limit(), 7_919);
+    assert_eq!(pset.try_find(7_920), Err(LimitExceeded { limit: 7_919 }));
+    assert_eq!(pset.iter().collect::<Vec<u64>>(), expected);
+
+    let pset: IterPrimes<_> = expected.iter().copied().take(1).into();
+    assert_eq!(pset.list(), &[2]);
+    assert_eq!(pset.expandable_limit(), 2);
+}
+
+#[test]
+fn test_collect_into_primeset() {
+    let mut source = TrialDivision::new();
+    let expected: Vec<u64> = source.iter().take(1_000).collect();
+
+    let mut sieve: Sieve = collect_into_primeset(expected[..500].iter().copied());
+    assert_eq!(sieve.list(), &expected[..500]);
+    assert_eq!(sieve.get(999), 7_919);
+    let mut td: TrialDivision = collect_into_primeset(expected[..500].iter().copied());
+    assert_eq!(td.get(999), 7_919);
+    let mut td: TrialDivision = Vec::new().into();
+    assert_eq!(td.get(999), 7_919);
+}
+
+#[test]
+#[should_panic]
+fn test_iter_primes_composite() {
+    let mut pset = IterPrimes::new(vec![2, 3, 5, 7, 9, 11].into_iter());
+    pset.get(4);
+}
+
+#[test]
+#[should_panic]
+fn test_collect_into_primeset_composite() {
+    let _: Sieve = collect_into_primeset(vec![2, 3, 5, 7, 11, 13, 15, 17]);
+}
+
+#[test]
+fn test_is_square() {
+    let mut root = 0;
+    for n in 0..100_000 {
+        if (root + 1) * (root + 1) == n {
+            root += 1;
+        }
+        assert_eq!(is_square(n), root * root == n, "{}", n);
+    }
+    // Around and above 2^53, where f64 square roots round to the wrong integer
+    for &r in &[
+        94_906_265u64,
+        94_906_267,
+        1 << 30,
+        3_037_000_499,
+        4_294_967_295,
+    ] {
+        let n = r * r;
+        assert!(is_square(n), "{}", n);
+        assert!(!is_square(n - 1), "{}", n - 1);
+        assert!(!is_square(n + 1), "{}", n + 1);
+    }
+    assert!(!is_square(u64::MAX));
+}
+
+#[test]
+fn test_sieve_inspection() {
+    let mut sieve = Sieve::new();
+    for n in 0..2_000 {
+        let p = sieve.get(n);
+        assert!(sieve.largest_checked() >= p);
+        assert_eq!(sieve.largest_checked(), sieve.current_wheel_position() - 1);
+        assert!(sieve.len() - sieve.pending_composites() <= 3);
+        // Nothing between the last prime and the wheel position is prime
+        let last = *sieve.list().last().unwrap();
+        assert!((last + 1..=sieve.largest_checked()).all(|m| !is_prime(m)));
+        assert!([1, 7, 11, 13, 17, 19, 23, 29].contains(&(sieve.current_wheel_position() % 30)));
+    }
+
+    let restored = Sieve::from_primes(sieve.list().to_vec());
+    assert_eq!(restored.largest_checked(), sieve.largest_checked());
+    assert_eq!(restored.pending_composites(), sieve.pending_composites());
+}
+
+#[test]
+fn test_iter_with_squares() {
+    let mut pset = Sieve::new();
+    for (p, square) in pset.iter_with_squares().take(1_000) {
+        assert_eq!(square, Some(p * p));
+    }
+
+    // Seeded with primes on either side of 2^32, whose squares don't fit
+    let mut td = TrialDivision::new();
+    let mut primes: Vec<u64> = td.iter().take(10).collect();
+    primes.extend_from_slice(&[4_294_967_291, 4_294_967_311]);
+    let mut pset = ExternalPrimes::from_slice(&primes);
+    let squares: Vec<Option<u64>> = pset
+        .iter_with_squares()
+        .skip(10)
+        .take(2)
+        .map(|(_, square)| square)
+        .collect();
+    assert_eq!(squares, vec![Some(4_294_967_291 * 4_294_967_291), None]);
+}
+
+#[test]
+fn test_wheel_filter() {
+    let wheel = WheelFilter::new(&[7, 2, 5, 3, 2]);
+    assert_eq!(wheel.primes(), &[2, 3, 5, 7]);
+    assert_eq!(wheel.modulus(), 210);
+    assert_eq!(wheel.residues().len(), 48);
+
+    for n in 0..1_000 {
+        let coprime = [2, 3, 5, 7].iter().all(|&p| n % p != 0);
+        assert_eq!(wheel.admissible(n), coprime, "{}", n);
+        let next = (n + 1..).find(|&m| wheel.admissible(m)).unwrap();
+        assert_eq!(wheel.next_admissible(n), next, "{}", n);
+    }
+    assert_eq!(wheel.next_admissible(209), 211);
+
+    // Every prime past the wheel's own survives the filter
+    let mut pset = Sieve::new();
+    for p in pset.iter().skip(4).take(1_000) {
+        assert!(wheel.admissible(p), "{}", p);
+    }
+
+    let everything = WheelFilter::new(&[]);
+    assert!(everything.admissible(0));
+    assert_eq!(everything.next_admissible(41), 42);
+}
+
+#[test]
+#[should_panic]
+fn test_wheel_filter_composite() {
+    WheelFilter::new(&[2, 9]);
+}
+
+#[test]
+fn test_wheel() {
+    let mut pset = Sieve::new();
+    let basis: Vec<u64> = pset.iter().take(7).collect();
+    for k in 0..=basis.len() {
+        let wheel = Wheel::for_basis(&basis[..k]);
+        let modulus: u64 = basis[..k].iter().product();
+        assert_eq!(wheel.modulus(), modulus);
+        assert_eq!(
+            wheel.spokes() as u64,
+            (0..modulus).filter(|&n| wheel.admissible(n)).count() as u64
+        );
+
+        let from = 1_000_000 - 7;
+        let candidates: Vec<u64> = wheel.candidates_from(from).take(500).collect();
+        let expected: Vec<u64> = (from..)
+            .filter(|&n| basis[..k].iter().all(|&p| n % p != 0))
+            .take(500)
+            .collect();
+        assert_eq!(candidates, expected, "{:?}", &basis[..k]);
+    }
+    assert!((Wheel::for_basis(&[2, 3, 5]).density() - 8.0 / 30.0).abs() < 1e-12);
+
+    // The last candidates below u64::MAX, and then nothing
+    let wheel = Wheel::for_basis(&[2, 3]);
+    let last: Vec<u64> = wheel.candidates_from(u64::MAX - 10).collect();
+    assert_eq!(
+        last,
+        vec![u64::MAX - 10, u64::MAX - 8, u64::MAX - 4, u64::MAX - 2]
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_wheel_too_large() {
+    Wheel::for_basis(&[2, 3, 5, 7, 11, 13, 17, 19, 23]);
+}
+
+#[test]
+fn test_shared_primes() {
+    let shared = SharedPrimes::new();
+    let mut sieve = Sieve::new();
+    let expected: Vec<u64> = sieve.iter().take(5_000).collect();
+
+    std::thread::scope(|s| {
+        for t in 0..4u64 {
+            let shared = &shared;
+            let expected = &expected;
+            s.spawn(move || {
+                let mut reader = shared.reader();
+                for n in (t..20_000).step_by(7) {
+                    assert_eq!(reader.is_prime(n), is_prime(n), "{}", n);
+                }
+                for (ix, &p) in expected.iter().enumerate().skip(t as usize).step_by(11) {
+                    assert_eq!(reader.get(ix), p);
+                    assert_eq!(reader.find(p), (ix, p));
+                }
+            });
+        }
+    });
+
+    let snapshot = shared.snapshot();
+    assert_eq!(&snapshot.as_slice()[..5_000], &expected[..]);
+    // Growth doubles each time, so a handful of publishes covers it all
+    assert!(shared.epoch() <= 16, "{}", shared.epoch());
+
+    // A reader sees snapshots published after it was created
+    let mut reader = shared.reader();
+    let before = reader.snapshot().last().unwrap();
+    shared.ensure(before * 3);
+    assert!(reader.snapshot().last().unwrap() >= before * 3);
+
+    // Checking numbers past the cache doesn't grow it
+    let epoch = shared.epoch();
+    assert!(reader.is_prime(LARGEST_U64_PRIME));
+    assert!(!reader.is_prime(u64::MAX));
+    assert_eq!(shared.epoch(), epoch);
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn test_divides_any() {
+    use num_bigint::BigUint;
+
+    let mut pset = Sieve::new();
+    for n in 1..3_000u64 {
+        let big = BigUint::from(n);
+        for &limit in &[1, 2, 10, 50, 3_000] {
+            let expected = primes::Factorization::of(n)
+                .pairs()
+                .first()
+                .map(|&(p, _)| p)
+                .filter(|&p| p <= limit);
+            assert_eq!(pset.divides_any(&big, limit), expected, "{} {}", n, limit);
+        }
+    }
+
+    // 100! has every prime up to 100, and nothing above
+    let factorial = (1..=100u32).fold(BigUint::from(1u32), |acc, k| acc * k);
+    assert_eq!(pset.divides_any(&factorial, 1_000), Some(2));
+    let odd = &factorial >> factorial.trailing_zeros().unwrap();
+    assert_eq!(pset.divides_any(&odd, 1_000), Some(3));
+    assert_eq!(pset.divides_any(&(odd + 2u32), 100), None);
+}
+
+#[test]
+fn test_sieve_jumps() {
+    // Far enough ahead that `find` and `get` switch to a segmented sieve
+    let mut sieve = Sieve::new();
+    assert_eq!(sieve.find(10_000_000), (664_579, 10_000_019));
+    assert_eq!(sieve.pending_composites(), sieve.len() - 3);
+    assert_eq!(sieve.get(664_580), 10_000_079);
+    assert_eq!(sieve.get(1_000_000), 15_485_867);
+
+    let mut td = TrialDivision::new();
+    assert_eq!(td.get(20_000), 224_743);
+    assert_eq!(&sieve.list()[..td.len()], td.list());
+
+    let mut sieve = Sieve::new();
+    sieve.expand_to(1_000_000);
+    assert_eq!(sieve.last_found(), Some(1_000_003));
+    // The incremental sieve carries on correctly from where the jump left off
+    let next: Vec<u64> = sieve.generator().take(3).collect();
+    assert_eq!(next, vec![1_000_033, 1_000_037, 1_000_039]);
+    assert_eq!(sieve.iter_to(2_000_000).len(), 148_933);
+
+    // Checking from just past each power of ten
+    for k in 1..7 {
+        let power = 10u64.pow(k);
+        let (ix, p) = Sieve::new().find(power);
+        assert_eq!(TrialDivision::new().get(ix), p);
+        assert_eq!(Sieve::new().get(ix), p);
+    }
+}
+
+#[test]
+fn test_expandable_limit() {
+    // A backend with a fixed table of primes, which panics if asked for more
+    struct Table(Vec<u64>);
+    impl PrimeSetBasics for Table {
+        fn expand(&mut self) {
+            panic!("the table has no more primes");
+        }
+        fn list(&self) -> &[u64] {
+            &self.0
+        }
+        fn expandable_limit(&self) -> u64 {
+            *self.0.last().unwrap()
+        }
+    }
+
+    let primes: Vec<u64> = TrialDivision::new().iter().take(1_000).collect();
+    let mut table = Table(primes.clone());
+    let limit = LimitExceeded { limit: 7_919 };
+    assert_eq!(table.try_find(7_919), Ok((999, 7_919)));
+    assert_eq!(table.try_find(7_920), Err(limit));
+    assert_eq!(table.try_get(999), Ok(7_919));
+    assert_eq!(table.try_get(1_000), Err(limit));
+    assert_eq!(table.try_get(1 << 40), Err(limit));
+    assert_eq!(table.iter().count(), 1_000);
+    assert_eq!(limit.to_string(), "no primes past 7919 are available");
+
+    let mut sieve = Sieve::new();
+    assert_eq!(sieve.expandable_limit(), LARGEST_U64_PRIME);
+    assert_eq!(sieve.try_get(999), Ok(7_919));
+    let beyond = Err(LimitExceeded {
+        limit: LARGEST_U64_PRIME,
+    });
+    assert_eq!(sieve.try_find(LARGEST_U64_PRIME + 1), beyond);
+
+    let mut pset = ExternalPrimes::from_slice(&primes[..10]);
+    assert_eq!(pset.expandable_limit(), 29);
+    assert_eq!(pset.iter().collect::<Vec<u64>>(), &primes[..10]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_expand_to_parallel() {
+    let mut expected = Sieve::new();
+    expected.expand_to(5_000_000);
+
+    for &limit in &[0, 7, 100, 1_000_000, 5_000_000] {
+        let mut sieve = Sieve::new();
+        sieve.expand_to_parallel(limit);
+        assert_eq!(sieve.list(), &expected.list()[..sieve.len()]);
+        assert!(sieve.last_found().unwrap() >= limit);
+        assert_eq!(sieve.pending_composites(), sieve.len() - 3);
+    }
+
+    // Starting partway, and carrying on incrementally afterwards
+    let mut sieve = Sieve::new();
+    sieve.get(10_000);
+    sieve.expand_to_parallel(3_000_000);
+    assert_eq!(sieve.get(348_512), expected.get(348_512));
+    assert_eq!(sieve.list(), &expected.list()[..sieve.len()]);
+}
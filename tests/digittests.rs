@@ -0,0 +1,78 @@
+use primes::digits::{
+    digit_sum, from_digits, is_left_truncatable_prime, is_right_truncatable_prime,
+    left_truncatable_primes, prime_digit_primes, primes_with_digit_sum, right_truncatable_primes,
+    to_digits,
+};
+use primes::{is_prime, PrimeSet, Sieve};
+
+#[test]
+fn test_digit_conversion() {
+    for &base in &[2, 3, 10, 16, 1 << 32] {
+        for &n in &[0, 1, 2, 1_000, 123_456_789, u64::MAX] {
+            assert_eq!(from_digits(&to_digits(n, base), base), Some(n));
+        }
+    }
+    assert_eq!(to_digits(0, 10), vec![0]);
+    assert_eq!(to_digits(255, 16), vec![15, 15]);
+    assert_eq!(from_digits(&[], 10), Some(0));
+    assert_eq!(from_digits(&[2; 20], 10), None);
+    assert_eq!(digit_sum(255, 16), 30);
+}
+
+#[test]
+fn test_truncatable_primes() {
+    let right = right_truncatable_primes();
+    let left = left_truncatable_primes();
+    let below: Vec<u64> = (0..100_000)
+        .filter(|&n| is_right_truncatable_prime(n))
+        .collect();
+    assert_eq!(
+        below,
+        right
+            .iter()
+            .copied()
+            .take_while(|&p| p < 100_000)
+            .collect::<Vec<_>>()
+    );
+    let below: Vec<u64> = (0..100_000)
+        .filter(|&n| is_left_truncatable_prime(n))
+        .collect();
+    assert_eq!(
+        below,
+        left.iter()
+            .copied()
+            .take_while(|&p| p < 100_000)
+            .collect::<Vec<_>>()
+    );
+
+    assert!(right.iter().all(|&p| is_right_truncatable_prime(p)));
+    assert!(left.iter().all(|&p| is_left_truncatable_prime(p)));
+    assert!(left.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn test_digit_primes() {
+    let mut sieve = Sieve::new();
+    let primes: Vec<u64> = sieve.iter().take_while(|&p| p < 1_000_000).collect();
+
+    let expected: Vec<u64> = primes
+        .iter()
+        .copied()
+        .filter(|&p| to_digits(p, 10).iter().all(|d| [2, 3, 5, 7].contains(d)))
+        .collect();
+    let built: Vec<u64> = prime_digit_primes()
+        .take_while(|&p| p < 1_000_000)
+        .collect();
+    assert_eq!(built, expected);
+    assert!(prime_digit_primes().skip(10_000).take(10).all(is_prime));
+
+    for sum in 0..50 {
+        let expected: Vec<u64> = primes
+            .iter()
+            .copied()
+            .filter(|&p| digit_sum(p, 10) == sum)
+            .collect();
+        assert_eq!(primes_with_digit_sum(sum, 999_999), expected, "sum {}", sum);
+    }
+    assert_eq!(primes_with_digit_sum(2, u64::MAX), vec![2, 11, 101]);
+}
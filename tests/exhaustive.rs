@@ -0,0 +1,68 @@
+//! Cross-checks of the primality tests, factorization, and sieves over whole ranges. The full
+//! `u32` runs are ignored by default; run them with
+//! `cargo test --release --test exhaustive -- --ignored`.
+
+use primes::{
+    factors, factors_with, is_prime_u64, spf_range, Bpsw, Primality, PrimalityTest, PrimeSet, Sieve,
+};
+
+// `Sieve` keeps every prime it finds, so it is only compared below this
+const SIEVE_LIMIT: u64 = 1 << 26;
+
+// Check everything in `[lo, hi]` against the segmented smallest-prime-factor sieve
+fn check_range(lo: u64, hi: u64) {
+    let mut sieve = Sieve::new();
+    let mut sieve_primes = sieve
+        .iter()
+        .take_while(|&p| p < SIEVE_LIMIT)
+        .skip_while(|&p| p < lo);
+    for (n, spf) in spf_range(lo, hi) {
+        let expected = spf == n;
+        assert_eq!(is_prime_u64(n), expected, "is_prime_u64({})", n);
+        assert_eq!(
+            Bpsw.test(n) == Primality::Prime,
+            expected,
+            "Bpsw.test({})",
+            n
+        );
+        if expected && n < SIEVE_LIMIT {
+            assert_eq!(sieve_primes.next(), Some(n), "Sieve at {}", n);
+        }
+
+        let fs = factors_with(n, &Bpsw);
+        assert_eq!(fs[0], spf, "factors_with({})", n);
+        assert!(fs.windows(2).all(|w| w[0] <= w[1]), "factors_with({})", n);
+        assert!(fs.iter().all(|&p| is_prime_u64(p)), "factors_with({})", n);
+        assert_eq!(fs.iter().product::<u64>(), n, "factors_with({})", n);
+    }
+}
+
+// `factors` is plain trial division, so it is compared with `factors_with`, which `check_range`
+// has checked, separately from the rest
+fn check_factors(lo: u64, hi: u64) {
+    for n in lo..=hi {
+        assert_eq!(factors(n), factors_with(n, &Bpsw), "factors({})", n);
+    }
+}
+
+#[test]
+fn test_small_ranges() {
+    check_range(0, 1 << 18);
+    check_range((1 << 32) - (1 << 16), (1 << 32) - 1);
+    check_factors(0, 1 << 18);
+    check_factors((1 << 32) - (1 << 12), (1 << 32) - 1);
+}
+
+#[test]
+#[ignore]
+fn test_every_u32() {
+    check_range(0, u32::MAX as u64);
+}
+
+// Trial division of every prime up to 2^32 takes hours even in release, so this is kept apart
+// from `test_every_u32`
+#[test]
+#[ignore]
+fn test_factors_every_u32() {
+    check_factors(0, u32::MAX as u64);
+}
@@ -0,0 +1,294 @@
+use primes::{
+    coprime_to, count_coprime_below, count_representations_as_product, divisor_pairs, factor_tree,
+    factors, gcd_all, lcm_all, multiplicative_partitions, ordered_factorizations,
+    phi_iterations_to_one, reduce_fraction, totient_chain, totient_range, FactoredRatio,
+    Factorization,
+};
+
+#[test]
+fn test_factorization() {
+    assert!(Factorization::of(1).is_one());
+    assert_eq!(Factorization::of(1), Factorization::one());
+    assert_eq!(Factorization::of(1).value(), Some(1));
+    assert_eq!(Factorization::of(1).divisors(), vec![1]);
+
+    for n in 1..2_000u64 {
+        let f = Factorization::of(n);
+        assert_eq!(f.value(), Some(n));
+        assert_eq!(f, Factorization::from_factors(&factors(n)));
+
+        let divisors: Vec<u64> = (1..=n).filter(|d| n % d == 0).collect();
+        assert_eq!(f.divisors(), divisors);
+        assert_eq!(f.num_divisors(), divisors.len() as u64);
+        assert_eq!(f.sum_divisors(), Some(divisors.iter().sum()));
+    }
+
+    let big = Factorization::from_factors(&[3, 2, 3, 2, 2]);
+    assert_eq!(big.pairs(), &[(2, 3), (3, 2)]);
+    assert_eq!(Factorization::from_factors(&[2; 64]).value(), None);
+    assert_eq!(Factorization::from_factors(&[2; 63]).value(), Some(1 << 63));
+    assert_eq!(
+        Factorization::from_factors(&[2; 63]).sum_divisors(),
+        Some(u64::MAX)
+    );
+    assert_eq!(Factorization::from_factors(&[3; 63]).sum_divisors(), None);
+}
+
+#[test]
+fn test_factorization_arithmetic() {
+    fn gcd(a: u64, b: u64) -> u64 {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+
+    for a in 1..120u64 {
+        let fa = Factorization::of(a);
+        for b in 1..120u64 {
+            let fb = Factorization::of(b);
+            assert_eq!((&fa * &fb).value(), Some(a * b));
+            assert_eq!(fa.clone() * fb.clone(), Factorization::of(a * b));
+            assert_eq!(fa.gcd(&fb).value(), Some(gcd(a, b)));
+            assert_eq!(fa.lcm(&fb).value(), Some(a / gcd(a, b) * b));
+            assert_eq!(fb.divides(&fa), a % b == 0);
+            let quotient = fa.checked_div(&fb).map(|q| q.value().unwrap());
+            assert_eq!(quotient, if a % b == 0 { Some(a / b) } else { None });
+        }
+        assert_eq!(fa.pow(3).value(), Some(a * a * a));
+        assert!(fa.pow(0).is_one());
+    }
+
+    let mut f = Factorization::one();
+    for _ in 0..100 {
+        f *= &Factorization::of(6);
+    }
+    assert_eq!(f.pairs(), &[(2, 100), (3, 100)]);
+    assert_eq!(f.value(), None);
+    assert_eq!(
+        f.checked_div(&Factorization::of(6).pow(99))
+            .unwrap()
+            .value(),
+        Some(6)
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_factorization_pow_overflow() {
+    assert_eq!(Factorization::of(2).pow(u32::MAX).pairs(), &[(2, u32::MAX)]);
+    Factorization::of(4).pow(u32::MAX / 2 + 1);
+}
+
+#[test]
+fn test_fractions() {
+    assert_eq!(reduce_fraction(0, 5), (0, 1));
+    assert_eq!(reduce_fraction(5, 1), (5, 1));
+    assert_eq!(reduce_fraction(u64::MAX, u64::MAX), (1, 1));
+    for num in 1..60u64 {
+        for den in 1..60u64 {
+            let (n, d) = reduce_fraction(num, den);
+            assert_eq!(n * den, d * num);
+            assert_eq!(FactoredRatio::new(num, den).value(), Some((n, d)));
+        }
+    }
+
+    // C(100, 50) / 2^100, far too big to evaluate directly
+    let mut ratio = FactoredRatio::new(1, 1);
+    for k in 1..=50 {
+        ratio = ratio * FactoredRatio::new(50 + k, k);
+    }
+    ratio = ratio / FactoredRatio::new(2, 1).pow(100);
+    assert_eq!(ratio.value(), None);
+    assert!((ratio.to_f64() - 0.0795892373871787).abs() < 1e-12);
+    assert!((ratio.recip().to_f64() * ratio.to_f64() - 1.0).abs() < 1e-12);
+    assert!(ratio.numerator().gcd(ratio.denominator()).is_one());
+}
+
+#[test]
+fn test_lcm_gcd_all() {
+    assert_eq!(lcm_all(Vec::new()), Some(1));
+    assert_eq!(gcd_all(Vec::new()), 0);
+    assert_eq!(lcm_all(vec![0, 5]), Some(0));
+    assert_eq!(gcd_all(vec![0, 5]), 5);
+    assert_eq!(gcd_all(vec![7]), 7);
+
+    let mut lcm: u128 = 1;
+    for n in 1..=88u64 {
+        let g = {
+            let (mut a, mut b) = (lcm, n as u128);
+            while b != 0 {
+                let r = a % b;
+                a = b;
+                b = r;
+            }
+            a
+        };
+        lcm = lcm / g * n as u128;
+        assert_eq!(lcm_all(1..=n), Some(lcm), "lcm(1..={})", n);
+    }
+    assert_eq!(lcm_all(1..=89), None);
+
+    assert_eq!(
+        lcm_all(vec![u64::MAX, u64::MAX - 1]),
+        Some(u64::MAX as u128 * (u64::MAX - 1) as u128)
+    );
+    assert_eq!(gcd_all(vec![1 << 40, 1 << 50, 3 << 45]), 1 << 40);
+}
+
+#[test]
+fn test_coprime() {
+    let gcd = |mut a: u64, mut b: u64| {
+        while b != 0 {
+            let r = a % b;
+            a = b;
+            b = r;
+        }
+        a
+    };
+    for m in (1..200).chain(vec![30_030, 510_510, 1 << 40, u64::MAX]) {
+        for n in (0..300).chain(vec![10_000]) {
+            let expected = (1..n).filter(|&k| gcd(k, m) == 1).count() as u64;
+            assert_eq!(count_coprime_below(n, m), expected, "n = {}, m = {}", n, m);
+        }
+        let filtered: Vec<u64> = coprime_to(m, 0..500).collect();
+        let expected: Vec<u64> = (0..500).filter(|&k| gcd(k, m) == 1).collect();
+        assert_eq!(filtered, expected, "m = {}", m);
+    }
+    // 2^64 - 1 = 3 * 5 * 17 * 257 * 641 * 65537 * 6700417
+    assert_eq!(
+        count_coprime_below(u64::MAX, u64::MAX),
+        9_208_981_628_670_443_520
+    );
+}
+
+#[test]
+fn test_factor_tree() {
+    for n in 0..5_000 {
+        let tree = factor_tree(n);
+        assert_eq!(tree.value(), n);
+        if n > 1 {
+            assert_eq!(tree.leaves(), factors(n), "{}", n);
+        } else {
+            assert_eq!(tree.leaves(), vec![n]);
+        }
+        if let Some((left, right)) = tree.children() {
+            assert_eq!(left.value() * right.value(), n);
+        }
+        // One line per node: one for each prime factor, one for each split
+        let lines = tree.to_string().lines().count();
+        assert_eq!(lines, (2 * tree.leaves().len()).max(2) - 1, "{}", n);
+    }
+    assert_eq!(factor_tree(7).to_string(), "7\n");
+}
+
+#[test]
+fn test_divisor_pairs() {
+    assert_eq!(divisor_pairs(1).collect::<Vec<_>>(), vec![(1, 1)]);
+    assert_eq!(divisor_pairs(13).collect::<Vec<_>>(), vec![(1, 13)]);
+    for n in 1..2_000u64 {
+        let pairs: Vec<(u64, u64)> = divisor_pairs(n).collect();
+        let expected: Vec<(u64, u64)> = (1..=n)
+            .filter(|d| d * d <= n && n % d == 0)
+            .map(|d| (d, n / d))
+            .collect();
+        assert_eq!(pairs, expected, "{}", n);
+    }
+}
+
+#[test]
+fn test_count_representations_as_product() {
+    // Count ordered k-tuples by choosing the first factor, then recursing
+    fn brute(n: u64, k: u64) -> u64 {
+        if k == 0 {
+            return (n == 1) as u64;
+        }
+        let divisors = Factorization::of(n).divisors();
+        divisors.iter().map(|&d| brute(n / d, k - 1)).sum()
+    }
+    for n in 1..200u64 {
+        for k in 0..5 {
+            assert_eq!(count_representations_as_product(n, k), Some(brute(n, k)));
+        }
+        let d = Factorization::of(n).num_divisors();
+        assert_eq!(count_representations_as_product(n, 2), Some(d));
+    }
+
+    // 2^63 into 2^40 parts: binomial(2^40 + 62, 63) is far too large
+    assert_eq!(count_representations_as_product(1 << 63, 1 << 40), None);
+    assert_eq!(count_representations_as_product(1 << 63, 2), Some(64));
+    assert_eq!(
+        count_representations_as_product(7, u64::MAX),
+        Some(u64::MAX)
+    );
+}
+
+#[test]
+fn test_factorization_counts() {
+    // Brute force: choose the first factor, then recurse on the cofactor, with factors either in
+    // any order or bounded by the previous one
+    fn ordered(n: u64) -> u64 {
+        let divisors = Factorization::of(n).divisors();
+        1.max(divisors[1..].iter().map(|&d| ordered(n / d)).sum())
+    }
+    fn unordered(n: u64, bound: u64) -> u64 {
+        if n == 1 {
+            return 1;
+        }
+        let divisors = Factorization::of(n).divisors();
+        let within = divisors[1..].iter().filter(|&&d| d <= bound);
+        within.map(|&d| unordered(n / d, d)).sum()
+    }
+    for n in 1..500u64 {
+        assert_eq!(ordered_factorizations(n), Some(ordered(n)), "{}", n);
+        assert_eq!(multiplicative_partitions(n), unordered(n, n), "{}", n);
+    }
+
+    // OEIS A074206 and A001055
+    assert_eq!(ordered_factorizations(720), Some(1_888));
+    assert_eq!(multiplicative_partitions(720), 98);
+    assert_eq!(multiplicative_partitions(2 * 3 * 5 * 7 * 11 * 13), 203);
+    // The product of the first 15 primes splits like a 15-element set: Bell(15)
+    assert_eq!(
+        multiplicative_partitions(614_889_782_588_491_410),
+        1_382_958_545
+    );
+
+    // 2^20·3^8·5^4 has H(n) around 1.0e18, and 2^25·3^10·5^5 around 7.0e22
+    let n = (1 << 20) * 6_561 * 625;
+    assert_eq!(ordered_factorizations(n), Some(1_004_839_029_391_228_928));
+    assert_eq!(ordered_factorizations((1 << 25) * 59_049 * 3_125), None);
+}
+
+#[test]
+fn test_totient_chain() {
+    let phi: Vec<u64> = totient_range(1, 5_000).map(|(_, phi)| phi).collect();
+    for n in 1..5_000u64 {
+        let f = Factorization::of(n);
+        assert_eq!(f.totient().value(), Some(phi[n as usize - 1]), "{}", n);
+
+        let chain: Vec<u64> = totient_chain(n).collect();
+        assert_eq!(chain[0], n);
+        assert_eq!(*chain.last().unwrap(), 1);
+        for w in chain.windows(2) {
+            assert_eq!(w[1], phi[w[0] as usize - 1]);
+        }
+        assert_eq!(phi_iterations_to_one(n) as usize, chain.len() - 1);
+    }
+
+    // φ(2^k) = 2^(k-1), and φ(3^k) = 2·3^(k-1)
+    assert_eq!(phi_iterations_to_one(1 << 63), 63);
+    assert_eq!(phi_iterations_to_one(3u64.pow(40)), 41);
+    // 10^18 + 1 = 101 · 9901 · 999999000001
+    let chain: Vec<u64> = totient_chain(1_000_000_000_000_000_001).take(3).collect();
+    assert_eq!(
+        chain,
+        vec![
+            1_000_000_000_000_000_001,
+            989_999_010_000_000_000,
+            184_757_760_000_000_000
+        ]
+    );
+    assert_eq!(phi_iterations_to_one(1_000_000_000_000_000_001), 51);
+}
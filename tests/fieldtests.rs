@@ -0,0 +1,129 @@
+use primes::{
+    find_ntt_prime, is_prime, sqrt_mod_prime, Prime, PrimeField, PrimeSet, PrimeSetBasics,
+    TrialDivision,
+};
+
+#[test]
+fn test_prime_field_small() {
+    for prime in TrialDivision::new()
+        .iter()
+        .typed()
+        .take_while(|p| p.get() < 300)
+    {
+        let p = prime.get();
+        let field = PrimeField::new(prime);
+        assert_eq!(field.modulus(), p);
+
+        let nonzero: Vec<u64> = (1..p).collect();
+        let inverses = field.batch_inv(&nonzero).unwrap();
+        for (&a, &inv) in nonzero.iter().zip(inverses.iter()) {
+            assert_eq!(field.inv(a), Some(inv));
+            assert_eq!(field.mul(a, inv), 1);
+        }
+
+        let squares: Vec<u64> = (1..p).map(|a| a * a % p).collect();
+        for a in 0..p {
+            let is_square = a == 0 || squares.contains(&a);
+            match field.sqrt(a) {
+                Some(r) => {
+                    assert!(is_square);
+                    assert_eq!(r * r % p, a);
+                    assert!(r <= p - r || r == 0);
+                }
+                None => assert!(!is_square),
+            }
+            let expected = if a == 0 {
+                0
+            } else if is_square {
+                1
+            } else {
+                -1
+            };
+            assert_eq!(field.legendre(a), expected, "({} / {})", a, p);
+        }
+
+        let g = field.primitive_root();
+        let order = (1..p).find(|&k| field.pow(g, k) == 1).unwrap();
+        assert_eq!(order, p - 1, "{} mod {}", g, p);
+    }
+}
+
+#[test]
+fn test_prime_field_large() {
+    assert!(Prime::try_new(0).is_none());
+    assert!(Prime::try_new(1).is_none());
+    assert!(Prime::try_new(u64::MAX).is_none());
+    for n in 0..1_000 {
+        assert_eq!(
+            Prime::try_new(n).map(u64::from),
+            Some(n).filter(|&n| is_prime(n))
+        );
+    }
+
+    let p = 18_446_744_073_709_551_557;
+    let field = PrimeField::from(Prime::try_new(p).unwrap());
+    assert_eq!(field.add(p - 1, 2), 1);
+    assert_eq!(field.mul(field.inv(123_456_789).unwrap(), 123_456_789), 1);
+
+    // p = 1 mod 8, which takes the full Tonelli-Shanks path
+    let p = 998_244_353;
+    let prime = Prime::try_new(p).unwrap();
+    for &a in [2u64, 3, 10, 12_345, 998_244_352].iter() {
+        if let Some(r) = sqrt_mod_prime(a, prime) {
+            assert_eq!(r * r % p, a);
+        }
+    }
+    assert_eq!(PrimeField::new(prime).primitive_root(), 3);
+}
+
+#[test]
+fn test_find_ntt_prime() {
+    for order in 0..20 {
+        for &min in &[0, 1, 2, 1_000, 1 << 30, 1 << 40] {
+            let (p, root) = find_ntt_prime(min, order).unwrap();
+            assert!(p >= min && is_prime(p), "{} {}", min, order);
+            assert_eq!((p - 1) % (1 << order), 0);
+            // Nothing smaller in the progression is prime
+            let first = (min.max(2) - 1 + (1 << order) - 1) >> order;
+            for k in first.max(1)..(p - 1) >> order {
+                assert!(!is_prime((k << order) + 1));
+            }
+            let field = PrimeField::new(Prime::try_new(p).unwrap());
+            assert_eq!(field.pow(root, 1 << order), 1);
+            if order > 0 {
+                assert_eq!(field.pow(root, 1 << (order - 1)), p - 1);
+            }
+        }
+    }
+    assert_eq!(find_ntt_prime(0, 23).unwrap().0, 20 * (1 << 23) + 1);
+    assert_eq!(find_ntt_prime(0, 57).unwrap().0, 29 * (1 << 57) + 1);
+    assert_eq!(find_ntt_prime(u64::MAX - 5, 0), None);
+    assert_eq!(find_ntt_prime(u64::MAX, 62), None);
+    assert_eq!(find_ntt_prime(0, 64), None);
+}
+
+// A set that lists whatever it is given, and can't find any more
+struct Listed(Vec<u64>);
+
+impl PrimeSetBasics for Listed {
+    fn expand(&mut self) {
+        panic!("no more primes");
+    }
+    fn list(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+#[test]
+fn test_typed_unverified() {
+    let mut pset = Listed(vec![2, 3, 5, 7, 11]);
+    let typed: Vec<u64> = pset.iter().typed().take(5).map(Prime::get).collect();
+    assert_eq!(typed, vec![2, 3, 5, 7, 11]);
+}
+
+#[test]
+#[should_panic]
+fn test_typed_unverified_composite() {
+    let mut pset = Listed(vec![2, 3, 5, 7, 9]);
+    let _: Vec<Prime> = pset.iter().typed().take(5).collect();
+}
@@ -0,0 +1,158 @@
+//! Known-answer tests against fixture data from the OEIS, in `tests/fixtures`.
+
+use primes::{
+    for_each_prime_in, gap_after, is_prime, is_prime_u64, max_gap_below, maximal_gaps,
+    maximal_gaps_below, next_prime, prev_prime, prime_count_in_range, prime_pi, prime_pi_sublinear,
+    read_primes, spf_range, totient_range, totient_summatory, write_primes, Bpsw, Primality,
+    PrimalityTest, PrimeFormat, PrimeSet, Sieve, TrialDivision,
+};
+
+// The non-comment lines of a fixture, split on whitespace and parsed
+fn fixture(text: &str) -> Vec<Vec<u64>> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| {
+            line.split_whitespace()
+                .map(|v| v.parse().unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+fn first_primes() -> Vec<u64> {
+    fixture(include_str!("fixtures/primes_100000.txt"))
+        .into_iter()
+        .map(|row| row[0])
+        .collect()
+}
+
+#[test]
+fn test_first_primes() {
+    let primes = first_primes();
+    assert_eq!(primes.len(), 100_000);
+    let last = *primes.last().unwrap();
+
+    let mut sieve = Sieve::new();
+    assert_eq!(sieve.iter().take(primes.len()).collect::<Vec<_>>(), primes);
+    let mut td = TrialDivision::new();
+    assert_eq!(td.iter().take(primes.len()).collect::<Vec<_>>(), primes);
+
+    let mut segmented = Vec::new();
+    for_each_prime_in(0, last, |p| segmented.push(p));
+    assert_eq!(segmented, primes);
+    let spf_primes: Vec<u64> = spf_range(0, last)
+        .filter(|&(n, spf)| n == spf)
+        .map(|(n, _)| n)
+        .collect();
+    assert_eq!(spf_primes, primes);
+
+    let mut out = Vec::new();
+    write_primes(&mut out, last, PrimeFormat::BinaryLe).unwrap();
+    assert_eq!(
+        read_primes(&out[..], PrimeFormat::BinaryLe, false).unwrap(),
+        primes
+    );
+
+    let mut ix = 0;
+    for n in 0..=last {
+        let expected = primes[ix] == n;
+        assert_eq!(is_prime(n), expected, "is_prime({})", n);
+        assert_eq!(is_prime_u64(n), expected, "is_prime_u64({})", n);
+        assert_eq!(Bpsw.test(n) == Primality::Prime, expected, "Bpsw({})", n);
+        assert_eq!(sieve.is_prime(n), expected, "Sieve::is_prime({})", n);
+        if expected {
+            ix += 1;
+        }
+    }
+
+    for (i, w) in primes.windows(2).enumerate() {
+        assert_eq!(next_prime(w[0]), Some(w[1]));
+        assert_eq!(next_prime(w[1] - 1), Some(w[1]));
+        assert_eq!(prev_prime(w[1]), Some(w[0]));
+        assert_eq!(prev_prime(w[0] + 1), Some(w[0]));
+        assert_eq!(gap_after(w[0]), Some(w[1] - w[0]));
+        assert_eq!(sieve.find(w[0] + 1), (i + 1, w[1]));
+    }
+}
+
+#[test]
+fn test_prime_pi() {
+    for row in fixture(include_str!("fixtures/prime_pi.txt")) {
+        let (x, pi) = (row[0], row[1]);
+        if x <= 10_000_000 {
+            assert_eq!(prime_pi(x), pi, "prime_pi({})", x);
+        }
+        if x <= 10_000_000_000 {
+            assert_eq!(prime_pi_sublinear(x), pi, "prime_pi_sublinear({})", x);
+            assert_eq!(
+                prime_count_in_range(0, x),
+                pi,
+                "prime_count_in_range({})",
+                x
+            );
+        }
+    }
+}
+
+#[test]
+fn test_maximal_gaps() {
+    let records = fixture(include_str!("fixtures/maximal_gaps.txt"));
+
+    // The records within the first primes are exactly the ones found by scanning them
+    let primes = first_primes();
+    let mut best = 0;
+    let mut found = Vec::new();
+    for w in primes.windows(2) {
+        if w[1] - w[0] > best {
+            best = w[1] - w[0];
+            found.push(vec![best, w[0]]);
+        }
+    }
+    assert_eq!(found, records[..found.len()].to_vec());
+
+    let small: Vec<Vec<u64>> = records
+        .iter()
+        .filter(|row| row[0] + row[1] <= 2_000_000)
+        .cloned()
+        .collect();
+    let from_iter: Vec<Vec<u64>> = maximal_gaps()
+        .take(small.len())
+        .map(|(gap, p)| vec![gap, p])
+        .collect();
+    assert_eq!(from_iter, small);
+    let below: Vec<Vec<u64>> = maximal_gaps_below(2_000_000)
+        .map(|(gap, p)| vec![gap, p])
+        .collect();
+    assert_eq!(below, small);
+
+    for row in records {
+        let (gap, p) = (row[0], row[1]);
+        assert_eq!(gap_after(p), Some(gap), "gap_after({})", p);
+        if p + gap <= 2_000_000 {
+            assert_eq!(
+                max_gap_below(p + gap),
+                Some((gap, p)),
+                "ending at {}",
+                p + gap
+            );
+        }
+    }
+}
+
+#[test]
+fn test_totients() {
+    let totients: Vec<u64> = fixture(include_str!("fixtures/totients_10000.txt"))
+        .into_iter()
+        .map(|row| row[0])
+        .collect();
+    let from_range: Vec<u64> = totient_range(1, totients.len() as u64)
+        .map(|(_, phi)| phi)
+        .collect();
+    assert_eq!(from_range, totients);
+
+    let mut sum = 0;
+    for (n, &phi) in (1..).zip(totients.iter()) {
+        sum += phi as u128;
+        assert_eq!(totient_summatory(n), sum, "totient_summatory({})", n);
+    }
+}
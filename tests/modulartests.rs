@@ -0,0 +1,106 @@
+use primes::{factorial_mod, mul_mod, pow_mod, wilson_quotient, ModInt};
+
+#[test]
+fn test_mul_mod() {
+    let ms = [
+        1u64,
+        2,
+        7,
+        1 << 32,
+        (1 << 32) + 15,
+        1 << 63,
+        u64::MAX - 58,
+        u64::MAX,
+    ];
+    let xs = [
+        0u64,
+        1,
+        2,
+        12345,
+        1 << 32,
+        (1 << 63) + 1,
+        u64::MAX - 1,
+        u64::MAX,
+    ];
+    for &m in ms.iter() {
+        for &a in xs.iter() {
+            for &b in xs.iter() {
+                let expected = ((a as u128 * b as u128) % m as u128) as u64;
+                assert_eq!(mul_mod(a, b, m), expected, "{} * {} % {}", a, b, m);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_pow_mod() {
+    assert_eq!(pow_mod(2, 0, 7), 1);
+    assert_eq!(pow_mod(2, 64, 1000), 616);
+    assert_eq!(pow_mod(3, 200, 1_000_000_007), 136_318_165);
+    // Fermat's little theorem for a large prime modulus
+    let p = 18_446_744_073_709_551_557;
+    assert_eq!(pow_mod(123_456_789, p - 1, p), 1);
+}
+
+#[test]
+fn test_mod_int() {
+    type Prime = ModInt<1_000_000_007>;
+    type Composite = ModInt<1_000_000_008>;
+    type Big = ModInt<{ u64::MAX }>;
+
+    assert_eq!(Prime::MODULUS, 1_000_000_007);
+    assert_eq!(Prime::new(1_000_000_008).value(), 1);
+    assert_eq!(Prime::from(5), Prime::new(5));
+    assert_eq!((-Prime::new(1)).value(), 1_000_000_006);
+    assert_eq!(format!("{}", Prime::new(42)), "42");
+
+    for n in 1..1_000u64 {
+        let a = Prime::new(n * 7919);
+        assert_eq!(a * a.inv().unwrap(), Prime::new(1));
+        assert_eq!(a / a, Prime::new(1));
+
+        let c = Composite::new(n);
+        match c.inv() {
+            Some(inv) => assert_eq!(c * inv, Composite::new(1)),
+            None => assert!([2, 3, 7, 109, 167].iter().any(|p| n % p == 0)),
+        }
+    }
+    assert_eq!(Prime::new(0).inv(), None);
+
+    let mut x = Big::new(u64::MAX - 1);
+    x += Big::new(5);
+    assert_eq!(x.value(), 4);
+    x -= Big::new(5);
+    assert_eq!(x.value(), u64::MAX - 1);
+    x *= x;
+    assert_eq!(x.value(), 1);
+    x /= Big::new(2);
+    assert_eq!(x * Big::new(2), Big::new(1));
+}
+
+#[test]
+fn test_factorial_mod() {
+    for &p in &[2u64, 3, 5, 7, 101, 1_009] {
+        let mut factorial = 1;
+        for n in 0..p + 3 {
+            if n > 0 {
+                factorial = factorial * n % p;
+            }
+            assert_eq!(factorial_mod(n, p), factorial, "{}! mod {}", n, p);
+        }
+    }
+    let p = 1_000_000_007;
+    assert_eq!(factorial_mod(p - 2, p), 1);
+    assert_eq!(factorial_mod(p - 3, p), (p - 1) / 2);
+    assert_eq!(factorial_mod(20, p), 146_326_063);
+}
+
+#[test]
+fn test_wilson_quotient() {
+    let wilson: Vec<u64> = (2..1_000)
+        .filter(|&p| primes::is_prime(p) && wilson_quotient(p) == 0)
+        .collect();
+    assert_eq!(wilson, vec![5, 13, 563]);
+    assert_eq!(wilson_quotient(2), 1);
+    assert_eq!(wilson_quotient(11), 1);
+}
@@ -0,0 +1,298 @@
+use primes::{
+    factors, factors_with, has_small_factor, is_prime, is_prime_bpsw, is_prime_const, is_prime_u64,
+    miller_rabin, next_prime, next_prime_capacity, next_prime_with, prev_prime, Bpsw, Primality,
+    PrimalityTest, PrimeSet, Sieve, StagedMillerRabin, TrialDivisionTest, WitnessSet,
+    LARGEST_U64_PRIME, PRIME_CAPACITIES,
+};
+
+#[test]
+fn test_witness_sets_small() {
+    for n in 0..100_000 {
+        let expected = is_prime(n);
+        for &set in WitnessSet::ALL.iter() {
+            assert_eq!(miller_rabin(n, set), expected, "{} with {:?}", n, set);
+        }
+        assert_eq!(is_prime_u64(n), expected, "{}", n);
+    }
+}
+
+#[test]
+fn test_witness_sets_limits() {
+    assert_eq!(WitnessSet::smallest_for(1000), WitnessSet::FirstFourPrimes);
+    assert_eq!(
+        WitnessSet::smallest_for(u32::MAX as u64),
+        WitnessSet::FirstSevenPrimes
+    );
+    assert_eq!(
+        WitnessSet::smallest_for(3_215_031_751),
+        WitnessSet::FirstSevenPrimes
+    );
+    assert_eq!(WitnessSet::smallest_for(u64::MAX), WitnessSet::Sinclair);
+    assert!(WitnessSet::Sinclair.covers(u64::MAX));
+
+    // Strong pseudoprimes at the boundaries of each set
+    assert!(miller_rabin(
+        341_550_071_728_321,
+        WitnessSet::FirstSevenPrimes
+    ));
+    assert!(!miller_rabin(
+        341_550_071_728_321,
+        WitnessSet::FirstNinePrimes
+    ));
+    assert!(miller_rabin(
+        3_825_123_056_546_413_051,
+        WitnessSet::FirstNinePrimes
+    ));
+    assert!(!miller_rabin(
+        3_825_123_056_546_413_051,
+        WitnessSet::Sinclair
+    ));
+    assert!(!is_prime_u64(3_825_123_056_546_413_051));
+}
+
+#[test]
+fn test_is_prime_u64_large() {
+    let primes = [
+        4_294_967_291u64,
+        1_000_000_000_000_000_003,
+        9_223_372_036_854_775_783,
+        18_446_744_073_709_551_557,
+    ];
+    for &p in primes.iter() {
+        assert!(is_prime_u64(p), "{}", p);
+    }
+    let composites = [
+        4_294_967_297u64, // 641 * 6700417
+        4_294_967_291 * 4_294_967_279,
+        1_000_000_007 * 998_244_353,
+        u64::MAX,
+    ];
+    for &n in composites.iter() {
+        assert!(!is_prime_u64(n), "{}", n);
+    }
+}
+
+#[test]
+fn test_next_prev_prime() {
+    let primes: Vec<u64> = (0..10_000).filter(|&n| is_prime(n)).collect();
+    for n in 0..9_000u64 {
+        let next = primes.iter().copied().find(|&p| p > n);
+        let prev = primes.iter().copied().rev().find(|&p| p < n);
+        assert_eq!(next_prime(n), next, "next_prime({})", n);
+        assert_eq!(prev_prime(n), prev, "prev_prime({})", n);
+    }
+
+    assert_eq!(next_prime(LARGEST_U64_PRIME - 1), Some(LARGEST_U64_PRIME));
+    assert_eq!(next_prime(LARGEST_U64_PRIME), None);
+    assert_eq!(next_prime(u64::MAX), None);
+    assert_eq!(prev_prime(LARGEST_U64_PRIME + 1), Some(LARGEST_U64_PRIME));
+    assert_eq!(
+        prev_prime(LARGEST_U64_PRIME),
+        Some(18_446_744_073_709_551_533)
+    );
+    assert_eq!(next_prime(4_294_967_291), Some(4_294_967_311));
+}
+
+#[test]
+fn test_next_prime_capacity() {
+    for pair in PRIME_CAPACITIES.windows(2) {
+        assert!(is_prime(pair[0]));
+        assert!(
+            pair[1] > pair[0] * 3 / 2 && pair[1] < pair[0] * 5 / 2,
+            "{:?}",
+            pair
+        );
+    }
+    for (i, &c) in PRIME_CAPACITIES.iter().enumerate() {
+        assert_eq!(next_prime_capacity(c), Some(c));
+        let expected = PRIME_CAPACITIES.get(i + 1).copied().or(next_prime(c));
+        assert_eq!(next_prime_capacity(c + 1), expected);
+    }
+    assert_eq!(next_prime_capacity(4_294_967_291), Some(4_294_967_291));
+    assert_eq!(
+        next_prime_capacity(LARGEST_U64_PRIME),
+        Some(LARGEST_U64_PRIME)
+    );
+    assert_eq!(next_prime_capacity(LARGEST_U64_PRIME + 1), None);
+}
+
+#[test]
+fn test_is_prime_const() {
+    const BIG: bool = is_prime_const(4_294_967_291);
+    const _: () = assert!(BIG);
+    const _: () = assert!(!is_prime_const(4_294_967_297));
+    for n in 0..100_000 {
+        assert_eq!(is_prime_const(n), is_prime(n), "{}", n);
+    }
+    assert!(is_prime_const(1_000_000_007));
+    assert!(!is_prime_const(1_000_000_007 * 3));
+}
+
+#[test]
+fn test_primality_tests() {
+    for n in 0..100_000 {
+        let expected = if is_prime(n) {
+            Primality::Prime
+        } else {
+            Primality::Composite
+        };
+        assert_eq!(TrialDivisionTest.test(n), expected, "{}", n);
+        assert_eq!(Bpsw.test(n), expected, "{}", n);
+        assert_eq!(StagedMillerRabin.test(n), expected, "{}", n);
+        assert_eq!(WitnessSet::FirstFourPrimes.test(n), expected, "{}", n);
+    }
+
+    // Strong pseudoprimes to base 2, Lucas pseudoprimes, Carmichael numbers, and strong
+    // pseudoprimes to each witness set's bases
+    for &n in &[
+        2_047,
+        3_277,
+        4_033,
+        5_459,
+        5_777,
+        10_877,
+        561,
+        1_105,
+        3_215_031_751,
+        341_550_071_728_321,
+        3_825_123_056_546_413_051,
+    ] {
+        assert_eq!(Bpsw.test(n), Primality::Composite, "{}", n);
+        assert!(!is_prime_bpsw(n), "{}", n);
+        assert_eq!(StagedMillerRabin.test(n), Primality::Composite, "{}", n);
+    }
+    assert_eq!(
+        WitnessSet::FirstSevenPrimes.test(341_550_071_728_321),
+        Primality::Probable
+    );
+    assert_eq!(
+        WitnessSet::Sinclair.test(341_550_071_728_321),
+        Primality::Composite
+    );
+
+    for &start in &[1 << 32, 1 << 50, u64::MAX - 100_000] {
+        for n in start..start + 100_000 {
+            let expected = is_prime_u64(n);
+            assert_eq!(Bpsw.test(n) == Primality::Prime, expected, "{}", n);
+            assert_eq!(
+                StagedMillerRabin.test(n) == Primality::Prime,
+                expected,
+                "{}",
+                n
+            );
+        }
+    }
+    assert_eq!(Bpsw.test(LARGEST_U64_PRIME), Primality::Prime);
+    assert_eq!(StagedMillerRabin.test(LARGEST_U64_PRIME), Primality::Prime);
+    // Straddling the end of the screen
+    for n in (131 * 131 - 2_000)..(131 * 131 + 2_000) {
+        assert_eq!(
+            StagedMillerRabin.test(n) == Primality::Prime,
+            is_prime(n),
+            "{}",
+            n
+        );
+    }
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn test_is_prime_bpsw_big() {
+    use num_bigint::BigUint;
+    use primes::is_prime_bpsw_big;
+
+    for n in (0..10_000).chain(LARGEST_U64_PRIME - 1_000..=LARGEST_U64_PRIME) {
+        assert_eq!(is_prime_bpsw_big(&BigUint::from(n)), is_prime(n), "{}", n);
+    }
+
+    // The primes just past 2^64
+    let base = BigUint::from(1u32) << 64;
+    let found: Vec<u32> = (0..200)
+        .filter(|&k| is_prime_bpsw_big(&(&base + k)))
+        .collect();
+    assert_eq!(found, vec![13, 37, 51, 81, 93, 141]);
+
+    let mersenne = |e: u32| (BigUint::from(1u32) << e) - 1u32;
+    assert!(is_prime_bpsw_big(&mersenne(89)));
+    assert!(is_prime_bpsw_big(&mersenne(521)));
+    assert!(!is_prime_bpsw_big(&mersenne(67)));
+    assert!(!is_prime_bpsw_big(&(mersenne(61) * mersenne(89))));
+    // A square, and a strong pseudoprime to base 2 (6005917 * 12011833 * 18017749)
+    assert!(!is_prime_bpsw_big(&(mersenne(89) * mersenne(89))));
+    let pseudoprime: BigUint = "1299837745921707516889".parse().unwrap();
+    assert!(!is_prime_bpsw_big(&pseudoprime));
+}
+
+#[test]
+fn test_with_primality_test() {
+    for n in (0..10_000).chain(vec![1 << 40, 600_851_475_143, u64::MAX]) {
+        assert_eq!(factors_with(n, &Bpsw), factors(n), "{}", n);
+        assert_eq!(next_prime_with(n, &Bpsw), next_prime(n), "{}", n);
+    }
+    assert_eq!(
+        factors_with(LARGEST_U64_PRIME, &Bpsw),
+        vec![LARGEST_U64_PRIME]
+    );
+    assert_eq!(next_prime_with(LARGEST_U64_PRIME, &Bpsw), None);
+
+    let mut pset = Sieve::new();
+    for n in (0..10_000).chain(vec![1 << 40, 600_851_475_143, u64::MAX]) {
+        assert_eq!(pset.prime_factors_with(n, &Bpsw), factors(n), "{}", n);
+        let first = next_prime(n.saturating_sub(1));
+        assert_eq!(pset.find_with(n, &Bpsw), first, "{}", n);
+        assert_eq!(Sieve::new().find_with(n, &Bpsw), first, "{}", n);
+    }
+    assert_eq!(
+        pset.prime_factors_with(LARGEST_U64_PRIME, &Bpsw),
+        vec![LARGEST_U64_PRIME]
+    );
+    // Trial division stops as soon as the cofactor is prime
+    let mut fresh = Sieve::new();
+    assert_eq!(
+        fresh.prime_factors_with(6 * 1_000_000_000_039, &Bpsw),
+        vec![2, 3, 1_000_000_000_039]
+    );
+    assert!(fresh.len() < 10);
+    assert_eq!(pset.find_with(LARGEST_U64_PRIME + 1, &Bpsw), None);
+    assert_eq!(
+        pset.find_with(3_215_031_750, &WitnessSet::FirstFourPrimes),
+        Some(3_215_031_751)
+    );
+}
+
+#[test]
+fn test_has_small_factor() {
+    for &bound in &[0, 1, 2, 10, 101, 103, 997, 1_000, 1_200] {
+        for n in (0..5_000).chain(1_000_000..1_002_000) {
+            let expected = factors(n).first().copied().filter(|&p| p <= bound);
+            let expected = if n == 0 && bound >= 2 {
+                Some(2)
+            } else {
+                expected
+            };
+            assert_eq!(
+                has_small_factor(n, bound),
+                expected,
+                "{} up to {}",
+                n,
+                bound
+            );
+        }
+    }
+    assert_eq!(has_small_factor(LARGEST_U64_PRIME, 100_000), None);
+    assert_eq!(
+        has_small_factor(4_294_967_291 * 65_521, 70_000),
+        Some(65_521)
+    );
+}
+
+#[test]
+fn test_is_prime_dispatch() {
+    // Across the table, trial division and Miller-Rabin ranges
+    for n in (0..70_000).chain((1 << 20) - 5_000..(1 << 20) + 5_000) {
+        assert_eq!(is_prime(n), n > 1 && factors(n) == vec![n], "{}", n);
+    }
+    for n in (LARGEST_U64_PRIME - 1_000)..=LARGEST_U64_PRIME {
+        assert_eq!(is_prime(n), is_prime_u64(n), "{}", n);
+    }
+}
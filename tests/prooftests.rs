@@ -0,0 +1,83 @@
+use primes::{
+    factors_uniq, is_prime, prove_prime_n_minus_1, prove_prime_n_plus_1, LucasCertificate,
+    PocklingtonCertificate,
+};
+
+#[test]
+fn test_prove_prime_n_minus_1() {
+    for n in 3..20_000 {
+        let certificate = prove_prime_n_minus_1(n, &factors_uniq(n - 1));
+        assert_eq!(certificate.is_some(), is_prime(n), "{}", n);
+        if let Some(certificate) = certificate {
+            assert!(certificate.verify(), "{}", n);
+        }
+    }
+    assert_eq!(prove_prime_n_minus_1(2, &[]), None);
+
+    // n - 1 = 2^2 * 11 * 137 * 547 * 5_594_472_617_641, where 2 and the largest factor alone
+    // cover more than the square root
+    let n = 18_446_744_073_709_551_557;
+    let certificate = prove_prime_n_minus_1(n, &[2, 5_594_472_617_641]).unwrap();
+    assert!(certificate.verify());
+    // Too little of n - 1, and factors that don't divide it
+    assert_eq!(prove_prime_n_minus_1(n, &[2, 11, 137, 547]), None);
+    assert_eq!(prove_prime_n_minus_1(n, &[3, 5_594_472_617_641]), None);
+    // A Carmichael number whose n - 1 = 2^4 * 5 * 7 is fully factored
+    assert_eq!(prove_prime_n_minus_1(561, &[2, 5, 7]), None);
+
+    // Tampered certificates don't verify
+    let good = prove_prime_n_minus_1(97, &[2, 3]).unwrap();
+    let repeated = PocklingtonCertificate {
+        n: 97,
+        witnesses: vec![good.witnesses[0], good.witnesses[0]],
+    };
+    assert!(!repeated.verify());
+    let wrong_base = PocklingtonCertificate {
+        n: 97,
+        witnesses: vec![(2, 1), good.witnesses[1]],
+    };
+    assert!(!wrong_base.verify());
+    let composite = PocklingtonCertificate {
+        n: 561,
+        witnesses: vec![(2, 2), (5, 2), (7, 2)],
+    };
+    assert!(!composite.verify());
+}
+
+#[test]
+fn test_prove_prime_n_plus_1() {
+    for n in 3..20_000 {
+        let certificate = prove_prime_n_plus_1(n, &factors_uniq(n + 1));
+        assert_eq!(certificate.is_some(), is_prime(n), "{}", n);
+        if let Some(certificate) = certificate {
+            assert!(certificate.verify(), "{}", n);
+        }
+    }
+
+    for &e in &[31, 61] {
+        let n = (1u64 << e) - 1;
+        assert!(
+            prove_prime_n_plus_1(n, &[2]).unwrap().verify(),
+            "2^{} - 1",
+            e
+        );
+    }
+    for &e in &[29, 37, 59, 63] {
+        assert_eq!(
+            prove_prime_n_plus_1((1 << e) - 1, &[2]),
+            None,
+            "2^{} - 1",
+            e
+        );
+    }
+    assert_eq!(prove_prime_n_plus_1(u64::MAX, &[2]), None);
+
+    let good = prove_prime_n_plus_1(8_191, &[2]).unwrap();
+    let wrong_discriminant = LucasCertificate {
+        discriminant: 1,
+        ..good.clone()
+    };
+    assert!(!wrong_discriminant.verify());
+    let composite = LucasCertificate { n: 8_189, ..good };
+    assert!(!composite.verify());
+}
@@ -0,0 +1,140 @@
+#[cfg(feature = "rand")]
+use primes::{
+    factors_with, random_blum_integer, random_strong_prime, random_strong_prime_with,
+    sample_primes_in, Bpsw, StagedMillerRabin,
+};
+use primes::{is_blum_prime, is_prime};
+#[cfg(feature = "rand")]
+use rand::{rngs::StdRng, SeedableRng};
+
+// A seeded generator, so that the tests are reproducible
+#[cfg(feature = "rand")]
+fn seeded() -> StdRng {
+    StdRng::seed_from_u64(0x9e37_79b9_7f4a_7c15)
+}
+
+#[cfg(feature = "rand")]
+fn largest_factor(n: u64) -> u64 {
+    *factors_with(n, &Bpsw).last().unwrap()
+}
+
+#[test]
+#[cfg(feature = "rand")]
+fn test_random_strong_prime() {
+    let mut rng = seeded();
+    for bits in 20..=64 {
+        for _ in 0..5 {
+            let p = random_strong_prime(bits, &mut rng);
+            assert!(is_prime(p), "{}", p);
+            assert_eq!(64 - p.leading_zeros(), bits, "{}", p);
+
+            let large = 1 << ((bits - 10) / 2 - 1);
+            // Some large r dividing p - 1 has a large factor of r - 1 in turn
+            let strong = factors_with(p - 1, &Bpsw)
+                .into_iter()
+                .any(|r| r >= large && largest_factor(r - 1) >= large / 2);
+            assert!(strong, "{}", p);
+            assert!(largest_factor(p + 1) >= large, "{}", p);
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "rand")]
+fn test_random_strong_prime_with() {
+    // An exact tester makes the same choices as random_strong_prime
+    let (mut rng, mut rng_with) = (seeded(), seeded());
+    for bits in 20..=64 {
+        assert_eq!(
+            random_strong_prime_with(bits, &mut rng_with, &StagedMillerRabin),
+            random_strong_prime(bits, &mut rng)
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "rand")]
+#[should_panic]
+fn test_random_strong_prime_too_small() {
+    random_strong_prime(19, &mut seeded());
+}
+
+#[test]
+#[cfg(all(feature = "rand", feature = "bigint"))]
+fn test_random_strong_prime_big() {
+    use num_bigint::BigUint;
+    use primes::{is_prime_bpsw_big, random_strong_prime_big};
+
+    let mut rng = seeded();
+    for &bits in &[20, 33, 64, 65, 100, 128, 256] {
+        let p = random_strong_prime_big(bits, &mut rng);
+        assert_eq!(p.bits(), bits, "{}", p);
+        assert!(is_prime_bpsw_big(&p), "{}", p);
+        if bits <= 64 {
+            let p = p.iter_u64_digits().next().unwrap();
+            let large = 1 << ((bits - 10) / 2 - 1);
+            assert!(largest_factor(p - 1) >= large, "{}", p);
+            assert!(largest_factor(p + 1) >= large, "{}", p);
+        }
+    }
+    assert_ne!(
+        random_strong_prime_big(128, &mut rng),
+        random_strong_prime_big(128, &mut rng)
+    );
+    assert!(random_strong_prime_big(512, &mut rng) > BigUint::from(1u32) << 511);
+}
+
+#[test]
+fn test_is_blum_prime() {
+    for n in 0..2_000 {
+        assert_eq!(is_blum_prime(n), is_prime(n) && n % 4 == 3, "{}", n);
+    }
+}
+
+#[test]
+#[cfg(feature = "rand")]
+fn test_random_blum_integer() {
+    let mut rng = seeded();
+    for bits in 5..=64 {
+        for _ in 0..20 {
+            let (n, p, q) = random_blum_integer(bits, &mut rng);
+            assert_eq!(64 - n.leading_zeros(), bits, "{}", n);
+            assert_eq!(n, p * q);
+            assert!(p < q && is_blum_prime(p) && is_blum_prime(q), "{} {}", p, q);
+            assert_eq!(64 - p.leading_zeros(), (bits - 1) / 2);
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "rand")]
+fn test_sample_primes_in() {
+    let mut rng = seeded();
+    let all: Vec<u64> = (100..=200).filter(|&n| is_prime(n)).collect();
+    assert_eq!(
+        sample_primes_in(100, 200, all.len(), &mut rng),
+        Some(all.clone())
+    );
+    assert_eq!(sample_primes_in(100, 200, all.len() + 1, &mut rng), None);
+    assert_eq!(sample_primes_in(200, 100, 0, &mut rng), Some(vec![]));
+
+    // Every prime should be picked about equally often
+    let mut counts = vec![0; all.len()];
+    for _ in 0..2_000 {
+        let sample = sample_primes_in(100, 200, 5, &mut rng).unwrap();
+        assert!(sample.windows(2).all(|w| w[0] < w[1]));
+        for p in sample {
+            counts[all.binary_search(&p).unwrap()] += 1;
+        }
+    }
+    // Each is expected 2_000 * 5 / 21 ≈ 476 times
+    assert!(
+        counts.iter().all(|&c| (350..600).contains(&c)),
+        "{:?}",
+        counts
+    );
+
+    let lo = 1_000_000_000_000;
+    let sample = sample_primes_in(lo, lo + 1_000, 3, &mut rng).unwrap();
+    assert!(sample.iter().all(|&p| is_prime(p) && p >= lo));
+}
@@ -0,0 +1,444 @@
+use primes::{
+    brun_constant_partial, factors_uniq, for_each_prime_in, gap_after, gap_histogram_below,
+    is_prime, iterate_index, large_primes_from, liouville, liouville_range, max_gap_below, mobius,
+    mobius_range, prime_count_in_range, prime_indexed_primes, prime_pi, prime_pi_sublinear,
+    read_primes, read_primes_iter, smallest_prime_factor, spf_range, squarefree_in_range,
+    totient_range, twin_prime_count, verify_goldbach, write_primes, Factorization, PiCheckpoints,
+    PrimeFormat, PrimeSet, ReadPrimesError, SegmentIter, Sieve,
+};
+
+#[test]
+fn test_prime_pi() {
+    let mut sieve = Sieve::new();
+    let primes: Vec<u64> = sieve.iter().take_while(|&p| p < 100_000).collect();
+    for x in (0..100_000)
+        .step_by(97)
+        .chain(vec![0, 1, 2, 3, 99_991, 99_999])
+    {
+        let expected = primes.iter().filter(|&&p| p <= x).count() as u64;
+        assert_eq!(prime_pi(x), expected, "pi({})", x);
+    }
+
+    // Queries going backwards and forwards across checkpoints
+    assert_eq!(prime_pi(10_000_000), 664_579);
+    assert_eq!(prime_pi(1_000_000), 78_498);
+    assert_eq!(prime_pi(2_000_000), 148_933);
+    assert_eq!(prime_pi(1_048_575), 82_025);
+    assert_eq!(prime_pi(1_048_576), 82_025);
+}
+
+#[test]
+fn test_gaps() {
+    let mut sieve = Sieve::new();
+    let primes: Vec<u64> = sieve.iter().take_while(|&p| p < 20_000).collect();
+    for w in primes.windows(2) {
+        assert_eq!(gap_after(w[0]), Some(w[1] - w[0]));
+    }
+    assert_eq!(gap_after(0), Some(2));
+    assert_eq!(gap_after(u64::MAX - 58), None);
+
+    assert_eq!(max_gap_below(3), Some((1, 2)));
+    assert_eq!(max_gap_below(1_000), Some((20, 887)));
+    // Maximal gap of 114 after 492113, the record below a million
+    assert_eq!(max_gap_below(1_000_000), Some((114, 492_113)));
+
+    let mut histogram = std::collections::BTreeMap::new();
+    for w in primes.windows(2) {
+        *histogram.entry(w[1] - w[0]).or_insert(0) += 1;
+    }
+    assert_eq!(gap_histogram_below(20_000), histogram);
+    assert!(gap_histogram_below(2).is_empty());
+    let histogram = gap_histogram_below(1_000_000);
+    assert_eq!(histogram.values().sum::<u64>(), 78_497);
+    assert_eq!(histogram.keys().last(), Some(&114));
+}
+
+#[test]
+fn test_twin_primes() {
+    let mut sieve = Sieve::new();
+    let primes: Vec<u64> = sieve.iter().take_while(|&p| p < 100_000).collect();
+    let twins: Vec<u64> = primes
+        .windows(2)
+        .filter(|w| w[1] - w[0] == 2)
+        .map(|w| w[0])
+        .collect();
+    for &x in &[0, 4, 5, 6, 7, 12, 13, 99_999] {
+        let below: Vec<u64> = twins.iter().copied().filter(|&p| p + 2 <= x).collect();
+        assert_eq!(twin_prime_count(x), below.len() as u64, "{}", x);
+        let sum: f64 = below
+            .iter()
+            .map(|&p| 1.0 / p as f64 + 1.0 / (p + 2) as f64)
+            .sum();
+        assert!((brun_constant_partial(x) - sum).abs() < 1e-12, "{}", x);
+    }
+    assert_eq!(twin_prime_count(10_000_000), 58_980);
+}
+
+#[test]
+fn test_verify_goldbach() {
+    for &(lo, hi) in &[
+        (0, 0),
+        (0, 4),
+        (5, 5),
+        (7, 3),
+        (0, 300_000),
+        (12_345, 67_891),
+    ] {
+        assert_eq!(verify_goldbach(lo..=hi), None, "[{}, {}]", lo, hi);
+    }
+    assert_eq!(verify_goldbach(u64::MAX..=u64::MAX), None);
+    for &lo in &[1 << 32, 1 << 40] {
+        assert_eq!(verify_goldbach(lo..=lo + 200_000), None, "{}", lo);
+    }
+}
+
+#[test]
+fn test_prime_count_in_range() {
+    for x in (0..5_000).chain((1..30).map(|k| k * k)) {
+        assert_eq!(prime_pi_sublinear(x), prime_pi(x), "pi({})", x);
+    }
+    assert_eq!(prime_pi_sublinear(10_000_000_000), 455_052_511);
+
+    for &(lo, hi) in [
+        (0, 0),
+        (0, 100),
+        (2, 2),
+        (4, 4),
+        (100, 50),
+        (90, 96),
+        (97, 97),
+    ]
+    .iter()
+    {
+        let expected = (lo..=hi).filter(|&n| is_prime(n)).count() as u64;
+        assert_eq!(prime_count_in_range(lo, hi), expected, "[{}, {}]", lo, hi);
+    }
+    assert_eq!(prime_count_in_range(1_000, 2_000_000), 148_933 - 168);
+    assert_eq!(prime_count_in_range(1 << 40, (1 << 40) + 100_000), 3_653);
+}
+
+#[test]
+fn test_write_primes() {
+    let mut sieve = Sieve::new();
+    let limit = 300_000;
+    let primes: Vec<u64> = sieve.iter().take_while(|&p| p <= limit).collect();
+
+    let mut lines = Vec::new();
+    let count = write_primes(&mut lines, limit, PrimeFormat::Lines).unwrap();
+    assert_eq!(count, primes.len() as u64);
+    let parsed: Vec<u64> = String::from_utf8(lines)
+        .unwrap()
+        .lines()
+        .map(|l| l.parse().unwrap())
+        .collect();
+    assert_eq!(parsed, primes);
+
+    let mut csv = Vec::new();
+    write_primes(&mut csv, limit, PrimeFormat::Csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.ends_with('\n'));
+    let parsed: Vec<u64> = csv
+        .trim_end()
+        .split(',')
+        .map(|v| v.parse().unwrap())
+        .collect();
+    assert_eq!(parsed, primes);
+
+    let mut bin = Vec::new();
+    write_primes(&mut bin, limit, PrimeFormat::BinaryLe).unwrap();
+    let parsed: Vec<u64> = bin
+        .chunks(8)
+        .map(|c| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(c);
+            u64::from_le_bytes(bytes)
+        })
+        .collect();
+    assert_eq!(parsed, primes);
+
+    let mut empty = Vec::new();
+    assert_eq!(write_primes(&mut empty, 1, PrimeFormat::Csv).unwrap(), 0);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_read_primes() {
+    for &format in &[PrimeFormat::Lines, PrimeFormat::Csv, PrimeFormat::BinaryLe] {
+        let mut out = Vec::new();
+        write_primes(&mut out, 100_000, format).unwrap();
+        let primes = read_primes(&out[..], format, true).unwrap();
+        assert_eq!(primes.len(), 9_592);
+        let mut sieve = Sieve::from_primes(primes);
+        assert_eq!(sieve.get(9_592), 100_003);
+    }
+
+    let lines = read_primes(&b" 2\n\n3, 5\n"[..], PrimeFormat::Lines, false).unwrap();
+    assert_eq!(lines, vec![2, 3, 5]);
+
+    match read_primes(&b"2,x"[..], PrimeFormat::Csv, false) {
+        Err(ReadPrimesError::Parse { index: 1, text }) => assert_eq!(text, "x"),
+        other => panic!("unexpected {:?}", other),
+    }
+    match read_primes(&b"2\n5\n3\n"[..], PrimeFormat::Lines, false) {
+        Err(ReadPrimesError::NotIncreasing { index: 2, value: 3 }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+    match read_primes(&b"2,3,4"[..], PrimeFormat::Csv, false) {
+        Ok(v) => assert_eq!(v, vec![2, 3, 4]),
+        other => panic!("unexpected {:?}", other),
+    }
+    match read_primes(&b"2,3,4"[..], PrimeFormat::Csv, true) {
+        Err(ReadPrimesError::NotPrime { index: 2, value: 4 }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+    match read_primes(
+        &[2, 0, 0, 0, 0, 0, 0, 0, 3][..],
+        PrimeFormat::BinaryLe,
+        false,
+    ) {
+        Err(ReadPrimesError::Truncated { trailing_bytes: 1 }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_read_primes_iter() {
+    for &format in &[PrimeFormat::Lines, PrimeFormat::Csv, PrimeFormat::BinaryLe] {
+        let mut out = Vec::new();
+        write_primes(&mut out, 10_000, format).unwrap();
+        // A tiny buffer, so binary values and text lines arrive in pieces
+        let reader = std::io::BufReader::with_capacity(3, &out[..]);
+        let primes: Vec<u64> = read_primes_iter(reader, format, true)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(primes, read_primes(&out[..], format, true).unwrap());
+        assert_eq!(primes.len(), 1_229);
+    }
+
+    // Everything before an error is still yielded, and nothing after it
+    let mut iter = read_primes_iter(&b"2, 3\n5,,x\n7\n"[..], PrimeFormat::Lines, false);
+    assert_eq!(iter.next().unwrap().unwrap(), 2);
+    assert_eq!(iter.next().unwrap().unwrap(), 3);
+    assert_eq!(iter.next().unwrap().unwrap(), 5);
+    match iter.next() {
+        Some(Err(ReadPrimesError::Parse { index: 3, text })) => assert_eq!(text, "x"),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(iter.next().is_none());
+
+    assert!(read_primes_iter(&b""[..], PrimeFormat::BinaryLe, true)
+        .next()
+        .is_none());
+}
+
+#[test]
+fn test_for_each_prime_in() {
+    let mut sieve = Sieve::new();
+    let (lo, hi) = (65_000, 300_000);
+    let expected: Vec<u64> = sieve
+        .iter()
+        .skip_while(|&p| p < lo)
+        .take_while(|&p| p <= hi)
+        .collect();
+    let mut seen = Vec::new();
+    for_each_prime_in(lo, hi, |p| seen.push(p));
+    assert_eq!(seen, expected);
+
+    let mut count = 0;
+    for_each_prime_in(0, 2, |_| count += 1);
+    for_each_prime_in(20, 10, |_| count += 1);
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn test_segment_iter() {
+    for &(lo, hi) in &[
+        (0, 0),
+        (0, 10),
+        (5, 4),
+        (1_000, 200_000),
+        (1 << 40, (1 << 40) + 70_000),
+    ] {
+        let mut expected = Vec::new();
+        for_each_prime_in(lo, hi, |p| expected.push(p));
+        let mut found = Vec::new();
+        let mut next_start = lo;
+        let mut segments = SegmentIter::new(lo, hi);
+        while let Some((start, flags)) = segments.next_segment() {
+            assert_eq!(start, next_start);
+            assert!(flags.len() <= 1 << 16);
+            next_start = start + flags.len() as u64;
+            found.extend(
+                (start..)
+                    .zip(flags)
+                    .filter(|&(_, &is_prime)| is_prime)
+                    .map(|(n, _)| n),
+            );
+        }
+        assert_eq!(next_start, if lo <= hi { hi + 1 } else { lo });
+        assert_eq!(found, expected, "[{}, {}]", lo, hi);
+    }
+}
+
+#[test]
+fn test_spf_range() {
+    let (lo, hi) = (1_000_000_000_000, 1_000_000_000_000 + 2_000);
+    let mut count = 0;
+    for (n, spf) in spf_range(lo, hi) {
+        assert_eq!(Some(spf), smallest_prime_factor(n), "spf({})", n);
+        count += 1;
+    }
+    assert_eq!(count, hi - lo + 1);
+
+    assert_eq!(spf_range(0, 1).count(), 0);
+    assert_eq!(spf_range(10, 9).count(), 0);
+    assert_eq!(
+        spf_range((1 << 32) - 1, (1 << 32) + 1).collect::<Vec<_>>(),
+        vec![((1 << 32) - 1, 3), (1 << 32, 2), ((1 << 32) + 1, 641)]
+    );
+}
+
+#[test]
+fn test_totient_range() {
+    let totient = |n: u64| {
+        factors_uniq(n)
+            .into_iter()
+            .fold(n, |phi, p| phi / p * (p - 1))
+    };
+    for &(lo, hi) in &[(1, 70_000), (1_000_000_000_000, 1_000_000_000_000 + 2_000)] {
+        let mut count = 0;
+        for (n, phi) in totient_range(lo, hi) {
+            assert_eq!(phi, totient(n), "phi({})", n);
+            count += 1;
+        }
+        assert_eq!(count, hi - lo + 1);
+    }
+    assert_eq!(totient_range(0, 0).count(), 0);
+    assert_eq!(totient_range(0, 1).collect::<Vec<_>>(), vec![(1, 1)]);
+}
+
+#[test]
+fn test_squarefree_in_range() {
+    let is_squarefree =
+        |n: u64| n != 0 && Factorization::of(n).pairs().iter().all(|&(_, e)| e == 1);
+    for &(lo, hi) in &[(0, 10_000), (100_000_000_000_000, 100_000_000_000_300)] {
+        let flags = squarefree_in_range(lo, hi);
+        assert_eq!(flags.len() as u64, hi - lo + 1);
+        for (n, &flag) in (lo..=hi).zip(flags.iter()) {
+            assert_eq!(flag, is_squarefree(n), "{}", n);
+        }
+    }
+    assert!(squarefree_in_range(10, 9).is_empty());
+    // About 6/π² of all numbers are squarefree
+    let count = squarefree_in_range(1, 1_000_000)
+        .iter()
+        .filter(|&&b| b)
+        .count();
+    assert_eq!(count, 607_926);
+}
+
+#[test]
+fn test_large_primes_from() {
+    let mut pset = Sieve::new();
+    let expected: Vec<u64> = pset.iter().take(20_000).collect();
+    let primes: Vec<u64> = large_primes_from(0).take(20_000).collect();
+    assert_eq!(primes, expected);
+    let primes: Vec<u64> = large_primes_from(2).take(10).collect();
+    assert_eq!(primes, expected[..10].to_vec());
+
+    // Across several segments, well past where the segment length starts growing
+    let start = (1 << 36) - 100_000;
+    let primes: Vec<u64> = large_primes_from(start).take(10_000).collect();
+    let expected: Vec<u64> = (start..).filter(|&n| is_prime(n)).take(10_000).collect();
+    assert_eq!(primes, expected);
+
+    assert_eq!(large_primes_from(4_294_967_292).next(), Some(4_294_967_311));
+}
+
+#[test]
+fn test_mobius_and_liouville_range() {
+    let mu = mobius_range(0, 5_000);
+    let lambda = liouville_range(0, 5_000);
+    assert_eq!((mu[0], lambda[0]), (0, 0));
+    for n in 1..=5_000u64 {
+        assert_eq!(mu[n as usize], mobius(n), "{}", n);
+        assert_eq!(lambda[n as usize], liouville(n), "{}", n);
+    }
+
+    // A window far from zero, with large prime cofactors left over after sieving
+    let lo = 1_000_000_000_000;
+    let mu = mobius_range(lo, lo + 2_000);
+    let lambda = liouville_range(lo, lo + 2_000);
+    for (i, n) in (lo..=lo + 2_000).enumerate() {
+        assert_eq!(mu[i], mobius(n), "{}", n);
+        assert_eq!(lambda[i], liouville(n), "{}", n);
+    }
+
+    // A window spanning several segments
+    let lo = 1_000_000 - 70_000;
+    let mu = mobius_range(lo, lo + 140_000);
+    let lambda = liouville_range(lo, lo + 140_000);
+    assert_eq!(mu.len(), 140_001);
+    for (i, n) in (lo..=lo + 140_000).enumerate().step_by(97) {
+        assert_eq!(mu[i], mobius(n), "{}", n);
+        assert_eq!(lambda[i], liouville(n), "{}", n);
+    }
+
+    // The Mertens function M(10^4)
+    assert_eq!(
+        mobius_range(1, 10_000)
+            .iter()
+            .map(|&m| m as i64)
+            .sum::<i64>(),
+        -23
+    );
+    assert!(mobius_range(10, 9).is_empty());
+}
+
+#[test]
+fn test_pi_checkpoints() {
+    let mut sieve = Sieve::new();
+    let primes: Vec<u64> = sieve.iter_to(200_000).collect();
+    for &step in &[1, 7, 1_000, 1 << 16, 1 << 20] {
+        let index = PiCheckpoints::build(200_000, step);
+        for n in (0..=200_000).step_by(997) {
+            let expected = primes.partition_point(|&p| p <= n) as u64;
+            assert_eq!(index.prime_pi(n), Some(expected), "{} {}", step, n);
+        }
+        for (ix, &p) in primes.iter().enumerate().step_by(101) {
+            assert_eq!(index.nth_prime(ix as u64 + 1), Some(p), "{} {}", step, ix);
+            assert_eq!(index.rank(p), Some(ix as u64 + 1));
+            // p + 1 is even, and only 3 = 2 + 1 is prime
+            assert_eq!(index.rank(p + 1), if p == 2 { Some(2) } else { None });
+        }
+        let last = *primes.last().unwrap();
+        assert_eq!(index.nth_prime(primes.len() as u64), Some(last));
+        assert_eq!(index.nth_prime(primes.len() as u64 + 1), None);
+        assert_eq!(index.nth_prime(0), None);
+        assert_eq!(index.prime_pi(200_001), None);
+    }
+}
+
+#[test]
+fn test_iterate_index() {
+    let mut pset = Sieve::new();
+    let counting: Vec<u64> = iterate_index(0).take(100).collect();
+    assert_eq!(counting, (1..=100).collect::<Vec<u64>>());
+    let primes: Vec<u64> = iterate_index(1).take(1_000).collect();
+    assert_eq!(primes, pset.iter().take(1_000).collect::<Vec<u64>>());
+
+    // Each level picks its values out of the primes at the positions the level below gives
+    let mut below = primes;
+    for depth in 2..5 {
+        let level: Vec<u64> = iterate_index(depth).take(50).collect();
+        let expected: Vec<u64> = below[..50]
+            .iter()
+            .map(|&i| pset.get(i as usize - 1))
+            .collect();
+        assert_eq!(level, expected, "depth {}", depth);
+        below = level;
+    }
+
+    assert_eq!(prime_indexed_primes().nth(9_999), Some(1_366_661));
+    assert_eq!(iterate_index(3).nth(999), Some(1_033_079));
+}
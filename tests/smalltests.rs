@@ -0,0 +1,35 @@
+use primes::small::{factors_u32, is_prime_u32};
+use primes::{factors, factors_with, is_prime_u64, Bpsw};
+
+#[test]
+fn test_is_prime_u32() {
+    for n in 0..1 << 20 {
+        assert_eq!(is_prime_u32(n), is_prime_u64(n as u64), "{}", n);
+    }
+    for n in (u32::MAX - 100_000)..=u32::MAX {
+        assert_eq!(is_prime_u32(n), is_prime_u64(n as u64), "{}", n);
+    }
+    // The smallest strong pseudoprimes to the bases 2; 2 and 3; 2, 3 and 5; and 2, 3, 5 and 7
+    for &n in &[2_047, 1_373_653, 25_326_001, 3_215_031_751] {
+        assert!(!is_prime_u32(n), "{}", n);
+    }
+}
+
+#[test]
+fn test_factors_u32() {
+    assert_eq!(factors_u32(0), Vec::<u32>::new());
+    assert_eq!(factors_u32(1), Vec::<u32>::new());
+    for n in 2..100_000u32 {
+        let expected: Vec<u32> = factors(n as u64).into_iter().map(|p| p as u32).collect();
+        assert_eq!(factors_u32(n), expected, "{}", n);
+    }
+    for n in (u32::MAX - 10_000)..=u32::MAX {
+        let expected: Vec<u32> = factors_with(n as u64, &Bpsw)
+            .into_iter()
+            .map(|p| p as u32)
+            .collect();
+        assert_eq!(factors_u32(n), expected, "{}", n);
+    }
+    assert_eq!(factors_u32(65_521 * 65_521), vec![65_521, 65_521]);
+    assert_eq!(factors_u32(1 << 31), vec![2; 31]);
+}
@@ -0,0 +1,159 @@
+use primes::special::{
+    chen_primes, happy_primes, is_chen_prime, is_happy, is_prime_special, is_semiprime,
+    lucas_lehmer_test, lucky_numbers, lucky_primes, pepin_test, polynomial_prime_count, proth_test,
+    SpecialForm,
+};
+use primes::{factors, is_prime};
+
+#[test]
+fn test_semiprimes() {
+    for n in 0..20_000 {
+        assert_eq!(is_semiprime(n), factors(n).len() == 2, "{}", n);
+    }
+    assert!(is_semiprime(4_294_967_291 * 4_294_967_279));
+    assert!(is_semiprime(2 * 9_223_372_036_854_775_783));
+    assert!(!is_semiprime(3 * 5 * 1_000_000_007));
+    assert!(!is_semiprime(18_446_744_073_709_551_557));
+}
+
+#[test]
+fn test_chen_primes() {
+    let expected: Vec<u64> = (0..20_000)
+        .filter(|&p| is_prime(p) && factors(p + 2).len() <= 2)
+        .collect();
+    for p in 0..20_000 {
+        assert_eq!(is_chen_prime(p), expected.contains(&p), "{}", p);
+    }
+    let chen: Vec<u64> = chen_primes().take(expected.len()).collect();
+    assert_eq!(chen, expected);
+    assert!(!is_chen_prime(u64::MAX));
+}
+
+#[test]
+fn test_lucky_numbers() {
+    // OEIS A000959
+    let expected = vec![
+        1, 3, 7, 9, 13, 15, 21, 25, 31, 33, 37, 43, 49, 51, 63, 67, 69, 73, 75, 79, 87, 93, 99,
+        105, 111, 115, 127, 129, 133, 135, 141, 151, 159, 163, 169, 171, 189, 193, 195,
+    ];
+    assert_eq!(lucky_numbers(195), expected);
+    assert_eq!(lucky_numbers(0), Vec::<u64>::new());
+    assert_eq!(lucky_numbers(1), vec![1]);
+    // There are 153 lucky numbers below 1000
+    assert_eq!(lucky_numbers(1_000).len(), 153);
+
+    let primes: Vec<u64> = lucky_primes(195).collect();
+    let expected: Vec<u64> = expected.into_iter().filter(|&n| is_prime(n)).collect();
+    assert_eq!(primes, expected);
+}
+
+#[test]
+fn test_happy_numbers() {
+    // OEIS A007770
+    let happy: Vec<u64> = (0..100).filter(|&n| is_happy(n)).collect();
+    assert_eq!(
+        happy,
+        vec![1, 7, 10, 13, 19, 23, 28, 31, 32, 44, 49, 68, 70, 79, 82, 86, 91, 94, 97]
+    );
+    // OEIS A035497
+    let primes: Vec<u64> = happy_primes().take_while(|&p| p < 200).collect();
+    assert_eq!(
+        primes,
+        vec![7, 13, 19, 23, 31, 79, 97, 103, 109, 139, 167, 193]
+    );
+    assert!(!is_happy(u64::MAX));
+}
+
+#[test]
+fn test_polynomial_prime_count() {
+    fn brute(a: i64, b: i64, c: i64, lo: i64, hi: i64) -> u64 {
+        (lo..=hi)
+            .map(|n| a * n * n + b * n + c)
+            .filter(|&v| v > 1 && is_prime(v as u64))
+            .count() as u64
+    }
+    let polynomials = [
+        (1, 1, 41),
+        (1, -79, 1_601),
+        (2, 0, 29),
+        (0, 6, 1),
+        (0, 0, 7),
+        (0, 0, 8),
+        (1, 0, 1),
+        (-1, 0, 1_000_003),
+    ];
+    for &(a, b, c) in &polynomials {
+        let count = polynomial_prime_count(a, b, c, -1_000..=70_000);
+        assert_eq!(count, brute(a, b, c, -1_000, 70_000), "{} {} {}", a, b, c);
+    }
+
+    // Every value is 2 or 3 for these n, so the sieve alone would rule them all out
+    assert_eq!(polynomial_prime_count(1, 0, 2, 0..=1), 2);
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn test_landau() {
+    use num_bigint::BigUint;
+    use primes::special::landau;
+
+    // OEIS A000793
+    let expected: [u64; 21] = [
+        1, 1, 2, 3, 4, 6, 6, 12, 15, 20, 30, 30, 60, 60, 84, 105, 140, 210, 210, 420, 420,
+    ];
+    for (n, &g) in expected.iter().enumerate() {
+        assert_eq!(landau(n as u64), BigUint::from(g), "{}", n);
+    }
+    assert_eq!(landau(100), BigUint::from(232_792_560u64));
+    let big: BigUint = "435341966072651918280".parse().unwrap();
+    assert_eq!(landau(400), big);
+}
+
+#[test]
+fn test_special_forms() {
+    for n in 0..100_000 {
+        assert_eq!(is_prime_special(n), is_prime(n), "{}", n);
+    }
+    for e in 1..63 {
+        for k in (1..(1u64 << e).min(2_000)).step_by(2) {
+            let n = ((k as u128) << e) + 1;
+            if n <= u64::MAX as u128 {
+                let n = n as u64;
+                assert_ne!(SpecialForm::of(n), SpecialForm::General, "{}", n);
+                assert_eq!(proth_test(k, e), is_prime(n), "{} * 2^{} + 1", k, e);
+            }
+        }
+    }
+    for p in 2..=64 {
+        assert_eq!(
+            SpecialForm::of(u64::MAX >> (64 - p)),
+            SpecialForm::Mersenne(p)
+        );
+        assert_eq!(
+            lucas_lehmer_test(p),
+            is_prime(u64::MAX >> (64 - p)),
+            "2^{} - 1",
+            p
+        );
+    }
+    for m in 0..=5 {
+        let f = (1u64 << (1 << m)) + 1;
+        let expected = if m == 0 {
+            SpecialForm::Mersenne(2)
+        } else {
+            SpecialForm::Fermat(m)
+        };
+        assert_eq!(SpecialForm::of(f), expected);
+        assert_eq!(pepin_test(m), is_prime(f), "F_{}", m);
+    }
+    assert_eq!(SpecialForm::of(2), SpecialForm::General);
+    assert_eq!(SpecialForm::of(1 << 40), SpecialForm::General);
+    assert_eq!(
+        SpecialForm::of(3 * (1 << 20) + 1),
+        SpecialForm::Proth { k: 3, n: 20 }
+    );
+    assert_eq!(
+        SpecialForm::of((1 << 40) + 1),
+        SpecialForm::Proth { k: 1, n: 40 }
+    );
+}
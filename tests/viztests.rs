@@ -0,0 +1,41 @@
+use primes::is_prime;
+use primes::viz::{sacks_spiral, ulam_spiral};
+
+#[test]
+fn test_ulam_spiral() {
+    let points: Vec<(i64, i64, bool)> = ulam_spiral(10_000).collect();
+    assert_eq!(points.len(), 10_000);
+    for (n, &(_, _, flag)) in (1..).zip(points.iter()) {
+        assert_eq!(flag, is_prime(n), "{}", n);
+    }
+
+    // Each point is one step from the last, and no point is visited twice
+    for w in points.windows(2) {
+        assert_eq!((w[0].0 - w[1].0).abs() + (w[0].1 - w[1].1).abs(), 1);
+    }
+    let mut seen: Vec<(i64, i64)> = points.iter().map(|&(x, y, _)| (x, y)).collect();
+    seen.sort_unstable();
+    seen.dedup();
+    assert_eq!(seen.len(), 10_000);
+
+    // The odd squares run down the diagonal to the bottom right
+    assert_eq!(points[9 - 1], (1, -1, false));
+    assert_eq!(points[25 - 1], (2, -2, false));
+    assert_eq!(points[10_000 - 1].0, -49);
+
+    assert_eq!(ulam_spiral(0).count(), 0);
+}
+
+#[test]
+fn test_sacks_spiral() {
+    let points: Vec<(f64, f64, bool)> = sacks_spiral(1_000).collect();
+    assert_eq!(points.len(), 1_000);
+    for (n, &(x, y, flag)) in (1..).zip(points.iter()) {
+        assert_eq!(flag, is_prime(n), "{}", n);
+        assert!(((x * x + y * y) - n as f64).abs() < 1e-6, "{}", n);
+    }
+    for k in 1..30 {
+        let (x, y, _) = points[k * k - 1];
+        assert!((x - k as f64).abs() < 1e-9 && y.abs() < 1e-9, "{}", k * k);
+    }
+}